@@ -0,0 +1,358 @@
+use std::{
+    collections::VecDeque,
+    f32::consts::PI,
+    net::SocketAddrV4,
+    thread,
+    time::{Duration, Instant},
+};
+
+use clap::ValueEnum;
+use rand::{rng, Rng};
+use serde::Serialize;
+
+use crate::{
+    client::{GameStateQueue, Hud, Networker},
+    common::{
+        ClientToServerPackage, Color, Complex, InputButtons, MovementInput, PlayerConnectedPackage,
+        PlayerInputPackage, PlayerState, PlayerWeapon, RespawnRequestPackage, Vector,
+        MOVEMENT_STEP,
+    },
+};
+
+/// Inputs sent by benchmark clients each movement step
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum BenchScript {
+    /// Movement keys, aim and fire button change randomly every second
+    Random,
+    /// Walking in circles while firing
+    Circle,
+    /// Standing still and turning around without firing
+    Idle,
+}
+
+/// Input of one movement step produced by script
+struct ScriptedInput {
+    script: BenchScript,
+    movement: MovementInput,
+    rotation: Complex,
+    buttons: InputButtons,
+    /// Second of benchmark in which random input was chosen
+    chosen_second: Option<u64>,
+}
+
+impl ScriptedInput {
+    fn new(script: BenchScript) -> Self {
+        Self {
+            script,
+            movement: Default::default(),
+            rotation: Complex { r: 1., i: 0. },
+            buttons: Default::default(),
+            chosen_second: None,
+        }
+    }
+
+    fn proceed<R: Rng>(&mut self, elapsed: Duration, rng: &mut R) {
+        // Full turn in three seconds
+        let angle = elapsed.as_secs_f32() * 2. * PI / 3.;
+        match self.script {
+            BenchScript::Random => {
+                let second = elapsed.as_secs();
+                if self.chosen_second != Some(second) {
+                    self.chosen_second = Some(second);
+                    self.movement = MovementInput {
+                        left: rng.random(),
+                        right: rng.random(),
+                        up: rng.random(),
+                        down: rng.random(),
+                    };
+                    self.buttons
+                        .set(InputButtons::ABILITY, rng.random_bool(0.1));
+                    self.rotation = Complex::from_rad(rng.random_range(0. ..2. * PI));
+                    self.buttons
+                        .set(InputButtons::PRIMARY, rng.random_bool(0.5));
+                }
+            }
+            BenchScript::Circle => {
+                self.rotation = Complex::from_rad(angle);
+                self.movement = MovementInput::towards(Vector::polar(self.rotation, 1.));
+                self.buttons.set(InputButtons::PRIMARY, true);
+            }
+            BenchScript::Idle => {
+                self.rotation = Complex::from_rad(angle);
+            }
+        }
+    }
+}
+
+/// Measurements of one benchmark client
+#[derive(Debug, Default)]
+struct ClientReport {
+    snapshots: u64,
+    snapshot_bytes: u64,
+    /// Milliseconds from sending input until broadcast in which server applied it is received
+    latencies: Vec<f32>,
+    /// Time client was connected
+    duration: Duration,
+    /// Reason if client was disconnected before the end of benchmark
+    disconnect: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+struct LatencySummary {
+    mean: f32,
+    p50: f32,
+    p95: f32,
+    max: f32,
+}
+
+impl LatencySummary {
+    fn new(latencies: &[f32]) -> Option<Self> {
+        if latencies.is_empty() {
+            return None;
+        }
+        let mut sorted = latencies.to_vec();
+        sorted.sort_by(f32::total_cmp);
+        let percentile = |q: f32| sorted[((sorted.len() - 1) as f32 * q).round() as usize];
+        Some(Self {
+            mean: sorted.iter().sum::<f32>() / sorted.len() as f32,
+            p50: percentile(0.5),
+            p95: percentile(0.95),
+            max: *sorted.last().unwrap(),
+        })
+    }
+}
+
+/// Summary of all clients. Printed as JSON to compare results across commits
+#[derive(Debug, Serialize)]
+struct BenchReport {
+    clients: usize,
+    duration_secs: f32,
+    /// Broadcasts per second received by one client
+    snapshot_rate: f32,
+    mean_snapshot_bytes: f32,
+    latency_ms: Option<LatencySummary>,
+    disconnects: usize,
+}
+
+impl BenchReport {
+    fn new(reports: &[ClientReport], duration: Duration) -> Self {
+        let snapshots: u64 = reports.iter().map(|x| x.snapshots).sum();
+        let bytes: u64 = reports.iter().map(|x| x.snapshot_bytes).sum();
+        let connected_secs: f32 = reports.iter().map(|x| x.duration.as_secs_f32()).sum();
+        let latencies: Vec<f32> = reports.iter().flat_map(|x| x.latencies.clone()).collect();
+        Self {
+            clients: reports.len(),
+            duration_secs: duration.as_secs_f32(),
+            snapshot_rate: snapshots as f32 / connected_secs.max(f32::EPSILON),
+            mean_snapshot_bytes: bytes as f32 / snapshots.max(1) as f32,
+            latency_ms: LatencySummary::new(&latencies),
+            disconnects: reports.iter().filter(|x| x.disconnect.is_some()).count(),
+        }
+    }
+}
+
+/// Connects `clients` headless players to server, sends scripted input for `duration` and
+/// prints measurements as a table or as JSON
+pub fn exec_bench_clients(
+    addr: SocketAddrV4,
+    clients: usize,
+    duration: Duration,
+    script: BenchScript,
+    json: bool,
+) {
+    let handles: Vec<_> = (0..clients)
+        .map(|i| thread::spawn(move || run_bench_client(addr, i, duration, script)))
+        .collect();
+    let reports: Vec<ClientReport> = handles.into_iter().map(|x| x.join().unwrap()).collect();
+    let report = BenchReport::new(&reports, duration);
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&report).unwrap());
+        return;
+    }
+
+    println!("  client  snapshots/s        bytes     p50 ms     p95 ms  status");
+    let row =
+        |name: String, rate: f32, bytes: f32, latency: Option<LatencySummary>, status: String| {
+            let (p50, p95) = latency.map_or(("-".to_string(), "-".to_string()), |x| {
+                (format!("{:.1}", x.p50), format!("{:.1}", x.p95))
+            });
+            println!(
+                "{:>8} {:>12.1} {:>12.0} {:>10} {:>10}  {}",
+                name, rate, bytes, p50, p95, status
+            );
+        };
+    for (i, x) in reports.iter().enumerate() {
+        row(
+            (i + 1).to_string(),
+            x.snapshots as f32 / x.duration.as_secs_f32().max(f32::EPSILON),
+            x.snapshot_bytes as f32 / x.snapshots.max(1) as f32,
+            LatencySummary::new(&x.latencies),
+            x.disconnect
+                .as_ref()
+                .map_or("ok".to_string(), |x| format!("disconnected: {}", x)),
+        );
+    }
+    row(
+        "all".to_string(),
+        report.snapshot_rate,
+        report.mean_snapshot_bytes,
+        report.latency_ms,
+        format!("{} disconnects", report.disconnects),
+    );
+}
+
+fn run_bench_client(
+    addr: SocketAddrV4,
+    index: usize,
+    duration: Duration,
+    script: BenchScript,
+) -> ClientReport {
+    let mut report: ClientReport = Default::default();
+    let join_request = PlayerConnectedPackage {
+        name: format!("Bench {}", index + 1),
+        color: Color {
+            a: 255,
+            r: 128,
+            g: 128,
+            b: 128,
+        },
+        team: None,
+    };
+    let start = Instant::now();
+    let mut networker = match Networker::connect(addr, join_request) {
+        Ok(networker) => networker,
+        Err(err) => {
+            report.disconnect = Some(err.to_string());
+            return report;
+        }
+    };
+    let mut game_state_queue = GameStateQueue::new();
+    let mut player_state: PlayerState = Default::default();
+    let mut hud = Hud::new();
+    let mut rng = rng();
+    let mut input = ScriptedInput::new(script);
+    let mut last_sequence_number: u32 = 0;
+    // Sent inputs which are not applied by server yet
+    let mut pending: VecDeque<(u32, Instant)> = Default::default();
+    let mut last_step_instant = start;
+
+    while start.elapsed() < duration {
+        if let Err(err) = networker.proceed(
+            &mut game_state_queue,
+            &mut player_state,
+            &mut hud,
+            last_sequence_number,
+        ) {
+            report.disconnect = Some(err.to_string());
+            break;
+        }
+        if let Some(reason) = networker.join_rejection.take() {
+            report.disconnect = Some(reason);
+            break;
+        }
+
+        let now = Instant::now();
+        while pending
+            .front()
+            .is_some_and(|(n, _)| *n <= networker.stats.acknowledged_sequence_number)
+        {
+            let (_, instant) = pending.pop_front().unwrap();
+            report.latencies.push((now - instant).as_secs_f32() * 1000.);
+        }
+
+        if networker.player_id.is_some() && now - last_step_instant >= MOVEMENT_STEP {
+            last_step_instant = now;
+            let result = if player_state.killed {
+                player_state.killed = false;
+                // Inputs sent while dead are never applied
+                pending.clear();
+                networker.write_package(ClientToServerPackage::RespawnRequest(
+                    RespawnRequestPackage {
+                        loadout: vec![PlayerWeapon::BallGun],
+                    },
+                ))
+            } else {
+                input.proceed(now - start, &mut rng);
+                last_sequence_number += 1;
+                pending.push_back((last_sequence_number, now));
+                networker.write_package(ClientToServerPackage::PlayerInput(PlayerInputPackage {
+                    sequence_number: last_sequence_number,
+                    movement: input.movement,
+                    rotation: input.rotation,
+                    buttons: input.buttons,
+                }))
+            };
+            if let Err(err) = result {
+                report.disconnect = Some(err.to_string());
+                break;
+            }
+        }
+
+        thread::sleep(Duration::from_millis(1));
+    }
+
+    report.snapshots = networker.stats.broadcasts;
+    report.snapshot_bytes = networker.stats.broadcast_bytes;
+    report.duration = start.elapsed();
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{BenchReport, ClientReport, LatencySummary};
+    use std::time::Duration;
+
+    #[test]
+    fn latency_summary() {
+        assert!(LatencySummary::new(&[]).is_none());
+
+        let summary = LatencySummary::new(&[4., 1., 3., 2., 5.]).unwrap();
+        assert_eq!(summary.mean, 3.);
+        assert_eq!(summary.p50, 3.);
+        assert_eq!(summary.p95, 5.);
+        assert_eq!(summary.max, 5.);
+
+        let latencies: Vec<f32> = (1..=100).rev().map(|x| x as f32).collect();
+        let summary = LatencySummary::new(&latencies).unwrap();
+        assert_eq!(summary.mean, 50.5);
+        assert_eq!(summary.p50, 51.);
+        assert_eq!(summary.p95, 95.);
+        assert_eq!(summary.max, 100.);
+    }
+
+    #[test]
+    fn bench_report() {
+        let reports = [
+            ClientReport {
+                snapshots: 30,
+                snapshot_bytes: 3000,
+                latencies: vec![10., 20.],
+                duration: Duration::from_secs(1),
+                disconnect: None,
+            },
+            ClientReport {
+                snapshots: 10,
+                snapshot_bytes: 2000,
+                latencies: vec![],
+                duration: Duration::from_secs(1),
+                disconnect: Some("Connection reset".to_string()),
+            },
+        ];
+        let report = BenchReport::new(&reports, Duration::from_secs(2));
+        assert_eq!(report.clients, 2);
+        assert_eq!(report.duration_secs, 2.);
+        assert_eq!(report.snapshot_rate, 20.);
+        assert_eq!(report.mean_snapshot_bytes, 125.);
+        assert_eq!(report.disconnects, 1);
+        let latency = report.latency_ms.unwrap();
+        assert_eq!(latency.mean, 15.);
+        assert_eq!(latency.max, 20.);
+
+        // Clients which never connected do not divide by zero
+        let report = BenchReport::new(&[Default::default()], Duration::from_secs(2));
+        assert_eq!(report.snapshot_rate, 0.);
+        assert_eq!(report.mean_snapshot_bytes, 0.);
+        assert!(report.latency_ms.is_none());
+    }
+}
@@ -1,158 +1,19 @@
 use std::{
-    cell::RefCell,
-    net::{SocketAddrV4, TcpStream},
-    num::NonZero,
-    ops::Deref,
+    net::SocketAddrV4,
     time::{Duration, Instant},
 };
 
-use rand::{rng, Rng};
-use sdl2::{event::Event, keyboard::Keycode, mouse::MouseButton};
+use sdl2::{event::Event, keyboard::Keycode, mouse::MouseButton, EventPump};
 
 use crate::{
-    client::RenderModel,
+    client::{GameStateQueue, Hud, JoinScreen, Networker, RenderModel},
     common::{
-        ClientToServerPackage, Collide as _, Color, GameState, PacketReader, PacketWriter,
-        PlayerConnectedPackage, PlayerInputPackage, PlayerState, PlayerWeapon, Point,
-        RespawnRequestPackage, Segments as _, ServerToClientPackage, Vector,
+        ClientToServerPackage, InputButtons, MovementInput, PlayerConnectedPackage,
+        PlayerInputPackage, PlayerState, PlayerWeapon, Point, RespawnRequestPackage,
+        SwitchWeaponPackage, MOVEMENT_STEP,
     },
 };
 
-pub(crate) struct GameStateQueue {
-    pub(crate) prediction: GameState,
-    pub(crate) last_received: GameState,
-    pub(crate) penultimate_received: GameState,
-}
-
-impl GameStateQueue {
-    pub(crate) fn new() -> Self {
-        Self {
-            prediction: GameState::new(),
-            last_received: GameState::new(),
-            penultimate_received: GameState::new(),
-        }
-    }
-}
-
-struct Networker {
-    stream: RefCell<TcpStream>,
-    reader: RefCell<PacketReader>,
-    player_id: Option<NonZero<u64>>,
-    last_broadcast_instant: Instant,
-    last_broadcast_insterval: Duration,
-}
-
-impl Networker {
-    pub fn connect(addr: SocketAddrV4) -> std::io::Result<Networker> {
-        let stream = TcpStream::connect(addr)?;
-        stream.set_nonblocking(true)?;
-        Ok(Networker {
-            stream: RefCell::new(stream),
-            reader: Default::default(),
-            player_id: None,
-            last_broadcast_instant: Instant::now(),
-            last_broadcast_insterval: Duration::from_millis(0),
-        })
-    }
-
-    fn interpolation_value(&self) -> f64 {
-        let now = Instant::now();
-        (now - self.last_broadcast_instant).div_duration_f64(self.last_broadcast_insterval)
-    }
-
-    pub fn write_package(&self, p: ClientToServerPackage) -> std::io::Result<()> {
-        PacketWriter::write(
-            &mut self.stream.borrow_mut().deref(),
-            &serde_json::to_vec(&p).unwrap(),
-        )
-    }
-
-    pub fn proceed(
-        &mut self,
-        game_state_queue: &mut GameStateQueue,
-        player_state: &mut PlayerState,
-        last_sequence_number: u32,
-    ) -> std::io::Result<()> {
-        for data in self
-            .reader
-            .borrow_mut()
-            .read_ref(self.stream.borrow_mut())?
-        {
-            let package: ServerToClientPackage = serde_json::from_slice(&data).unwrap();
-
-            let mut rng = rng();
-
-            match package {
-                ServerToClientPackage::Init(init_package) => {
-                    self.player_id = Some(init_package.player_id);
-                    self.write_package(ClientToServerPackage::PlayerConnected(
-                        PlayerConnectedPackage {
-                            color: Color {
-                                a: rng.random(),
-                                r: rng.random(),
-                                g: rng.random(),
-                                b: rng.random(),
-                            },
-                        },
-                    ))
-                    .unwrap();
-                }
-                ServerToClientPackage::Broadcast(broadcast_package) => {
-                    let player_entity_copy = self
-                        .player_id
-                        .map(|id| {
-                            game_state_queue
-                                .prediction
-                                .find_character_by_player_id_mut(id)
-                        })
-                        .flatten()
-                        .map(|x| x.clone());
-
-                    game_state_queue.penultimate_received = game_state_queue.last_received.clone();
-                    game_state_queue.last_received = broadcast_package.game_state.clone();
-                    game_state_queue.prediction = broadcast_package.game_state;
-
-                    if broadcast_package.sequence_number < last_sequence_number
-                        && !player_state.killed
-                    {
-                        if let (Some(player_id), Some(player_entity_copy)) =
-                            (self.player_id, player_entity_copy)
-                        {
-                            if let Some(mut entity) = game_state_queue
-                                .prediction
-                                .find_character_by_player_id_mut(player_id)
-                            {
-                                entity.pos = player_entity_copy.pos;
-                                entity.rot = player_entity_copy.rot;
-                            }
-                        }
-                    }
-
-                    let now = Instant::now();
-                    self.last_broadcast_insterval = now - self.last_broadcast_instant;
-                    self.last_broadcast_instant = now;
-                }
-                ServerToClientPackage::Kill(_) => {
-                    println!("Kill package received");
-                    player_state.killed = true
-                }
-            }
-        }
-
-        if let Some(player_id) = self.player_id {
-            GameState::lerp_merge(
-                &mut game_state_queue.prediction,
-                &game_state_queue.penultimate_received,
-                &game_state_queue.last_received,
-                self.interpolation_value(),
-                player_id,
-            );
-        }
-
-        Ok(())
-    }
-}
-
 struct Controlls {
     left_pressed: bool,
     right_pressed: bool,
@@ -160,8 +21,8 @@ struct Controlls {
     down_pressed: bool,
     space_pressed: bool,
     mouse_pos: Point,
-    left_mouse_pressed: bool,
-    old_left_mouse_pressed: bool,
+    buttons: InputButtons,
+    old_buttons: InputButtons,
 }
 
 impl Controlls {
@@ -173,26 +34,85 @@ impl Controlls {
             down_pressed: false,
             space_pressed: false,
             mouse_pos: Point { x: 0., y: 0. },
-            left_mouse_pressed: false,
-            old_left_mouse_pressed: false,
+            buttons: Default::default(),
+            old_buttons: Default::default(),
         }
     }
 }
 
-pub(crate) fn exec_client(addr: SocketAddrV4) -> Result<(), String> {
+/// Loadout slot selected by number key
+fn loadout_slot_key(keycode: Keycode) -> Option<usize> {
+    [
+        Keycode::Num1,
+        Keycode::Num2,
+        Keycode::Num3,
+        Keycode::Num4,
+        Keycode::Num5,
+        Keycode::Num6,
+        Keycode::Num7,
+        Keycode::Num8,
+        Keycode::Num9,
+    ]
+    .iter()
+    .position(|x| *x == keycode)
+}
+
+/// Puts weapon in loadout slot. Swaps slots if weapon is already in loadout
+fn assign_loadout_slot(loadout: &mut Vec<PlayerWeapon>, slot: usize, weapon: PlayerWeapon) {
+    let slot = slot.min(loadout.len());
+    match loadout.iter().position(|x| *x == weapon) {
+        Some(i) if slot < loadout.len() => loadout.swap(i, slot),
+        Some(_) => {}
+        None if slot < loadout.len() => loadout[slot] = weapon,
+        None => loadout.push(weapon),
+    }
+}
+
+pub fn exec_client(addr: SocketAddrV4, name: Option<String>) -> Result<(), String> {
+    let sdl_context = sdl2::init()?;
+    let mut event_pump = sdl_context.event_pump()?;
+    let mut render_model = RenderModel::new(sdl_context)?;
+    let mut join_screen = JoinScreen::new(name.unwrap_or_default());
+
+    while let Some(join_request) = join_screen.exec(&mut event_pump, &mut render_model) {
+        match play(addr, join_request, &mut event_pump, &mut render_model)? {
+            Some(reason) => join_screen.error = Some(reason),
+            None => break,
+        }
+    }
+
+    Ok(())
+}
+
+/// Runs game until window is closed. Returns reason if player has to go back to join screen
+fn play(
+    addr: SocketAddrV4,
+    join_request: PlayerConnectedPackage,
+    event_pump: &mut EventPump,
+    render_model: &mut RenderModel,
+) -> Result<Option<String>, String> {
     println!("Running client. Connecting to {}", addr);
 
     let mut game_state_queue = GameStateQueue::new();
     let mut controlls = Controlls::new();
     let mut last_sequence_number: u32 = 0;
-    let mut networker = Networker::connect(addr).unwrap();
-    let sdl_context = sdl2::init()?;
-    let mut event_pump = sdl_context.event_pump()?;
-    let mut render_model = RenderModel::new(sdl_context)?;
+    let mut networker = match Networker::connect(addr, join_request) {
+        Ok(networker) => networker,
+        Err(err) => return Ok(Some(format!("Failed to connect: {}", err))),
+    };
     let mut player_state: PlayerState = Default::default();
+    let mut hud = Hud::new();
     let mut weapon = PlayerWeapon::BallGun;
+    let mut loadout = vec![weapon];
+    let mut last_frame_instant = Instant::now();
+    // Time not yet covered by prediction movement steps
+    let mut movement_remaining = Duration::ZERO;
 
     'running: loop {
+        let now = Instant::now();
+        let frame_duration = now - last_frame_instant;
+        last_frame_instant = now;
+
         for event in event_pump.poll_iter() {
             match event {
                 Event::Quit { .. }
@@ -220,7 +140,8 @@ pub(crate) fn exec_client(addr: SocketAddrV4) -> Result<(), String> {
                 } => {
                     controlls.left_pressed = false;
                     if player_state.killed {
-                        weapon = weapon.rotated_left()
+                        weapon = weapon.rotated_left();
+                        assign_loadout_slot(&mut loadout, 0, weapon);
                     }
                 }
 
@@ -243,7 +164,8 @@ pub(crate) fn exec_client(addr: SocketAddrV4) -> Result<(), String> {
                 } => {
                     controlls.right_pressed = false;
                     if player_state.killed {
-                        weapon = weapon.rotated_right()
+                        weapon = weapon.rotated_right();
+                        assign_loadout_slot(&mut loadout, 0, weapon);
                     }
                 }
 
@@ -256,6 +178,24 @@ pub(crate) fn exec_client(addr: SocketAddrV4) -> Result<(), String> {
                     ..
                 } => controlls.space_pressed = false,
 
+                Event::KeyDown {
+                    keycode: Some(Keycode::E | Keycode::LShift),
+                    ..
+                } => controlls.buttons.set(InputButtons::ABILITY, true),
+                Event::KeyUp {
+                    keycode: Some(Keycode::E | Keycode::LShift),
+                    ..
+                } => controlls.buttons.set(InputButtons::ABILITY, false),
+
+                Event::KeyDown {
+                    keycode: Some(Keycode::Tab),
+                    ..
+                } => hud.show_scoreboard = true,
+                Event::KeyUp {
+                    keycode: Some(Keycode::Tab),
+                    ..
+                } => hud.show_scoreboard = false,
+
                 Event::MouseMotion { x, y, .. } => {
                     controlls.mouse_pos = Point {
                         x: x as f32,
@@ -265,11 +205,77 @@ pub(crate) fn exec_client(addr: SocketAddrV4) -> Result<(), String> {
                 Event::MouseButtonDown {
                     mouse_btn: MouseButton::Left,
                     ..
-                } => controlls.left_mouse_pressed = true,
+                } => controlls.buttons.set(InputButtons::PRIMARY, true),
                 Event::MouseButtonUp {
                     mouse_btn: MouseButton::Left,
                     ..
-                } => controlls.left_mouse_pressed = false,
+                } => controlls.buttons.set(InputButtons::PRIMARY, false),
+                Event::MouseButtonDown {
+                    mouse_btn: MouseButton::Right,
+                    ..
+                } => controlls.buttons.set(InputButtons::SECONDARY, true),
+                Event::MouseButtonUp {
+                    mouse_btn: MouseButton::Right,
+                    ..
+                } => controlls.buttons.set(InputButtons::SECONDARY, false),
+
+                Event::KeyDown {
+                    keycode: Some(Keycode::R),
+                    ..
+                } => controlls.buttons.set(InputButtons::RELOAD, true),
+                Event::KeyUp {
+                    keycode: Some(Keycode::R),
+                    ..
+                } => controlls.buttons.set(InputButtons::RELOAD, false),
+
+                Event::KeyDown {
+                    keycode: Some(keycode),
+                    ..
+                } => {
+                    if let Some(slot) = loadout_slot_key(keycode) {
+                        if player_state.killed {
+                            let size = hud
+                                .received_config
+                                .config
+                                .as_ref()
+                                .map_or(1, |x| x.loadout.size);
+                            if slot < size {
+                                assign_loadout_slot(&mut loadout, slot, weapon);
+                            }
+                        } else if slot < player_state.loadout.len() {
+                            networker
+                                .write_package(ClientToServerPackage::SwitchWeapon(
+                                    SwitchWeaponPackage { slot },
+                                ))
+                                .unwrap();
+                        }
+                    }
+                }
+
+                Event::MouseWheel { y, .. } if y != 0 && !player_state.killed => {
+                    let len = player_state.loadout.len();
+                    let active = networker.player_id.and_then(|player_id| {
+                        let character = game_state_queue
+                            .prediction
+                            .find_character_by_player_id_mut(player_id)?;
+                        player_state
+                            .loadout
+                            .iter()
+                            .position(|x| *x == character.player_weapon)
+                    });
+                    if let Some(active) = active.filter(|_| len > 1) {
+                        let slot = if y > 0 {
+                            (active + 1) % len
+                        } else {
+                            (active + len - 1) % len
+                        };
+                        networker
+                            .write_package(ClientToServerPackage::SwitchWeapon(
+                                SwitchWeaponPackage { slot },
+                            ))
+                            .unwrap();
+                    }
+                }
 
                 _ => {}
             }
@@ -281,46 +287,40 @@ pub(crate) fn exec_client(addr: SocketAddrV4) -> Result<(), String> {
                     player_state.killed = false;
                     networker
                         .write_package(ClientToServerPackage::RespawnRequest(
-                            RespawnRequestPackage { weapon },
+                            RespawnRequestPackage {
+                                loadout: loadout.clone(),
+                            },
                         ))
                         .unwrap();
                 }
             } else {
-                let velocity = 5.;
-
-                let mut movement = Vector { x: 0., y: 0. };
-
-                if controlls.left_pressed {
-                    movement.x = -velocity;
-                } else if controlls.right_pressed {
-                    movement.x = velocity;
-                }
-                if controlls.up_pressed {
-                    movement.y = -velocity;
-                } else if controlls.down_pressed {
-                    movement.y = velocity;
-                }
-
-                if let Some(mut entity) = game_state_queue
-                    .prediction
-                    .find_character_by_player_id_mut(player_id)
-                {
-                    entity.pos.x = entity.pos.x + movement.x;
-                    entity.pos.y = entity.pos.y + movement.y;
+                let movement = MovementInput {
+                    left: controlls.left_pressed,
+                    right: controlls.right_pressed,
+                    up: controlls.up_pressed,
+                    down: controlls.down_pressed,
+                };
+
+                if let (Some(config), Some(mut entity)) = (
+                    &hud.received_config.config,
+                    game_state_queue
+                        .prediction
+                        .find_character_by_player_id_mut(player_id),
+                ) {
+                    let moving = !movement.is_empty() || !entity.movement.idle();
+                    entity.movement_input = movement;
+                    entity.dash_held = controlls.buttons.contains(InputButtons::ABILITY);
+                    movement_remaining += frame_duration;
+                    while movement_remaining >= MOVEMENT_STEP {
+                        movement_remaining -= MOVEMENT_STEP;
+                        game_state_queue
+                            .prediction
+                            .step_character(&mut entity, &config.movement);
+                    }
                     let old_rot = entity.rot;
                     entity.rot = (controlls.mouse_pos - entity.pos).normalize_into_complex();
 
-                    for bound in game_state_queue.prediction.world_bounds().edges() {
-                        if let Some(exit_vec) = entity.vertices().segments_ringe().collide(&[bound])
-                        {
-                            entity.pos += exit_vec;
-                        }
-                    }
-
-                    if movement.x != 0.
-                        || movement.y != 0.
-                        || entity.rot != old_rot
-                        || controlls.old_left_mouse_pressed != controlls.left_mouse_pressed
+                    if moving || entity.rot != old_rot || controlls.old_buttons != controlls.buttons
                     {
                         last_sequence_number += 1;
                         networker
@@ -328,12 +328,12 @@ pub(crate) fn exec_client(addr: SocketAddrV4) -> Result<(), String> {
                                 sequence_number: last_sequence_number,
                                 movement,
                                 rotation: entity.rot,
-                                left_mouse_pressed: controlls.left_mouse_pressed,
+                                buttons: controlls.buttons,
                             }))
                             .unwrap();
                     }
 
-                    controlls.old_left_mouse_pressed = controlls.left_mouse_pressed
+                    controlls.old_buttons = controlls.buttons
                 }
             }
         }
@@ -342,15 +342,22 @@ pub(crate) fn exec_client(addr: SocketAddrV4) -> Result<(), String> {
             .proceed(
                 &mut game_state_queue,
                 &mut player_state,
+                &mut hud,
                 last_sequence_number,
             )
             .unwrap();
 
+        if let Some(reason) = networker.join_rejection.take() {
+            return Ok(Some(reason));
+        }
+
         if let Some(player_id) = networker.player_id {
             render_model.render(
                 &game_state_queue.prediction,
                 &player_state,
+                &hud,
                 weapon,
+                &loadout,
                 player_id,
             );
         }
@@ -358,5 +365,5 @@ pub(crate) fn exec_client(addr: SocketAddrV4) -> Result<(), String> {
         ::std::thread::sleep(Duration::new(0, 1_000_000_000u32 / 60));
     }
 
-    Ok(())
+    Ok(None)
 }
@@ -0,0 +1,201 @@
+use std::time::Duration;
+
+use rand::{rng, Rng};
+use sdl2::{event::Event, keyboard::Keycode, mouse::MouseButton, EventPump};
+
+use crate::{
+    client::RenderModel,
+    common::{Color, PlayerConnectedPackage, PlayerRegistry, Point, Rect, Team},
+};
+
+/// Name entry and color picker shown before connecting to server
+pub(crate) struct JoinScreen {
+    pub(crate) name: String,
+    pub(crate) color_index: usize,
+    /// Preferred team. Server picks one if not set
+    pub(crate) team: Option<Team>,
+    pub(crate) error: Option<String>,
+}
+
+impl JoinScreen {
+    pub(crate) const PALETTE: [Color; 8] = [
+        Color {
+            a: 255,
+            r: 255,
+            g: 255,
+            b: 255,
+        },
+        Color {
+            a: 255,
+            r: 0,
+            g: 0,
+            b: 0,
+        },
+        Color {
+            a: 255,
+            r: 0,
+            g: 128,
+            b: 255,
+        },
+        Color {
+            a: 255,
+            r: 0,
+            g: 200,
+            b: 0,
+        },
+        Color {
+            a: 255,
+            r: 255,
+            g: 160,
+            b: 0,
+        },
+        Color {
+            a: 255,
+            r: 160,
+            g: 0,
+            b: 255,
+        },
+        Color {
+            a: 255,
+            r: 0,
+            g: 255,
+            b: 255,
+        },
+        Color {
+            a: 255,
+            r: 255,
+            g: 0,
+            b: 160,
+        },
+    ];
+
+    pub(crate) fn new(name: String) -> Self {
+        Self {
+            name,
+            color_index: rng().random_range(0..Self::PALETTE.len()),
+            team: None,
+            error: None,
+        }
+    }
+
+    pub(crate) fn color(&self) -> Color {
+        Self::PALETTE[self.color_index].clone()
+    }
+
+    /// Area of palette color swatch which can be clicked
+    pub(crate) fn swatch_rect(index: usize) -> Rect {
+        let size = 32.;
+        let spacing = 16.;
+        let width = Self::PALETTE.len() as f32 * (size + spacing) - spacing;
+        Rect {
+            x: 400. - width / 2. + index as f32 * (size + spacing),
+            y: 340.,
+            w: size,
+            h: size,
+        }
+    }
+
+    fn switch_team(&mut self) {
+        self.team = match self.team {
+            None => Some(Team::Red),
+            Some(Team::Red) => Some(Team::Blue),
+            Some(Team::Blue) => None,
+        };
+    }
+
+    fn confirm(&mut self) -> Option<PlayerConnectedPackage> {
+        match PlayerRegistry::validate_name_format(&self.name) {
+            Ok(()) => Some(PlayerConnectedPackage {
+                name: self.name.clone(),
+                color: self.color(),
+                team: self.team,
+            }),
+            Err(err) => {
+                self.error = Some(err);
+                None
+            }
+        }
+    }
+
+    /// Shows join screen until player confirms it. Returns `None` if window is closed
+    pub(crate) fn exec(
+        &mut self,
+        event_pump: &mut EventPump,
+        render_model: &mut RenderModel,
+    ) -> Option<PlayerConnectedPackage> {
+        loop {
+            for event in event_pump.poll_iter() {
+                match event {
+                    Event::Quit { .. }
+                    | Event::KeyDown {
+                        keycode: Some(Keycode::Escape),
+                        ..
+                    } => return None,
+
+                    Event::TextInput { text, .. } => {
+                        for c in text.chars().filter(|c| !c.is_control()) {
+                            if self.name.chars().count() < PlayerRegistry::MAX_NAME_LEN {
+                                self.name.push(c);
+                            }
+                        }
+                    }
+                    Event::KeyDown {
+                        keycode: Some(Keycode::Backspace),
+                        ..
+                    } => {
+                        self.name.pop();
+                    }
+                    Event::KeyDown {
+                        keycode: Some(Keycode::Return | Keycode::KpEnter),
+                        ..
+                    } => {
+                        if let Some(package) = self.confirm() {
+                            return Some(package);
+                        }
+                    }
+
+                    Event::KeyDown {
+                        keycode: Some(Keycode::Left),
+                        ..
+                    } => {
+                        self.color_index =
+                            (self.color_index + Self::PALETTE.len() - 1) % Self::PALETTE.len()
+                    }
+                    Event::KeyDown {
+                        keycode: Some(Keycode::Right),
+                        ..
+                    } => self.color_index = (self.color_index + 1) % Self::PALETTE.len(),
+
+                    Event::KeyDown {
+                        keycode: Some(Keycode::Up | Keycode::Down),
+                        ..
+                    } => self.switch_team(),
+
+                    Event::MouseButtonDown {
+                        mouse_btn: MouseButton::Left,
+                        x,
+                        y,
+                        ..
+                    } => {
+                        let p = Point {
+                            x: x as f32,
+                            y: y as f32,
+                        };
+                        if let Some(index) = (0..Self::PALETTE.len()).find(|i| {
+                            let r = Self::swatch_rect(*i);
+                            p.x >= r.x && p.x < r.x + r.w && p.y >= r.y && p.y < r.y + r.h
+                        }) {
+                            self.color_index = index;
+                        }
+                    }
+
+                    _ => {}
+                }
+            }
+
+            render_model.render_join_screen(self);
+
+            ::std::thread::sleep(Duration::new(0, 1_000_000_000u32 / 60));
+        }
+    }
+}
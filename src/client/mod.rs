@@ -1,4 +1,16 @@
+mod networker;
+pub use networker::*;
+#[cfg(feature = "sdl")]
 mod client;
-pub(crate) use client::*;
+#[cfg(feature = "sdl")]
+pub use client::*;
+#[cfg(feature = "sdl")]
 mod render_model;
+#[cfg(feature = "sdl")]
 pub(crate) use render_model::*;
+#[cfg(feature = "sdl")]
+mod join_screen;
+#[cfg(feature = "sdl")]
+pub(crate) use join_screen::*;
+mod bench;
+pub use bench::*;
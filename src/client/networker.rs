@@ -0,0 +1,337 @@
+use std::{
+    cell::RefCell,
+    collections::VecDeque,
+    net::{SocketAddrV4, TcpStream},
+    num::NonZero,
+    ops::Deref,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use crate::common::{
+    ClientToServerPackage, GameConfig, GameEvent, GameState, KillPackage, Map, MovementState,
+    PacketReader, PacketWriter, PlayerConnectedPackage, PlayerState, Point, ServerToClientPackage,
+    Vector,
+};
+
+pub struct GameStateQueue {
+    pub prediction: GameState,
+    pub last_received: GameState,
+    pub penultimate_received: GameState,
+}
+
+impl GameStateQueue {
+    pub fn new() -> Self {
+        Self {
+            prediction: GameState::new(),
+            last_received: GameState::new(),
+            penultimate_received: GameState::new(),
+        }
+    }
+}
+
+impl Default for GameStateQueue {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Gameplay config received from server
+pub struct ReceivedConfig {
+    pub config: Option<GameConfig>,
+    /// Time when config was changed by server after it was received first time
+    pub update_instant: Option<Instant>,
+}
+
+impl ReceivedConfig {
+    pub fn new() -> Self {
+        Self {
+            config: None,
+            update_instant: None,
+        }
+    }
+}
+
+impl Default for ReceivedConfig {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Recent game events shown in the kill feed
+pub struct EventFeed {
+    pub entries: VecDeque<(Instant, GameEvent)>,
+    pub last_kill: Option<KillPackage>,
+}
+
+impl EventFeed {
+    pub const ENTRY_LIFETIME: Duration = Duration::from_secs(6);
+    const CAPACITY: usize = 8;
+
+    pub fn new() -> Self {
+        Self {
+            entries: Default::default(),
+            last_kill: None,
+        }
+    }
+
+    fn push(&mut self, event: GameEvent) {
+        if self.entries.len() == Self::CAPACITY {
+            self.entries.pop_front();
+        }
+        self.entries.push_back((Instant::now(), event));
+    }
+
+    fn remove_expired(&mut self) {
+        let now = Instant::now();
+        self.entries
+            .retain(|(instant, _)| now - *instant < Self::ENTRY_LIFETIME);
+    }
+}
+
+impl Default for EventFeed {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Expanding ring shown where explosion happened
+pub struct ExplosionEffect {
+    pub instant: Instant,
+    pub pos: Point,
+    pub radius: f32,
+}
+
+impl ExplosionEffect {
+    pub const LIFETIME: Duration = Duration::from_millis(400);
+}
+
+/// Client side state shown in HUD which is not a part of game state
+pub struct Hud {
+    pub received_config: ReceivedConfig,
+    pub event_feed: EventFeed,
+    pub explosions: Vec<ExplosionEffect>,
+    pub show_scoreboard: bool,
+    /// Time when own character was damaged last time. Hearts flash after it
+    pub damage_instant: Option<Instant>,
+}
+
+impl Hud {
+    pub fn new() -> Self {
+        Self {
+            received_config: ReceivedConfig::new(),
+            event_feed: EventFeed::new(),
+            explosions: vec![],
+            show_scoreboard: false,
+            damage_instant: None,
+        }
+    }
+}
+
+impl Default for Hud {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Received traffic counters. Used by load tests
+#[derive(Debug, Clone, Copy, Default)]
+pub struct NetworkStats {
+    pub broadcasts: u64,
+    /// Total size of received broadcast packets
+    pub broadcast_bytes: u64,
+    /// Sequence number of the last input applied by server
+    pub acknowledged_sequence_number: u32,
+}
+
+pub struct Networker {
+    stream: RefCell<TcpStream>,
+    reader: RefCell<PacketReader>,
+    pub player_id: Option<NonZero<u64>>,
+    last_broadcast_instant: Instant,
+    last_broadcast_insterval: Duration,
+    /// Name and color sent to server after init
+    join_request: Option<PlayerConnectedPackage>,
+    /// Reason received from server if it rejected join request
+    pub join_rejection: Option<String>,
+    /// Received from server on connect. Game states in broadcasts do not contain it
+    map: Arc<Map>,
+    pub stats: NetworkStats,
+    /// Id of own character and its knockback sum already added to prediction
+    replayed_knockback: (u32, Vector),
+}
+
+impl Networker {
+    pub fn connect(
+        addr: SocketAddrV4,
+        join_request: PlayerConnectedPackage,
+    ) -> std::io::Result<Networker> {
+        let stream = TcpStream::connect(addr)?;
+        stream.set_nonblocking(true)?;
+        Ok(Networker {
+            stream: RefCell::new(stream),
+            reader: Default::default(),
+            player_id: None,
+            last_broadcast_instant: Instant::now(),
+            last_broadcast_insterval: Duration::from_millis(0),
+            join_request: Some(join_request),
+            join_rejection: None,
+            map: Default::default(),
+            stats: Default::default(),
+            replayed_knockback: (0, Vector { x: 0., y: 0. }),
+        })
+    }
+
+    fn interpolation_value(&self) -> f64 {
+        let now = Instant::now();
+        (now - self.last_broadcast_instant).div_duration_f64(self.last_broadcast_insterval)
+    }
+
+    pub fn write_package(&self, p: ClientToServerPackage) -> std::io::Result<()> {
+        PacketWriter::write(
+            &mut self.stream.borrow_mut().deref(),
+            &serde_json::to_vec(&p).unwrap(),
+        )
+    }
+
+    pub fn proceed(
+        &mut self,
+        game_state_queue: &mut GameStateQueue,
+        player_state: &mut PlayerState,
+        hud: &mut Hud,
+        last_sequence_number: u32,
+    ) -> std::io::Result<()> {
+        for data in self
+            .reader
+            .borrow_mut()
+            .read_ref(self.stream.borrow_mut())?
+        {
+            let package: ServerToClientPackage = serde_json::from_slice(&data).unwrap();
+
+            match package {
+                ServerToClientPackage::Init(init_package) => {
+                    self.player_id = Some(init_package.player_id);
+                    if let Some(join_request) = self.join_request.take() {
+                        self.write_package(ClientToServerPackage::PlayerConnected(join_request))
+                            .unwrap();
+                    }
+                }
+                ServerToClientPackage::Broadcast(broadcast_package) => {
+                    self.stats.broadcasts += 1;
+                    self.stats.broadcast_bytes += data.len() as u64;
+                    self.stats.acknowledged_sequence_number = broadcast_package.sequence_number;
+
+                    let player_entity_copy = self
+                        .player_id
+                        .and_then(|id| {
+                            game_state_queue
+                                .prediction
+                                .find_character_by_player_id_mut(id)
+                        })
+                        .map(|x| x.clone());
+
+                    let mut game_state = broadcast_package.game_state;
+                    game_state.set_map(self.map.clone());
+                    player_state.weapon_state = broadcast_package.player_state.weapon_state;
+                    player_state.loadout = broadcast_package.player_state.loadout;
+
+                    game_state_queue.penultimate_received = game_state_queue.last_received.clone();
+                    game_state_queue.last_received = game_state.clone();
+                    game_state_queue.prediction = game_state;
+
+                    if let Some(mut entity) = self.player_id.and_then(|id| {
+                        game_state_queue
+                            .prediction
+                            .find_character_by_player_id_mut(id)
+                    }) {
+                        // Received state contains all knockback given so far
+                        let (replayed_id, replayed) = self.replayed_knockback;
+                        let knockback = entity.movement.knockback;
+                        self.replayed_knockback = (entity.id, knockback);
+
+                        if broadcast_package.sequence_number < last_sequence_number
+                            && !player_state.killed
+                        {
+                            if let Some(player_entity_copy) = player_entity_copy {
+                                entity.pos = player_entity_copy.pos;
+                                entity.rot = player_entity_copy.rot;
+                                // Knockback given by server is not predicted. Kept prediction
+                                // already contains the part replayed before
+                                let replayed = if replayed_id == entity.id {
+                                    replayed
+                                } else {
+                                    Vector { x: 0., y: 0. }
+                                };
+                                entity.movement = MovementState {
+                                    impulse: player_entity_copy.movement.impulse + knockback
+                                        - replayed,
+                                    knockback,
+                                    ..player_entity_copy.movement
+                                };
+                            }
+                        }
+                    }
+
+                    let now = Instant::now();
+                    self.last_broadcast_insterval = now - self.last_broadcast_instant;
+                    self.last_broadcast_instant = now;
+                }
+                ServerToClientPackage::Kill(kill_package) => {
+                    println!("Kill package received");
+                    player_state.killed = true;
+                    hud.event_feed.last_kill = Some(kill_package);
+                }
+                ServerToClientPackage::Config(config_package) => {
+                    if hud.received_config.config.is_some() {
+                        println!("Config update received");
+                        hud.received_config.update_instant = Some(Instant::now());
+                    }
+                    hud.received_config.config = Some(config_package.config);
+                }
+                ServerToClientPackage::Event(event_package) => match event_package.event {
+                    GameEvent::Explosion { pos, radius, .. } => {
+                        hud.explosions.push(ExplosionEffect {
+                            instant: Instant::now(),
+                            pos,
+                            radius,
+                        })
+                    }
+                    GameEvent::Damage { player_id, .. } => {
+                        if Some(player_id) == self.player_id {
+                            hud.damage_instant = Some(Instant::now());
+                        }
+                    }
+                    event => hud.event_feed.push(event),
+                },
+                ServerToClientPackage::JoinRejected(join_rejected_package) => {
+                    println!("Join rejected: {}", join_rejected_package.reason);
+                    self.join_rejection = Some(join_rejected_package.reason);
+                }
+                ServerToClientPackage::Map(map_package) => {
+                    self.map = Arc::new(map_package.map);
+                    game_state_queue.prediction.set_map(self.map.clone());
+                    game_state_queue.last_received.set_map(self.map.clone());
+                    game_state_queue
+                        .penultimate_received
+                        .set_map(self.map.clone());
+                }
+            }
+        }
+
+        hud.event_feed.remove_expired();
+        hud.explosions
+            .retain(|x| x.instant.elapsed() < ExplosionEffect::LIFETIME);
+
+        if let Some(player_id) = self.player_id {
+            GameState::lerp_merge(
+                &mut game_state_queue.prediction,
+                &game_state_queue.penultimate_received,
+                &game_state_queue.last_received,
+                self.interpolation_value(),
+                player_id,
+            );
+        }
+
+        Ok(())
+    }
+}
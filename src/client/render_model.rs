@@ -1,6 +1,8 @@
+use crate::client::{EventFeed, ExplosionEffect, Hud, JoinScreen};
 use crate::common::{
-    CharacterWeapon, Color, Complex, DynSizeSegments, EntityRole, GameState, PlayerState,
-    PlayerWeapon, Point, ProjectileKind, Rect,
+    CharacterWeapon, Color, Complex, DynSizeSegments, EntityRole, GameEvent, GameState,
+    HealthConfig, MatchPhase, PickupKind, PlayerState, PlayerStats, PlayerWeapon, Point,
+    ProjectileKind, Rect, Team, Vector, Zone, ZoneHolder, ZoneShape,
 };
 use font_loader::system_fonts;
 use sdl2::{
@@ -8,14 +10,16 @@ use sdl2::{
     pixels, rect,
     render::{Canvas, TextureQuery},
     rwops::RWops,
-    ttf::Sdl2TtfContext,
+    ttf::{Font, Sdl2TtfContext},
     video::Window,
     Sdl,
 };
 use std::{
+    cell::RefCell,
+    collections::HashMap,
     num::NonZero,
-    sync::{Arc, OnceLock},
-    time::Instant,
+    sync::OnceLock,
+    time::{Duration, Instant},
 };
 
 fn game_color_to_sdl_color(c: Color) -> pixels::Color {
@@ -89,9 +93,11 @@ fn heart_vertices(p: Point, k: f32) -> [Point; 14] {
     ]
 }
 
+/// Monospace system font. Loaded fonts are cached by point size
 struct OwnedFont {
-    bytes: Box<Vec<u8>>,
-    ctx: Arc<Sdl2TtfContext>,
+    bytes: &'static [u8],
+    ctx: &'static Sdl2TtfContext,
+    fonts: RefCell<HashMap<u16, Font<'static, 'static>>>,
 }
 
 impl OwnedFont {
@@ -103,22 +109,18 @@ impl OwnedFont {
         text: &str,
         font_point_size: u16,
     ) {
-        let rwops = RWops::from_bytes(&self.bytes[..]).unwrap();
-        let font = self
-            .ctx
-            .load_font_from_rwops(rwops, font_point_size)
-            .unwrap();
-
         let texture_creator = canvas.texture_creator();
-        let surface = font
-            .render(text)
-            .blended(color)
-            .map_err(|e| e.to_string())
-            .unwrap();
-        let texture = texture_creator
+        let surface = self.with_font(font_point_size, |font| {
+            font.render(text)
+                .blended(color)
+                .map_err(|e| e.to_string())
+                .unwrap()
+        });
+        let mut texture = texture_creator
             .create_texture_from_surface(&surface)
             .map_err(|e| e.to_string())
             .unwrap();
+        texture.set_alpha_mod(color.a);
 
         let TextureQuery { width, height, .. } = texture.query();
         canvas
@@ -129,29 +131,72 @@ impl OwnedFont {
             )
             .unwrap();
     }
+
+    pub fn text_width(&self, text: &str, font_point_size: u16) -> u32 {
+        self.with_font(font_point_size, |font| {
+            font.size_of(text).map_err(|e| e.to_string()).unwrap().0
+        })
+    }
+
+    fn with_font<T>(&self, font_point_size: u16, f: impl FnOnce(&Font) -> T) -> T {
+        let mut fonts = self.fonts.borrow_mut();
+        let font = fonts.entry(font_point_size).or_insert_with(|| {
+            let rwops = RWops::from_bytes(self.bytes).unwrap();
+            self.ctx
+                .load_font_from_rwops(rwops, font_point_size)
+                .unwrap()
+        });
+        f(font)
+    }
+
+    /// Draws differently colored pieces of text in a row ending at `right` point
+    pub fn draw_text_row_from_right(
+        &self,
+        canvas: &mut Canvas<sdl2::video::Window>,
+        right: rect::Point,
+        pieces: &[(pixels::Color, &str)],
+        font_point_size: u16,
+    ) {
+        let widths: Vec<_> = pieces
+            .iter()
+            .map(|(_, text)| self.text_width(text, font_point_size) as i32)
+            .collect();
+        let mut x = right.x - widths.iter().sum::<i32>();
+        for ((color, text), width) in pieces.iter().zip(widths) {
+            self.draw_text(
+                canvas,
+                (x + width / 2, right.y).into(),
+                *color,
+                text,
+                font_point_size,
+            );
+            x += width;
+        }
+    }
 }
 
 impl OwnedFont {
     fn new() -> Self {
-        let mut property = system_fonts::FontPropertyBuilder::new().monospace().build();
-        let sysfonts = system_fonts::query_specific(&mut property);
+        static CTX_LOCK: OnceLock<Sdl2TtfContext> = OnceLock::new();
+        let ctx = CTX_LOCK.get_or_init(|| sdl2::ttf::init().map_err(|e| e.to_string()).unwrap());
 
-        static CTX_LOCK: OnceLock<Arc<Sdl2TtfContext>> = OnceLock::new();
-        let ctx = CTX_LOCK
-            .get_or_init(|| Arc::new(sdl2::ttf::init().map_err(|e| e.to_string()).unwrap()))
-            .clone();
+        static BYTES_LOCK: OnceLock<Vec<u8>> = OnceLock::new();
+        let bytes = BYTES_LOCK.get_or_init(|| {
+            let mut property = system_fonts::FontPropertyBuilder::new().monospace().build();
+            let sysfonts = system_fonts::query_specific(&mut property);
+            system_fonts::get(
+                &system_fonts::FontPropertyBuilder::new()
+                    .family(sysfonts.first().unwrap())
+                    .build(),
+            )
+            .unwrap()
+            .0
+        });
 
         Self {
-            bytes: Box::new(
-                system_fonts::get(
-                    &system_fonts::FontPropertyBuilder::new()
-                        .family(sysfonts.first().unwrap())
-                        .build(),
-                )
-                .unwrap()
-                .0,
-            ),
+            bytes,
             ctx,
+            fonts: RefCell::new(HashMap::new()),
         }
     }
 }
@@ -184,7 +229,9 @@ impl RenderModel {
         &mut self,
         game_state: &GameState,
         player_state: &PlayerState,
+        hud: &Hud,
         weapon: PlayerWeapon,
+        loadout: &[PlayerWeapon],
         player_id: NonZero<u64>,
     ) {
         let now = Instant::now();
@@ -197,6 +244,21 @@ impl RenderModel {
             .draw_rect(game_rect_to_sdl_rect(game_state.world_bounds()))
             .unwrap();
 
+        for wall in &game_state.map().walls {
+            let xs: Vec<_> = wall.iter().map(|x| x.p0.x as i16).collect();
+            let ys: Vec<_> = wall.iter().map(|x| x.p0.y as i16).collect();
+            self.canvas
+                .filled_polygon(&xs, &ys, pixels::Color::RGB(96, 96, 96))
+                .unwrap();
+            self.canvas
+                .polygon(&xs, &ys, pixels::Color::RGB(255, 255, 0))
+                .unwrap();
+        }
+
+        if let Some(zone) = game_state.zone() {
+            self.render_zone(game_state, zone, now);
+        }
+
         for entity in game_state.entities() {
             match &entity.role {
                 EntityRole::Character { weapon } => {
@@ -210,9 +272,23 @@ impl RenderModel {
                         )
                         .unwrap();
 
-                    match weapon {
-                        CharacterWeapon::BallGun { .. } => {}
-                        CharacterWeapon::RayGun { .. } => {}
+                    if entity.protected {
+                        let a =
+                            128. + 127. * ((now - self.creation_instant).as_secs_f32() * 10.).sin();
+                        self.canvas
+                            .circle(
+                                entity.pos.x as i16,
+                                entity.pos.y as i16,
+                                16,
+                                pixels::Color::RGBA(255, 255, 255, a as u8),
+                            )
+                            .unwrap();
+                    }
+
+                    // Barrel length and width
+                    let barrel = match weapon {
+                        CharacterWeapon::BallGun { .. } => Some((14., 3)),
+                        CharacterWeapon::RayGun { .. } => Some((18., 1)),
                         CharacterWeapon::Shield { shield, .. } => {
                             let seg = shield.segment(entity.pos, entity.rot);
 
@@ -226,8 +302,24 @@ impl RenderModel {
                                     game_color_to_sdl_color(entity.color.clone()),
                                 )
                                 .unwrap();
+                            None
                         }
-                        CharacterWeapon::MineGun { .. } => {}
+                        CharacterWeapon::MineGun { .. } => Some((12., 5)),
+                        CharacterWeapon::MissileLauncher { .. } => Some((16., 4)),
+                        CharacterWeapon::GrenadeLauncher { .. } => Some((12., 6)),
+                    };
+                    if let Some((length, width)) = barrel {
+                        let end = entity.pos + Vector { x: length, y: 0. } * entity.rot;
+                        self.canvas
+                            .thick_line(
+                                entity.pos.x as i16,
+                                entity.pos.y as i16,
+                                end.x as i16,
+                                end.y as i16,
+                                width,
+                                pixels::Color::RGB(255, 255, 255),
+                            )
+                            .unwrap();
                     }
                 }
                 EntityRole::Projectile { kind } => match kind {
@@ -270,16 +362,13 @@ impl RenderModel {
                         let p = entity.pos.inflate(*radius).points().map(|p| {
                             entity.pos
                                 + (p - entity.pos)
-                                    * Complex::from_rad(
-                                        (now - self.creation_instant).as_millis_f32() * 0.001,
-                                    )
+                                    * Complex::from_rad((now - self.creation_instant).as_secs_f32())
                         });
 
                         let color = if entity.activated {
                             game_color_to_sdl_color(entity.color.clone().with_r(
-                                ((((now - self.creation_instant).as_millis_f32() * 0.001).sin()
-                                    + 1.)
-                                    * 128.) as u8,
+                                (((now - self.creation_instant).as_secs_f32().sin() + 1.) * 128.)
+                                    as u8,
                             ))
                         } else {
                             game_color_to_sdl_color(entity.color.clone())
@@ -289,108 +378,947 @@ impl RenderModel {
                             .filled_polygon(&p.map(|p| p.x as i16), &p.map(|p| p.y as i16), color)
                             .unwrap();
                     }
+                    ProjectileKind::Missile { radius, .. } => {
+                        let exhaust_len = radius
+                            * (2.
+                                + ((now - self.creation_instant).as_secs_f32() * 50.
+                                    + entity.id as f32)
+                                    .sin());
+                        for (len, width, color) in [
+                            (exhaust_len * 1.5, 4, pixels::Color::RGBA(255, 64, 0, 160)),
+                            (exhaust_len, 2, pixels::Color::RGB(255, 255, 0)),
+                        ] {
+                            let p0 = entity.pos + Vector { x: -radius, y: 0. } * entity.rot;
+                            let p1 = p0 + Vector { x: -len, y: 0. } * entity.rot;
+                            self.canvas
+                                .thick_line(
+                                    p0.x as i16,
+                                    p0.y as i16,
+                                    p1.x as i16,
+                                    p1.y as i16,
+                                    width,
+                                    color,
+                                )
+                                .unwrap();
+                        }
+
+                        let p = [
+                            Vector {
+                                x: radius * 2.,
+                                y: 0.,
+                            },
+                            Vector {
+                                x: -radius,
+                                y: -radius,
+                            },
+                            Vector {
+                                x: -radius,
+                                y: *radius,
+                            },
+                        ]
+                        .map(|v| entity.pos + v * entity.rot);
+                        self.canvas
+                            .filled_trigon(
+                                p[0].x as i16,
+                                p[0].y as i16,
+                                p[1].x as i16,
+                                p[1].y as i16,
+                                p[2].x as i16,
+                                p[2].y as i16,
+                                game_color_to_sdl_color(entity.color.clone()),
+                            )
+                            .unwrap();
+                    }
+                    ProjectileKind::Grenade {
+                        fuse_duration,
+                        radius,
+                        ..
+                    } => {
+                        self.canvas
+                            .filled_circle(
+                                entity.pos.x as i16,
+                                entity.pos.y as i16,
+                                *radius as i16,
+                                game_color_to_sdl_color(entity.color.clone()),
+                            )
+                            .unwrap();
+
+                        // Blinks faster as fuse burns out
+                        let burnt = entity.age.div_duration_f32(*fuse_duration);
+                        if (burnt * burnt * 16.).fract() < 0.5 {
+                            self.canvas
+                                .circle(
+                                    entity.pos.x as i16,
+                                    entity.pos.y as i16,
+                                    *radius as i16 + 2,
+                                    pixels::Color::RGB(255, 255, 255),
+                                )
+                                .unwrap();
+                        }
+                    }
                 },
+                EntityRole::Flag {
+                    team,
+                    home,
+                    carrier,
+                } => {
+                    let color = game_color_to_sdl_color(team.color());
+                    self.canvas
+                        .circle(home.x as i16, home.y as i16, 24, color)
+                        .unwrap();
+
+                    let pole = if carrier.is_some() {
+                        self.canvas
+                            .circle(entity.pos.x as i16, entity.pos.y as i16, 14, color)
+                            .unwrap();
+                        entity.pos + Vector { x: 10., y: -4. }
+                    } else {
+                        entity.pos
+                    };
+                    self.canvas
+                        .thick_line(
+                            pole.x as i16,
+                            (pole.y + 12.) as i16,
+                            pole.x as i16,
+                            (pole.y - 12.) as i16,
+                            2,
+                            pixels::Color::RGB(255, 255, 255),
+                        )
+                        .unwrap();
+                    self.canvas
+                        .filled_trigon(
+                            pole.x as i16,
+                            (pole.y - 12.) as i16,
+                            (pole.x + 14.) as i16,
+                            (pole.y - 7.) as i16,
+                            pole.x as i16,
+                            (pole.y - 2.) as i16,
+                            color,
+                        )
+                        .unwrap();
+                }
+                EntityRole::Pickup { kind } => {
+                    let color = match kind {
+                        PickupKind::Health { .. } => pixels::Color::RGB(0, 192, 0),
+                        PickupKind::Armor { .. } => pixels::Color::RGB(128, 192, 255),
+                        PickupKind::SpeedBoost { .. } => pixels::Color::RGB(0, 128, 255),
+                        PickupKind::RapidFire { .. } => pixels::Color::RGB(255, 128, 0),
+                        PickupKind::WeaponSwap { .. } => pixels::Color::RGB(192, 0, 192),
+                    };
+                    let radius = entity.inscribed_circle_radius() as i16;
+                    self.canvas
+                        .filled_circle(entity.pos.x as i16, entity.pos.y as i16, radius, color)
+                        .unwrap();
+                    self.canvas
+                        .circle(
+                            entity.pos.x as i16,
+                            entity.pos.y as i16,
+                            radius,
+                            pixels::Color::RGB(255, 255, 255),
+                        )
+                        .unwrap();
+                    self.font.draw_text(
+                        &mut self.canvas,
+                        (entity.pos.x as i32, entity.pos.y as i32 - 18).into(),
+                        pixels::Color::RGB(255, 255, 255),
+                        kind.name(),
+                        10,
+                    );
+                }
+            }
+        }
+
+        for explosion in &hud.explosions {
+            let t = (now - explosion.instant).div_duration_f32(ExplosionEffect::LIFETIME);
+            let alpha = (255. * (1. - t).max(0.)) as u8;
+            let radius = (explosion.radius * t.min(1.)) as i16;
+            self.canvas
+                .filled_circle(
+                    explosion.pos.x as i16,
+                    explosion.pos.y as i16,
+                    radius,
+                    pixels::Color::RGBA(255, 128, 0, alpha / 4),
+                )
+                .unwrap();
+            for i in 0..3 {
+                self.canvas
+                    .circle(
+                        explosion.pos.x as i16,
+                        explosion.pos.y as i16,
+                        radius - i,
+                        pixels::Color::RGBA(255, 255, 0, alpha),
+                    )
+                    .unwrap();
+            }
+        }
+
+        for entity in game_state.entities() {
+            if let (EntityRole::Character { .. }, Some(player)) =
+                (&entity.role, game_state.players().get(entity.player_id))
+            {
+                self.font.draw_text(
+                    &mut self.canvas,
+                    (entity.pos.x as i32, entity.pos.y as i32 - 24).into(),
+                    pixels::Color::RGB(255, 255, 255),
+                    &player.name,
+                    12,
+                );
             }
         }
 
         self.canvas.set_draw_color(pixels::Color::RGB(0, 255, 0));
 
         if let Some(character) = game_state.find_character_by_player_id_mut(player_id) {
-            for i in 0..character.health {
-                let vertices = heart_vertices(
-                    Point {
-                        x: 22. + i as f32 * 7. * 4.,
-                        y: 22.,
-                    },
-                    4.,
+            let max_health = hud
+                .received_config
+                .config
+                .as_ref()
+                .map_or(HealthConfig::default().max, |x| x.health.max);
+            let flash = hud
+                .damage_instant
+                .is_some_and(|x| now - x < Duration::from_millis(200));
+            let health_color = if flash {
+                pixels::Color::RGB(255, 64, 64)
+            } else {
+                pixels::Color::RGB(255, 255, 255)
+            };
+            // Each heart is one point of health, partially filled hearts are drawn smaller
+            let hearts = (0..max_health.ceil() as usize)
+                .map(|i| (character.health - i as f32, health_color))
+                .chain((0..character.armor.ceil() as usize).map(|i| {
+                    (
+                        character.armor - i as f32,
+                        pixels::Color::RGB(128, 192, 255),
+                    )
+                }));
+            let mut heart_count = 0;
+            for (i, (fill, color)) in hearts.enumerate() {
+                let center = Point {
+                    x: 22. + i as f32 * 7. * 4.,
+                    y: 22.,
+                };
+                let outline = heart_vertices(center, 4.);
+                self.canvas
+                    .polygon(
+                        &outline.map(|p| p.x as i16),
+                        &outline.map(|p| p.y as i16),
+                        pixels::Color::RGB(128, 128, 128),
+                    )
+                    .unwrap();
+                let fill = fill.clamp(0., 1.);
+                if fill > 0. {
+                    let vertices = heart_vertices(center, 4. * fill);
+                    self.canvas
+                        .filled_polygon(
+                            &vertices.map(|p| p.x as i16),
+                            &vertices.map(|p| p.y as i16),
+                            color,
+                        )
+                        .unwrap();
+                }
+                heart_count += 1;
+            }
+
+            let mut x = 22 + heart_count * 7 * 4;
+            for buff in &character.buffs {
+                let text = buff.name();
+                let width = self.font.text_width(&text, 12) as i32;
+                self.font.draw_text(
+                    &mut self.canvas,
+                    (x + width / 2, 22).into(),
+                    pixels::Color::RGB(255, 255, 0),
+                    &text,
+                    12,
                 );
+                x += width + 12;
+            }
 
+            if let EntityRole::Character { weapon } = &character.role {
+                let weapon_state = player_state.weapon_state;
+                let progress = weapon_state.progress(weapon.magazine());
+                let bar = Rect {
+                    x: 10.,
+                    y: 38.,
+                    w: 80.,
+                    h: 4.,
+                };
+                self.canvas.set_draw_color(pixels::Color::RGB(80, 80, 80));
+                self.canvas.fill_rect(game_rect_to_sdl_rect(bar)).unwrap();
                 self.canvas
-                    .filled_polygon(
-                        &vertices.map(|p| p.x as i16),
-                        &vertices.map(|p| p.y as i16),
-                        pixels::Color::RGB(255, 255, 255),
-                    )
+                    .set_draw_color(pixels::Color::RGB(255, 255, 255));
+                self.canvas
+                    .fill_rect(game_rect_to_sdl_rect(Rect {
+                        w: bar.w * progress,
+                        ..bar
+                    }))
                     .unwrap();
+
+                if let Some(ammo) = weapon_state.ammo {
+                    let text = match (ammo.reload_remaining, ammo.reserve) {
+                        (Some(_), _) => "Reloading".to_string(),
+                        (None, Some(reserve)) => format!("{}/{}", ammo.loaded, reserve),
+                        (None, None) => ammo.loaded.to_string(),
+                    };
+                    let width = self.font.text_width(&text, 10) as i32;
+                    self.font.draw_text(
+                        &mut self.canvas,
+                        (100 + width / 2, 40).into(),
+                        pixels::Color::RGB(255, 255, 255),
+                        &text,
+                        10,
+                    );
+                }
+            }
+
+            let mut x = 160;
+            for (i, slot_weapon) in player_state.loadout.iter().enumerate() {
+                let text = format!("{} {}", i + 1, slot_weapon.name());
+                let width = self.font.text_width(&text, 10) as i32;
+                self.font.draw_text(
+                    &mut self.canvas,
+                    (x + width / 2, 40).into(),
+                    if *slot_weapon == character.player_weapon {
+                        pixels::Color::RGB(255, 255, 0)
+                    } else {
+                        pixels::Color::RGB(255, 255, 255)
+                    },
+                    &text,
+                    10,
+                );
+                x += width + 12;
             }
         }
 
         let window_size = self.canvas.window().size();
+
+        if let Some(stats) = game_state.scoreboard().stats(player_id) {
+            self.font.draw_text(
+                &mut self.canvas,
+                (window_size.0 as i32 - 100, 22).into(),
+                pixels::Color::RGB(255, 255, 255),
+                &format!("Score: {}  Streak: {}", stats.score(), stats.streak),
+                14,
+            );
+        }
+
+        let clock = match game_state.match_state().phase() {
+            MatchPhase::Warmup => "Warmup".to_string(),
+            MatchPhase::Countdown { remaining } => {
+                format!("Starting in {}", remaining.as_secs_f32().ceil())
+            }
+            MatchPhase::Round { remaining } => {
+                let seconds = remaining.as_secs_f32().ceil() as u64;
+                format!("{}:{:02}", seconds / 60, seconds % 60)
+            }
+            MatchPhase::Results { remaining, .. } => {
+                format!("Next round in {}", remaining.as_secs_f32().ceil())
+            }
+        };
+        self.font.draw_text(
+            &mut self.canvas,
+            (window_size.0 as i32 / 2, 22).into(),
+            pixels::Color::RGB(255, 255, 255),
+            &clock,
+            16,
+        );
+
+        if let Some(update_instant) = hud.received_config.update_instant {
+            if now - update_instant < Duration::from_secs(3) {
+                self.font.draw_text(
+                    &mut self.canvas,
+                    (window_size.0 as i32 / 2, 40).into(),
+                    pixels::Color::RGB(255, 255, 0),
+                    "Server config updated",
+                    14,
+                );
+            }
+        }
+
         if player_state.killed {
             self.font.draw_text(
                 &mut self.canvas,
                 (window_size.0 as i32 / 2, window_size.1 as i32 / 2).into(),
                 pixels::Color::RGB(255, 255, 0),
                 &format!("You are killed. SPACE to respawn"),
-                (10. * (((now - self.creation_instant).as_millis_f32() * 0.002).sin() + 2.)) as u16,
+                (10. * (((now - self.creation_instant).as_secs_f32() * 2.).sin() + 2.)) as u16,
             );
 
-            self.font.draw_text(
-                &mut self.canvas,
-                (200, 500).into(),
-                if weapon == PlayerWeapon::BallGun {
+            if let Some(kill) = &hud.event_feed.last_kill {
+                let (killer, killer_color) = Self::player_label(game_state, kill.killer);
+                self.font.draw_text(
+                    &mut self.canvas,
+                    (window_size.0 as i32 / 2, window_size.1 as i32 / 2 + 40).into(),
+                    killer_color,
+                    &if kill.killer == player_id {
+                        format!("by yourself with {}", kill.weapon.name())
+                    } else {
+                        format!("by {} with {}", killer, kill.weapon.name())
+                    },
+                    16,
+                );
+            }
+
+            let weapon_color = |x: PlayerWeapon| {
+                if x == weapon {
                     pixels::Color::RGB(255, 255, 0)
+                } else if loadout.contains(&x) {
+                    pixels::Color::RGB(255, 255, 255)
                 } else {
                     pixels::Color::RGB(0, 255, 0)
-                },
+                }
+            };
+
+            self.font.draw_text(
+                &mut self.canvas,
+                (100, 500).into(),
+                weapon_color(PlayerWeapon::BallGun),
                 &format!("Ball gun"),
-                (16. * (((now - self.creation_instant).as_millis_f32() * 0.001).sin() / 8. + 1.))
-                    as u16,
+                (16. * ((now - self.creation_instant).as_secs_f32().sin() / 8. + 1.)) as u16,
             );
 
             self.font.draw_text(
                 &mut self.canvas,
-                (300, 500).into(),
-                if weapon == PlayerWeapon::PulseGun {
-                    pixels::Color::RGB(255, 255, 0)
-                } else {
-                    pixels::Color::RGB(0, 255, 0)
-                },
+                (200, 500).into(),
+                weapon_color(PlayerWeapon::PulseGun),
                 &format!("Pulse gun"),
-                (16. * (((now - self.creation_instant).as_millis_f32() * 0.001 + 1.).sin() / 8.
-                    + 1.)) as u16,
+                (16. * (((now - self.creation_instant).as_secs_f32() + 1.).sin() / 8. + 1.)) as u16,
             );
 
             self.font.draw_text(
                 &mut self.canvas,
-                (400, 500).into(),
-                if weapon == PlayerWeapon::RayGun {
-                    pixels::Color::RGB(255, 255, 0)
-                } else {
-                    pixels::Color::RGB(0, 255, 0)
-                },
+                (300, 500).into(),
+                weapon_color(PlayerWeapon::RayGun),
                 &format!("Ray gun"),
-                (16. * (((now - self.creation_instant).as_millis_f32() * 0.001 + 2.).sin() / 8.
-                    + 1.)) as u16,
+                (16. * (((now - self.creation_instant).as_secs_f32() + 2.).sin() / 8. + 1.)) as u16,
             );
 
             self.font.draw_text(
                 &mut self.canvas,
-                (500, 500).into(),
-                if weapon == PlayerWeapon::Shield {
-                    pixels::Color::RGB(255, 255, 0)
-                } else {
-                    pixels::Color::RGB(0, 255, 0)
-                },
+                (400, 500).into(),
+                weapon_color(PlayerWeapon::Shield),
                 &format!("Shield"),
-                (16. * (((now - self.creation_instant).as_millis_f32() * 0.001 + 3.).sin() / 8.
-                    + 1.)) as u16,
+                (16. * (((now - self.creation_instant).as_secs_f32() + 3.).sin() / 8. + 1.)) as u16,
             );
 
             self.font.draw_text(
                 &mut self.canvas,
-                (600, 500).into(),
-                if weapon == PlayerWeapon::MineGun {
-                    pixels::Color::RGB(255, 255, 0)
-                } else {
-                    pixels::Color::RGB(0, 255, 0)
-                },
+                (500, 500).into(),
+                weapon_color(PlayerWeapon::MineGun),
                 &format!("Mine gun"),
-                (16. * (((now - self.creation_instant).as_millis_f32() * 0.001 + 4.).sin() / 8.
-                    + 1.)) as u16,
+                (16. * (((now - self.creation_instant).as_secs_f32() + 4.).sin() / 8. + 1.)) as u16,
+            );
+
+            self.font.draw_text(
+                &mut self.canvas,
+                (600, 500).into(),
+                weapon_color(PlayerWeapon::MissileLauncher),
+                "Missiles",
+                (16. * (((now - self.creation_instant).as_secs_f32() + 5.).sin() / 8. + 1.)) as u16,
             );
+
+            self.font.draw_text(
+                &mut self.canvas,
+                (700, 500).into(),
+                weapon_color(PlayerWeapon::GrenadeLauncher),
+                "Grenades",
+                (16. * (((now - self.creation_instant).as_secs_f32() + 6.).sin() / 8. + 1.)) as u16,
+            );
+
+            if let Some(config) = &hud.received_config.config {
+                for (x, weapon) in [
+                    (100, PlayerWeapon::BallGun),
+                    (200, PlayerWeapon::PulseGun),
+                    (300, PlayerWeapon::RayGun),
+                    (400, PlayerWeapon::Shield),
+                    (500, PlayerWeapon::MineGun),
+                    (600, PlayerWeapon::MissileLauncher),
+                    (700, PlayerWeapon::GrenadeLauncher),
+                ] {
+                    if let Some(fire_interval) = config.weapons.get(weapon).fire_interval() {
+                        self.font.draw_text(
+                            &mut self.canvas,
+                            (x, 525).into(),
+                            pixels::Color::RGB(0, 255, 0),
+                            &format!("{:.1}s", fire_interval.as_secs_f32()),
+                            12,
+                        );
+                    }
+                }
+
+                let slots: Vec<_> = (0..config.loadout.size)
+                    .map(|i| format!("{}: {}", i + 1, loadout.get(i).map_or("-", |x| x.name())))
+                    .collect();
+                self.font.draw_text(
+                    &mut self.canvas,
+                    (window_size.0 as i32 / 2, 560).into(),
+                    pixels::Color::RGB(255, 255, 255),
+                    &format!(
+                        "A/D to choose, number to put in loadout   {}",
+                        slots.join("  ")
+                    ),
+                    14,
+                );
+            }
+        }
+
+        self.render_event_feed(game_state, &hud.event_feed);
+        self.render_flag_status(game_state);
+
+        if let MatchPhase::Results { standings, .. } = game_state.match_state().phase() {
+            let standings: Vec<_> = standings.iter().collect();
+            let title = match (game_state.scoreboard().leading_team(), standings.first()) {
+                (Some(team), _) => format!(
+                    "Round {} won by {} team",
+                    game_state.match_state().round(),
+                    team.name()
+                ),
+                (None, Some(winner)) => format!(
+                    "Round {} won by {}",
+                    game_state.match_state().round(),
+                    Self::player_label(game_state, winner.player_id).0
+                ),
+                (None, None) => format!("Round {} is over", game_state.match_state().round()),
+            };
+            self.font.draw_text(
+                &mut self.canvas,
+                (window_size.0 as i32 / 2, 56).into(),
+                pixels::Color::RGB(255, 255, 0),
+                &title,
+                20,
+            );
+            self.render_scoreboard(game_state, &standings, player_id);
+        } else if hud.show_scoreboard {
+            self.render_scoreboard(game_state, &game_state.scoreboard().ranked(), player_id);
         }
 
         self.canvas.present();
     }
+
+    pub(crate) fn render_join_screen(&mut self, join_screen: &JoinScreen) {
+        let window_size = self.canvas.window().size();
+        let center_x = window_size.0 as i32 / 2;
+        let text_color = pixels::Color::RGB(255, 255, 255);
+
+        self.canvas.set_draw_color(pixels::Color::RGB(255, 0, 0));
+        self.canvas.clear();
+
+        self.font.draw_text(
+            &mut self.canvas,
+            (center_x, 200).into(),
+            pixels::Color::RGB(255, 255, 0),
+            "Enter your name",
+            24,
+        );
+
+        self.font.draw_text(
+            &mut self.canvas,
+            (center_x, 270).into(),
+            text_color,
+            &format!("{}_", join_screen.name),
+            20,
+        );
+
+        for (i, color) in JoinScreen::PALETTE.iter().enumerate() {
+            let rect = JoinScreen::swatch_rect(i);
+            self.canvas
+                .set_draw_color(game_color_to_sdl_color(color.clone()));
+            self.canvas.fill_rect(game_rect_to_sdl_rect(rect)).unwrap();
+            if i == join_screen.color_index {
+                self.canvas.set_draw_color(pixels::Color::RGB(255, 255, 0));
+                self.canvas
+                    .draw_rect(game_rect_to_sdl_rect(Rect {
+                        x: rect.x - 4.,
+                        y: rect.y - 4.,
+                        w: rect.w + 8.,
+                        h: rect.h + 8.,
+                    }))
+                    .unwrap();
+            }
+        }
+
+        self.font.draw_text(
+            &mut self.canvas,
+            (center_x, 420).into(),
+            text_color,
+            "LEFT/RIGHT or click to choose color. ENTER to join",
+            14,
+        );
+
+        self.font.draw_text(
+            &mut self.canvas,
+            (center_x, 390).into(),
+            join_screen
+                .team
+                .map_or(text_color, |team| game_color_to_sdl_color(team.color())),
+            &format!(
+                "Team: {} (UP/DOWN, used in team modes)",
+                join_screen.team.map_or("Auto", |team| team.name())
+            ),
+            14,
+        );
+
+        if let Some(error) = &join_screen.error {
+            self.font.draw_text(
+                &mut self.canvas,
+                (center_x, 460).into(),
+                pixels::Color::RGB(255, 255, 0),
+                error,
+                14,
+            );
+        }
+
+        self.canvas.present();
+    }
+
+    fn render_flag_status(&mut self, game_state: &GameState) {
+        let window_size = self.canvas.window().size();
+        let statuses: Vec<_> = game_state
+            .entities()
+            .filter_map(|e| match e.role {
+                EntityRole::Flag {
+                    team,
+                    home,
+                    carrier,
+                } => Some((
+                    team,
+                    match carrier {
+                        Some(player_id) => {
+                            format!("carried by {}", Self::player_label(game_state, player_id).0)
+                        }
+                        None if e.pos == home => "at base".to_string(),
+                        None => "dropped".to_string(),
+                    },
+                )),
+                _ => None,
+            })
+            .collect();
+
+        for (i, (team, status)) in statuses.into_iter().enumerate() {
+            self.font.draw_text(
+                &mut self.canvas,
+                (
+                    window_size.0 as i32 / 4 * (1 + 2 * i as i32),
+                    window_size.1 as i32 - 14,
+                )
+                    .into(),
+                game_color_to_sdl_color(team.color()),
+                &format!("{} flag {}", team.name(), status),
+                14,
+            );
+        }
+    }
+
+    fn zone_holder_label(game_state: &GameState, holder: ZoneHolder) -> (String, pixels::Color) {
+        match holder {
+            ZoneHolder::Team(team) => (
+                format!("{} team", team.name()),
+                game_color_to_sdl_color(team.color()),
+            ),
+            ZoneHolder::Player(player_id) => Self::player_label(game_state, player_id),
+        }
+    }
+
+    fn render_zone(&mut self, game_state: &GameState, zone: &Zone, now: Instant) {
+        let window_size = self.canvas.window().size();
+        let (holder, holder_color) = match zone.holder {
+            Some(holder) => Self::zone_holder_label(game_state, holder),
+            None => ("nobody".to_string(), pixels::Color::RGB(255, 255, 255)),
+        };
+        let outline_color =
+            if zone.contested && ((now - self.creation_instant).as_secs_f32() * 10.).sin() > 0. {
+                pixels::Color::RGB(255, 255, 0)
+            } else {
+                holder_color
+            };
+        let fill_color = pixels::Color {
+            a: (32. + 96. * zone.capture_progress) as u8,
+            ..holder_color
+        };
+
+        match zone.shape {
+            ZoneShape::Circle { radius } => {
+                let (x, y, radius) = (zone.center.x as i16, zone.center.y as i16, radius as i16);
+                self.canvas.filled_circle(x, y, radius, fill_color).unwrap();
+                self.canvas.circle(x, y, radius, outline_color).unwrap();
+            }
+            ZoneShape::Rect { w, h } => {
+                let (x0, y0) = (zone.center.x - w / 2., zone.center.y - h / 2.);
+                let (x1, y1) = (x0 + w, y0 + h);
+                self.canvas
+                    .box_(x0 as i16, y0 as i16, x1 as i16, y1 as i16, fill_color)
+                    .unwrap();
+                self.canvas
+                    .rectangle(x0 as i16, y0 as i16, x1 as i16, y1 as i16, outline_color)
+                    .unwrap();
+            }
+        }
+
+        let status = if zone.contested {
+            "contested".to_string()
+        } else {
+            format!("{} {:.0}%", holder, zone.capture_progress * 100.)
+        };
+        self.font.draw_text(
+            &mut self.canvas,
+            (window_size.0 as i32 / 2, window_size.1 as i32 - 14).into(),
+            outline_color,
+            &format!(
+                "Zone: {}  moves in {}",
+                status,
+                zone.relocation_remaining.as_secs_f32().ceil()
+            ),
+            14,
+        );
+    }
+
+    fn player_label(game_state: &GameState, player_id: NonZero<u64>) -> (String, pixels::Color) {
+        match game_state.players().get(player_id) {
+            Some(player) => (
+                player.name.clone(),
+                game_color_to_sdl_color(player.color.clone().with_a(255)),
+            ),
+            None => (player_id.to_string(), pixels::Color::RGB(255, 255, 255)),
+        }
+    }
+
+    fn render_event_feed(&mut self, game_state: &GameState, event_feed: &EventFeed) {
+        let now = Instant::now();
+        let window_size = self.canvas.window().size();
+        let fade_duration = Duration::from_millis(1500);
+        let text_color = pixels::Color::RGB(255, 255, 255);
+
+        for (i, (instant, event)) in event_feed.entries.iter().rev().enumerate() {
+            let remaining = EventFeed::ENTRY_LIFETIME.saturating_sub(now - *instant);
+            let alpha = if remaining < fade_duration {
+                (255. * remaining.div_duration_f32(fade_duration)) as u8
+            } else {
+                255
+            };
+            let with_alpha = |color: pixels::Color| pixels::Color { a: alpha, ..color };
+
+            let label = |player_id| {
+                let (text, color) = Self::player_label(game_state, player_id);
+                (text, with_alpha(color))
+            };
+
+            let pieces: Vec<(pixels::Color, String)> = match event {
+                GameEvent::Kill {
+                    killer,
+                    victim,
+                    weapon,
+                } => {
+                    let (killer, killer_color) = label(*killer);
+                    let (victim, victim_color) = label(*victim);
+                    vec![
+                        (killer_color, killer),
+                        (with_alpha(text_color), format!(" [{}] ", weapon.name())),
+                        (victim_color, victim),
+                    ]
+                }
+                GameEvent::Suicide { player_id } => {
+                    let (player, color) = label(*player_id);
+                    vec![
+                        (color, player),
+                        (with_alpha(text_color), " self destructed".to_string()),
+                    ]
+                }
+                GameEvent::Join { player_id } => {
+                    let (player, color) = label(*player_id);
+                    vec![
+                        (color, player),
+                        (with_alpha(text_color), " joined".to_string()),
+                    ]
+                }
+                GameEvent::Leave { player } => vec![
+                    (
+                        with_alpha(game_color_to_sdl_color(player.color.clone().with_a(255))),
+                        player.name.clone(),
+                    ),
+                    (with_alpha(text_color), " left".to_string()),
+                ],
+                GameEvent::MineDetonation { player_id, .. } => {
+                    let (player, color) = label(*player_id);
+                    vec![
+                        (color, format!("{}'s", player)),
+                        (with_alpha(text_color), " mine detonated".to_string()),
+                    ]
+                }
+                // Rendered as an effect and never added to feed
+                GameEvent::Explosion { .. } | GameEvent::Damage { .. } => vec![],
+                GameEvent::FlagTaken { player_id, team } => {
+                    let (player, color) = label(*player_id);
+                    vec![
+                        (color, player),
+                        (with_alpha(text_color), " took ".to_string()),
+                        (
+                            with_alpha(game_color_to_sdl_color(team.color())),
+                            format!("{} flag", team.name()),
+                        ),
+                    ]
+                }
+                GameEvent::FlagDropped { team } => vec![
+                    (
+                        with_alpha(game_color_to_sdl_color(team.color())),
+                        format!("{} flag", team.name()),
+                    ),
+                    (with_alpha(text_color), " dropped".to_string()),
+                ],
+                GameEvent::FlagReturned { player_id, team } => {
+                    let (player, color) = label(*player_id);
+                    vec![
+                        (color, player),
+                        (with_alpha(text_color), " returned ".to_string()),
+                        (
+                            with_alpha(game_color_to_sdl_color(team.color())),
+                            format!("{} flag", team.name()),
+                        ),
+                    ]
+                }
+                GameEvent::FlagCaptured { player_id, team } => {
+                    let (player, color) = label(*player_id);
+                    vec![
+                        (color, player),
+                        (with_alpha(text_color), " captured ".to_string()),
+                        (
+                            with_alpha(game_color_to_sdl_color(team.color())),
+                            format!("{} flag", team.name()),
+                        ),
+                    ]
+                }
+                GameEvent::ZoneCaptured { holder } => {
+                    let (holder, color) = Self::zone_holder_label(game_state, *holder);
+                    vec![
+                        (with_alpha(color), holder),
+                        (with_alpha(text_color), " captured the zone".to_string()),
+                    ]
+                }
+                GameEvent::ZoneMoved => vec![(with_alpha(text_color), "Zone moved".to_string())],
+                GameEvent::RoundStarted { round } => {
+                    vec![(with_alpha(text_color), format!("Round {} started", round))]
+                }
+                GameEvent::RoundEnded {
+                    winning_team: Some(team),
+                    ..
+                } => vec![
+                    (with_alpha(text_color), "Round won by ".to_string()),
+                    (
+                        with_alpha(game_color_to_sdl_color(team.color())),
+                        format!("{} team", team.name()),
+                    ),
+                ],
+                GameEvent::RoundEnded { winner, .. } => match winner {
+                    Some(winner) => {
+                        let (player, color) = label(*winner);
+                        vec![
+                            (with_alpha(text_color), "Round won by ".to_string()),
+                            (color, player),
+                        ]
+                    }
+                    None => vec![(with_alpha(text_color), "Round ended".to_string())],
+                },
+            };
+
+            let pieces: Vec<_> = pieces
+                .iter()
+                .map(|(color, text)| (*color, text.as_str()))
+                .collect();
+
+            self.font.draw_text_row_from_right(
+                &mut self.canvas,
+                (window_size.0 as i32 - 16, 48 + i as i32 * 18).into(),
+                &pieces,
+                12,
+            );
+        }
+    }
+
+    fn render_scoreboard(
+        &mut self,
+        game_state: &GameState,
+        ranked: &[&PlayerStats],
+        player_id: NonZero<u64>,
+    ) {
+        let window_size = self.canvas.window().size();
+        let row_height = 24;
+        let top = 120;
+        let columns = [
+            ("Player", -240),
+            ("Score", -80),
+            ("Kills", 0),
+            ("Deaths", 80),
+            ("Streak", 160),
+            ("Best", 240),
+        ];
+        let center = window_size.0 as i32 / 2;
+
+        if game_state.scoreboard().leading_team().is_some() {
+            let [red, blue] = Team::ALL.map(|team| {
+                (
+                    game_color_to_sdl_color(team.color()),
+                    format!(
+                        "{} {}",
+                        team.name(),
+                        game_state.scoreboard().team_score(team)
+                    ),
+                )
+            });
+            let separator = (pixels::Color::RGB(255, 255, 255), " : ".to_string());
+            let pieces = [red, separator, blue];
+            let width: u32 = pieces
+                .iter()
+                .map(|(_, text)| self.font.text_width(text, 16))
+                .sum();
+            let pieces: Vec<_> = pieces
+                .iter()
+                .map(|(color, text)| (*color, text.as_str()))
+                .collect();
+            self.font.draw_text_row_from_right(
+                &mut self.canvas,
+                (center + width as i32 / 2, top - row_height - 12).into(),
+                &pieces,
+                16,
+            );
+        }
+
+        self.canvas
+            .box_(
+                (center - 300) as i16,
+                (top - row_height) as i16,
+                (center + 300) as i16,
+                (top + row_height * (ranked.len() as i32 + 1)) as i16,
+                pixels::Color::RGBA(0, 0, 0, 192),
+            )
+            .unwrap();
+
+        for (title, x) in columns {
+            self.font.draw_text(
+                &mut self.canvas,
+                (center + x, top).into(),
+                pixels::Color::RGB(255, 255, 255),
+                title,
+                14,
+            );
+        }
+
+        for (i, stats) in ranked.iter().enumerate() {
+            let y = top + row_height * (i as i32 + 1);
+            let color = if stats.player_id == player_id {
+                pixels::Color::RGB(255, 255, 0)
+            } else {
+                pixels::Color::RGB(0, 255, 0)
+            };
+            let values = [
+                Self::player_label(game_state, stats.player_id).0,
+                stats.score().to_string(),
+                stats.kills.to_string(),
+                stats.deaths.to_string(),
+                stats.streak.to_string(),
+                stats.best_streak.to_string(),
+            ];
+            for ((_, x), value) in columns.iter().zip(values) {
+                self.font
+                    .draw_text(&mut self.canvas, (center + x, y).into(), color, &value, 14);
+            }
+        }
+    }
 }
@@ -0,0 +1,302 @@
+use super::{
+    Complex, Entity, EntityRole, GameState, InputButtons, MovementInput, PlayerWeapon, Point,
+    ProjectileKind, Segment, Vector,
+};
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use std::{f32::consts::PI, num::NonZero, time::Duration};
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct BotConfig {
+    /// Delay between noticing an enemy and firing at it
+    pub reaction_time: Duration,
+    /// Max angle in radians by which bot misses the direction to its target
+    pub inaccuracy: f32,
+}
+
+impl Default for BotConfig {
+    fn default() -> Self {
+        Self {
+            reaction_time: Duration::from_millis(400),
+            inaccuracy: 0.1,
+        }
+    }
+}
+
+impl BotConfig {
+    pub fn validate(&self) -> Result<(), String> {
+        if !(self.inaccuracy.is_finite() && self.inaccuracy >= 0. && self.inaccuracy < PI) {
+            return Err("`inaccuracy` must be in [0, PI)".to_string());
+        }
+        Ok(())
+    }
+}
+
+/// Input chosen by bot for one movement step
+#[derive(Debug, Clone, Copy)]
+pub struct BotInput {
+    pub movement: MovementInput,
+    pub rotation: Complex,
+    pub buttons: InputButtons,
+}
+
+/// Decisions of a bot player. Bot sees the same game state as clients do
+#[derive(Debug, Default)]
+pub struct Bot {
+    /// Entity id of enemy character bot aims at
+    target: Option<u32>,
+    /// Time until bot can fire at its target
+    reaction_remaining: Duration,
+    strafe_left: bool,
+    /// Time until strafe direction is chosen again
+    strafe_remaining: Duration,
+}
+
+impl Bot {
+    /// How far ahead paths of balls are checked
+    const DANGER_LOOKAHEAD: Duration = Duration::from_millis(500);
+    /// Extra distance kept from balls and mines
+    const DANGER_MARGIN: f32 = 16.;
+    /// Bot stops approaching its target at this distance and strafes instead
+    const PREFERRED_DISTANCE: f32 = 200.;
+
+    /// Loadout requested by bot on respawn
+    pub fn choose_loadout<R: Rng>(rng: &mut R) -> Vec<PlayerWeapon> {
+        vec![PlayerWeapon::ALL[rng.random_range(0..PlayerWeapon::ALL.len())]]
+    }
+
+    /// Chooses input for the next movement step. `None` if bot has no alive character
+    pub fn think<R: Rng>(
+        &mut self,
+        game_state: &GameState,
+        player_id: NonZero<u64>,
+        dt: Duration,
+        config: &BotConfig,
+        rng: &mut R,
+    ) -> Option<BotInput> {
+        let character = game_state
+            .entities()
+            .find(|e| e.player_id == player_id && matches!(e.role, EntityRole::Character { .. }))
+            .map(|e| e.clone())
+            .filter(|e| e.health > 0.)?;
+        let walls: Vec<Segment> = game_state.map().segments().collect();
+        let visible = |pos: Point| {
+            let line = Segment {
+                p0: character.pos,
+                p1: pos,
+            };
+            !walls
+                .iter()
+                .any(|x| x.ray_cast(line).is_some_and(|r| r.intersects()))
+        };
+        let enemies: Vec<(u32, Point, bool)> = game_state
+            .entities()
+            .filter(|e| {
+                matches!(e.role, EntityRole::Character { .. })
+                    && e.player_id != player_id
+                    && e.health > 0.
+                    && !game_state.players().teammates(player_id, e.player_id)
+            })
+            .map(|e| (e.id, e.pos, visible(e.pos)))
+            .collect();
+        let distance = |pos: Point| (pos - character.pos).len();
+        let nearest = |visible_only: bool| {
+            enemies
+                .iter()
+                .filter(|(_, _, visible)| *visible || !visible_only)
+                .min_by(|a, b| distance(a.1).total_cmp(&distance(b.1)))
+                .map(|(id, pos, _)| (*id, *pos))
+        };
+
+        let target = nearest(true);
+        if target.map(|(id, _)| id) != self.target {
+            self.target = target.map(|(id, _)| id);
+            self.reaction_remaining = config.reaction_time;
+        } else {
+            self.reaction_remaining = self.reaction_remaining.saturating_sub(dt);
+        }
+        self.strafe_remaining = self.strafe_remaining.saturating_sub(dt);
+        if self.strafe_remaining.is_zero() {
+            self.strafe_left = rng.random_bool(0.5);
+            self.strafe_remaining = Duration::from_millis(rng.random_range(500..1500));
+        }
+
+        let mut rotation = character.rot;
+        let mut buttons: InputButtons = Default::default();
+        let mut direction = Vector { x: 0., y: 0. };
+        if let Some((_, pos)) = target {
+            let offset = pos - character.pos;
+            if offset.len() > 0. {
+                let error = rng.random_range(-config.inaccuracy..=config.inaccuracy);
+                rotation = offset.normalize_into_complex() * Complex::from_rad(error);
+                direction = if offset.len() > Self::PREFERRED_DISTANCE {
+                    offset.normalize()
+                } else if self.strafe_left {
+                    offset.normalize().left_perpendicular()
+                } else {
+                    offset.normalize().right_perpendicular()
+                };
+            }
+            buttons.set(InputButtons::PRIMARY, self.reaction_remaining.is_zero());
+        } else if let Some((_, pos)) = nearest(false) {
+            // Walls are slid along when bot walks into them
+            if distance(pos) > 0. {
+                direction = (pos - character.pos).normalize();
+            }
+        }
+        if let Some(escape) = Self::escape_direction(game_state, &character) {
+            direction = escape;
+        }
+
+        Some(BotInput {
+            movement: MovementInput::towards(direction),
+            rotation,
+            buttons,
+        })
+    }
+
+    /// Direction away from enemy balls which are about to hit character and from activated
+    /// enemy mines nearby
+    fn escape_direction(game_state: &GameState, character: &Entity) -> Option<Vector> {
+        let mut escape = Vector { x: 0., y: 0. };
+        for e in game_state.entities() {
+            if e.player_id == character.player_id
+                || game_state
+                    .players()
+                    .teammates(e.player_id, character.player_id)
+            {
+                continue;
+            }
+            let EntityRole::Projectile { kind } = &e.role else {
+                continue;
+            };
+            let safe_distance = character.inscribed_circle_radius()
+                + e.inscribed_circle_radius()
+                + Self::DANGER_MARGIN;
+            match kind {
+                ProjectileKind::Ball { velocity, .. } => {
+                    let direction = Vector::polar(e.rot, 1.);
+                    let lookahead = velocity * Self::DANGER_LOOKAHEAD.as_secs_f32();
+                    let t = (character.pos - e.pos).dot(direction).clamp(0., lookahead);
+                    let closest = e.pos + direction * t;
+                    let away = character.pos - closest;
+                    if away.len() < safe_distance && t > 0. {
+                        escape = escape
+                            + if away.len() > 0. {
+                                away.normalize()
+                            } else {
+                                direction.left_perpendicular()
+                            };
+                    }
+                }
+                ProjectileKind::Mine {
+                    detection_radius, ..
+                } if e.activated => {
+                    let away = character.pos - e.pos;
+                    if away.len() < detection_radius + safe_distance && away.len() > 0. {
+                        escape = escape + away.normalize();
+                    }
+                }
+                _ => {}
+            }
+        }
+        if escape.len() > 0. {
+            Some(escape.normalize())
+        } else {
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Bot, BotConfig};
+    use crate::common::{
+        fixtures, Complex, EntityCreateInfo, GameConfig, GameState, InputButtons, Map,
+        PlayerWeapon, Point, Segment,
+    };
+    use rand::rng;
+    use std::{num::NonZero, sync::Arc, time::Duration};
+
+    fn character(pos: Point) -> EntityCreateInfo {
+        fixtures::character(
+            pos,
+            GameConfig::default().character_weapon(PlayerWeapon::BallGun),
+        )
+    }
+
+    #[test]
+    fn aiming_and_dodging() {
+        let config = BotConfig {
+            reaction_time: Duration::from_millis(100),
+            inaccuracy: 0.,
+        };
+        let dt = Duration::from_millis(60);
+        let bot_id = NonZero::new(1).unwrap();
+        let enemy = NonZero::new(2).unwrap();
+        let mut rng = rng();
+
+        let mut game_state = GameState::new();
+        game_state.create(
+            character(Point { x: 100., y: 100. }),
+            bot_id,
+            &Default::default(),
+        );
+        game_state.create(
+            character(Point { x: 100., y: 200. }),
+            enemy,
+            &Default::default(),
+        );
+        let mut bot: Bot = Default::default();
+        let input = bot
+            .think(&game_state, bot_id, dt, &config, &mut rng)
+            .unwrap();
+        assert!((input.rotation.r - 0.).abs() < 1e-6 && (input.rotation.i - 1.).abs() < 1e-6);
+        assert!(!input.buttons.contains(InputButtons::PRIMARY));
+        bot.think(&game_state, bot_id, dt, &config, &mut rng);
+        let input = bot
+            .think(&game_state, bot_id, dt, &config, &mut rng)
+            .unwrap();
+        assert!(input.buttons.contains(InputButtons::PRIMARY));
+        assert!(!input.movement.up && !input.movement.down);
+
+        // Enemy behind wall is hunted but not shot at
+        game_state.set_map(Arc::new(Map {
+            walls: vec![vec![
+                Segment {
+                    p0: Point { x: 50., y: 150. },
+                    p1: Point { x: 150., y: 150. },
+                },
+                Segment {
+                    p0: Point { x: 150., y: 150. },
+                    p1: Point { x: 50., y: 150. },
+                },
+            ]],
+            ..Default::default()
+        }));
+        let input = bot
+            .think(&game_state, bot_id, dt, &config, &mut rng)
+            .unwrap();
+        assert!(!input.buttons.contains(InputButtons::PRIMARY));
+        assert!(input.movement.down);
+
+        // Ball flying from the right at bot is dodged vertically
+        let mut game_state = GameState::new();
+        game_state.create(
+            character(Point { x: 100., y: 100. }),
+            bot_id,
+            &Default::default(),
+        );
+        let mut incoming =
+            fixtures::ball_with(Point { x: 200., y: 102. }, fixtures::ball_kind(400., 1, 1.));
+        incoming.rot = Complex { r: -1., i: 0. };
+        game_state.create(incoming, enemy, &Default::default());
+        let input = bot
+            .think(&game_state, bot_id, dt, &config, &mut rng)
+            .unwrap();
+        assert!(input.movement.up && !input.movement.left && !input.movement.right);
+        assert!(bot
+            .think(&game_state, enemy, dt, &config, &mut rng)
+            .is_none());
+    }
+}
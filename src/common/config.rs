@@ -0,0 +1,608 @@
+use super::{
+    BotConfig, CharacterWeapon, FriendlyFire, HealthConfig, KingOfTheHillConfig, KnockbackConfig,
+    LoadoutConfig, Magazine, MatchRules, MovementConfig, PickupsConfig, PlayerWeapon,
+    ProjectileInteractions, ProjectileKind, SecondaryAction, Shield,
+};
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+/// Defines when characters that are already alive receive changed weapon definitions
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+pub enum WeaponUpdatePolicy {
+    Immediately,
+    OnRespawn,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct WeaponsConfig {
+    pub ball_gun: CharacterWeapon,
+    pub pulse_gun: CharacterWeapon,
+    pub ray_gun: CharacterWeapon,
+    pub shield: CharacterWeapon,
+    pub mine_gun: CharacterWeapon,
+    #[serde(default = "WeaponsConfig::default_missile_launcher")]
+    pub missile_launcher: CharacterWeapon,
+    #[serde(default = "WeaponsConfig::default_grenade_launcher")]
+    pub grenade_launcher: CharacterWeapon,
+}
+
+impl Default for WeaponsConfig {
+    fn default() -> Self {
+        Self {
+            ball_gun: CharacterWeapon::BallGun {
+                life_duration: Duration::from_secs(60),
+                owner_invincibility_duration: Duration::from_millis(200),
+                fire_interval: Duration::from_millis(1000 / 10),
+                velocity: 200.,
+                projectile_health: 1,
+                damage: 1.,
+                radius: 4.,
+                magazine: Some(Magazine {
+                    size: 30,
+                    reload_duration: Duration::from_secs(2),
+                    reserve: None,
+                }),
+                secondary: None,
+            },
+            pulse_gun: CharacterWeapon::RayGun {
+                life_duration: Duration::from_millis(4000),
+                owner_invincibility_duration: Duration::from_millis(500),
+                tail_freeze_duration: Duration::from_millis(100),
+                fire_interval: Duration::from_millis(1000),
+                velocity: 1000.,
+                projectile_health: 1,
+                damage: 1.,
+                magazine: None,
+                secondary: None,
+            },
+            ray_gun: CharacterWeapon::RayGun {
+                life_duration: Duration::from_millis(1000),
+                owner_invincibility_duration: Duration::from_millis(500),
+                tail_freeze_duration: Duration::from_millis(1000),
+                fire_interval: Duration::from_millis(2000),
+                velocity: 2000.,
+                projectile_health: 16,
+                damage: 1.,
+                magazine: None,
+                secondary: Some(SecondaryAction::ChargedShot {
+                    charge_duration: Duration::from_secs(1),
+                    max_multiplier: 2.,
+                }),
+            },
+            shield: CharacterWeapon::Shield {
+                shield: Shield {
+                    width: 48.,
+                    dst_from_character: 32.,
+                },
+                secondary: Some(SecondaryAction::SelfDestruct {
+                    hold_duration: Duration::from_secs(2),
+                }),
+            },
+            mine_gun: CharacterWeapon::MineGun {
+                fire_interval: Duration::from_secs(8),
+                life_duration: Duration::from_secs(32),
+                owner_invincibility_duration: Duration::from_secs(1000),
+                activation_duration: Duration::from_secs(2),
+                start_velocity: 500.,
+                acceleration: -100.,
+                radius: 6.,
+                detection_radius: 200.,
+                explosion_radius: 100.,
+                explosion_damage: 1.,
+                debris_kind: Box::new(ProjectileKind::Mine {
+                    life_duration: Duration::from_secs(16),
+                    owner_invincibility_duration: Duration::from_secs(2),
+                    activation_duration: Duration::from_secs(1),
+                    velocity: 500.,
+                    acceleration: -100.,
+                    radius: 4.,
+                    detection_radius: 100.,
+                    explosion_radius: 50.,
+                    explosion_damage: 1.,
+                    debris_kind: Box::new(ProjectileKind::Ray {
+                        life_duration: Duration::from_millis(4000),
+                        owner_invincibility_duration: Duration::from_millis(500),
+                        tail_freeze_duration: Duration::from_millis(100),
+                        velocity: 200.,
+                        health: 1,
+                        damage: 1.,
+                    }),
+                    debris_count: 12,
+                }),
+                debris_count: 6,
+                magazine: None,
+                secondary: Some(SecondaryAction::DetonateMines),
+            },
+            missile_launcher: Self::default_missile_launcher(),
+            grenade_launcher: Self::default_grenade_launcher(),
+        }
+    }
+}
+
+impl WeaponsConfig {
+    fn default_missile_launcher() -> CharacterWeapon {
+        CharacterWeapon::MissileLauncher {
+            fire_interval: Duration::from_secs(3),
+            life_duration: Duration::from_secs(8),
+            owner_invincibility_duration: Duration::from_millis(500),
+            start_velocity: 100.,
+            acceleration: 200.,
+            turn_rate: 3.,
+            lock_on_angle: 1.,
+            fuel_duration: Duration::from_secs(2),
+            projectile_health: 1,
+            damage: 1.,
+            radius: 6.,
+            magazine: Some(Magazine {
+                size: 2,
+                reload_duration: Duration::from_secs(5),
+                reserve: None,
+            }),
+            secondary: None,
+        }
+    }
+
+    fn default_grenade_launcher() -> CharacterWeapon {
+        CharacterWeapon::GrenadeLauncher {
+            fire_interval: Duration::from_secs(2),
+            fuse_duration: Duration::from_millis(1500),
+            start_velocity: 400.,
+            deceleration: 250.,
+            radius: 5.,
+            explosion_radius: 80.,
+            explosion_damage: 3.,
+            magazine: Some(Magazine {
+                size: 3,
+                reload_duration: Duration::from_secs(3),
+                reserve: Some(9),
+            }),
+            secondary: None,
+        }
+    }
+
+    pub fn get(&self, weapon: PlayerWeapon) -> &CharacterWeapon {
+        match weapon {
+            PlayerWeapon::BallGun => &self.ball_gun,
+            PlayerWeapon::PulseGun => &self.pulse_gun,
+            PlayerWeapon::RayGun => &self.ray_gun,
+            PlayerWeapon::Shield => &self.shield,
+            PlayerWeapon::MineGun => &self.mine_gun,
+            PlayerWeapon::MissileLauncher => &self.missile_launcher,
+            PlayerWeapon::GrenadeLauncher => &self.grenade_launcher,
+        }
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        fn expect(
+            weapon: PlayerWeapon,
+            character_weapon: &CharacterWeapon,
+            matches: bool,
+        ) -> Result<(), String> {
+            if matches {
+                validate_character_weapon(character_weapon)
+                    .map_err(|e| format!("{:?}: {}", weapon, e))
+            } else {
+                Err(format!(
+                    "{:?}: unexpected weapon kind {:?}",
+                    weapon, character_weapon
+                ))
+            }
+        }
+
+        expect(
+            PlayerWeapon::BallGun,
+            &self.ball_gun,
+            matches!(self.ball_gun, CharacterWeapon::BallGun { .. }),
+        )?;
+        expect(
+            PlayerWeapon::PulseGun,
+            &self.pulse_gun,
+            matches!(self.pulse_gun, CharacterWeapon::RayGun { .. }),
+        )?;
+        expect(
+            PlayerWeapon::RayGun,
+            &self.ray_gun,
+            matches!(self.ray_gun, CharacterWeapon::RayGun { .. }),
+        )?;
+        expect(
+            PlayerWeapon::Shield,
+            &self.shield,
+            matches!(self.shield, CharacterWeapon::Shield { .. }),
+        )?;
+        expect(
+            PlayerWeapon::MineGun,
+            &self.mine_gun,
+            matches!(self.mine_gun, CharacterWeapon::MineGun { .. }),
+        )?;
+        expect(
+            PlayerWeapon::MissileLauncher,
+            &self.missile_launcher,
+            matches!(
+                self.missile_launcher,
+                CharacterWeapon::MissileLauncher { .. }
+            ),
+        )?;
+        expect(
+            PlayerWeapon::GrenadeLauncher,
+            &self.grenade_launcher,
+            matches!(
+                self.grenade_launcher,
+                CharacterWeapon::GrenadeLauncher { .. }
+            ),
+        )
+    }
+}
+
+fn validate_positive(name: &str, value: f32) -> Result<(), String> {
+    if value.is_finite() && value > 0. {
+        Ok(())
+    } else {
+        Err(format!("`{}` must be positive, got {}", name, value))
+    }
+}
+
+fn validate_non_zero(name: &str, value: Duration) -> Result<(), String> {
+    if value.is_zero() {
+        Err(format!("`{}` must not be zero", name))
+    } else {
+        Ok(())
+    }
+}
+
+fn validate_character_weapon(weapon: &CharacterWeapon) -> Result<(), String> {
+    if let Some(magazine) = weapon.magazine() {
+        magazine
+            .validate()
+            .map_err(|e| format!("magazine: {}", e))?;
+    }
+    match weapon.secondary() {
+        Some(SecondaryAction::ChargedShot {
+            charge_duration,
+            max_multiplier,
+        }) => {
+            if !matches!(weapon, CharacterWeapon::RayGun { .. }) {
+                return Err("`ChargedShot` is supported by ray guns only".to_string());
+            }
+            validate_non_zero("secondary.charge_duration", *charge_duration)?;
+            if !(max_multiplier.is_finite() && *max_multiplier >= 1.) {
+                return Err("`secondary.max_multiplier` must be at least 1".to_string());
+            }
+        }
+        Some(SecondaryAction::SelfDestruct { .. } | SecondaryAction::DetonateMines) | None => {}
+    }
+    match weapon {
+        CharacterWeapon::BallGun {
+            fire_interval,
+            velocity,
+            projectile_health,
+            damage,
+            radius,
+            ..
+        } => {
+            validate_non_zero("fire_interval", *fire_interval)?;
+            validate_positive("velocity", *velocity)?;
+            validate_positive("projectile_health", *projectile_health as f32)?;
+            validate_positive("damage", *damage)?;
+            validate_positive("radius", *radius)
+        }
+        CharacterWeapon::RayGun {
+            fire_interval,
+            velocity,
+            projectile_health,
+            damage,
+            ..
+        } => {
+            validate_non_zero("fire_interval", *fire_interval)?;
+            validate_positive("velocity", *velocity)?;
+            validate_positive("projectile_health", *projectile_health as f32)?;
+            validate_positive("damage", *damage)
+        }
+        CharacterWeapon::Shield { shield, .. } => {
+            validate_positive("shield.width", shield.width)?;
+            validate_positive("shield.dst_from_character", shield.dst_from_character)
+        }
+        CharacterWeapon::MineGun {
+            fire_interval,
+            radius,
+            detection_radius,
+            explosion_radius,
+            debris_kind,
+            ..
+        } => {
+            validate_non_zero("fire_interval", *fire_interval)?;
+            validate_positive("radius", *radius)?;
+            validate_positive("detection_radius", *detection_radius)?;
+            validate_positive("explosion_radius", *explosion_radius)?;
+            validate_projectile_kind(debris_kind).map_err(|e| format!("debris_kind: {}", e))
+        }
+        CharacterWeapon::MissileLauncher {
+            fire_interval,
+            start_velocity,
+            turn_rate,
+            lock_on_angle,
+            projectile_health,
+            damage,
+            radius,
+            ..
+        } => {
+            validate_non_zero("fire_interval", *fire_interval)?;
+            validate_positive("start_velocity", *start_velocity)?;
+            validate_positive("turn_rate", *turn_rate)?;
+            validate_positive("lock_on_angle", *lock_on_angle)?;
+            validate_positive("projectile_health", *projectile_health as f32)?;
+            validate_positive("damage", *damage)?;
+            validate_positive("radius", *radius)
+        }
+        CharacterWeapon::GrenadeLauncher {
+            fire_interval,
+            start_velocity,
+            radius,
+            explosion_radius,
+            explosion_damage,
+            ..
+        } => {
+            validate_non_zero("fire_interval", *fire_interval)?;
+            validate_positive("start_velocity", *start_velocity)?;
+            validate_positive("radius", *radius)?;
+            validate_positive("explosion_radius", *explosion_radius)?;
+            validate_positive("explosion_damage", *explosion_damage)
+        }
+    }
+}
+
+fn validate_projectile_kind(kind: &ProjectileKind) -> Result<(), String> {
+    match kind {
+        ProjectileKind::Ball {
+            velocity,
+            health,
+            damage,
+            radius,
+            ..
+        } => {
+            validate_positive("velocity", *velocity)?;
+            validate_positive("health", *health as f32)?;
+            validate_positive("damage", *damage)?;
+            validate_positive("radius", *radius)
+        }
+        ProjectileKind::Ray {
+            velocity,
+            health,
+            damage,
+            ..
+        } => {
+            validate_positive("velocity", *velocity)?;
+            validate_positive("health", *health as f32)?;
+            validate_positive("damage", *damage)
+        }
+        ProjectileKind::Mine {
+            radius,
+            detection_radius,
+            explosion_radius,
+            debris_kind,
+            ..
+        } => {
+            validate_positive("radius", *radius)?;
+            validate_positive("detection_radius", *detection_radius)?;
+            validate_positive("explosion_radius", *explosion_radius)?;
+            validate_projectile_kind(debris_kind).map_err(|e| format!("debris_kind: {}", e))
+        }
+        ProjectileKind::Missile {
+            velocity,
+            turn_rate,
+            lock_on_angle,
+            health,
+            damage,
+            radius,
+            ..
+        } => {
+            validate_positive("velocity", *velocity)?;
+            validate_positive("turn_rate", *turn_rate)?;
+            validate_positive("lock_on_angle", *lock_on_angle)?;
+            validate_positive("health", *health as f32)?;
+            validate_positive("damage", *damage)?;
+            validate_positive("radius", *radius)
+        }
+        ProjectileKind::Grenade {
+            velocity,
+            radius,
+            explosion_radius,
+            explosion_damage,
+            ..
+        } => {
+            validate_positive("velocity", *velocity)?;
+            validate_positive("radius", *radius)?;
+            validate_positive("explosion_radius", *explosion_radius)?;
+            validate_positive("explosion_damage", *explosion_damage)
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+pub enum GameMode {
+    #[default]
+    FreeForAll,
+    /// Players are assigned to teams on join
+    TeamDeathmatch,
+    /// Teams score by bringing enemy flag to own base
+    CaptureTheFlag,
+    /// Team or player standing in zone alone scores points
+    KingOfTheHill { teams: bool },
+}
+
+impl GameMode {
+    pub fn has_teams(self) -> bool {
+        match self {
+            GameMode::FreeForAll => false,
+            GameMode::TeamDeathmatch => true,
+            GameMode::CaptureTheFlag => true,
+            GameMode::KingOfTheHill { teams } => teams,
+        }
+    }
+
+    /// Points which team gets for killing an enemy
+    pub fn kill_team_points(self) -> i64 {
+        match self {
+            GameMode::TeamDeathmatch => 1,
+            _ => 0,
+        }
+    }
+}
+
+/// Gameplay tuning values. Loaded by server from config file and sent to clients
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct GameConfig {
+    pub weapon_update_policy: WeaponUpdatePolicy,
+    pub weapons: WeaponsConfig,
+    #[serde(default)]
+    pub match_rules: MatchRules,
+    /// Applied to players which join after mode is changed
+    #[serde(default)]
+    pub mode: GameMode,
+    #[serde(default)]
+    pub friendly_fire: FriendlyFire,
+    #[serde(default)]
+    pub king_of_the_hill: KingOfTheHillConfig,
+    /// Time after spawn during which character can not be damaged
+    #[serde(default = "GameConfig::default_spawn_protection")]
+    pub spawn_protection: Duration,
+    #[serde(default)]
+    pub pickups: PickupsConfig,
+    #[serde(default)]
+    pub projectile_interactions: ProjectileInteractions,
+    #[serde(default)]
+    pub loadout: LoadoutConfig,
+    #[serde(default)]
+    pub movement: MovementConfig,
+    #[serde(default)]
+    pub knockback: KnockbackConfig,
+    #[serde(default)]
+    pub health: HealthConfig,
+    /// Behaviour of bots started with the server
+    #[serde(default)]
+    pub bots: BotConfig,
+}
+
+impl Default for GameConfig {
+    fn default() -> Self {
+        Self {
+            weapon_update_policy: WeaponUpdatePolicy::OnRespawn,
+            weapons: Default::default(),
+            match_rules: Default::default(),
+            mode: Default::default(),
+            friendly_fire: Default::default(),
+            king_of_the_hill: Default::default(),
+            spawn_protection: Self::default_spawn_protection(),
+            pickups: Default::default(),
+            projectile_interactions: Default::default(),
+            loadout: Default::default(),
+            movement: Default::default(),
+            knockback: Default::default(),
+            health: Default::default(),
+            bots: Default::default(),
+        }
+    }
+}
+
+impl GameConfig {
+    fn default_spawn_protection() -> Duration {
+        Duration::from_secs(2)
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        self.weapons.validate()?;
+        self.match_rules
+            .validate()
+            .map_err(|e| format!("match_rules: {}", e))?;
+        self.king_of_the_hill
+            .validate()
+            .map_err(|e| format!("king_of_the_hill: {}", e))?;
+        self.pickups
+            .validate()
+            .map_err(|e| format!("pickups: {}", e))?;
+        self.projectile_interactions
+            .validate()
+            .map_err(|e| format!("projectile_interactions: {}", e))?;
+        self.loadout
+            .validate()
+            .map_err(|e| format!("loadout: {}", e))?;
+        self.movement
+            .validate()
+            .map_err(|e| format!("movement: {}", e))?;
+        self.knockback
+            .validate()
+            .map_err(|e| format!("knockback: {}", e))?;
+        self.health
+            .validate()
+            .map_err(|e| format!("health: {}", e))?;
+        self.friendly_fire
+            .validate()
+            .map_err(|e| format!("friendly_fire: {}", e))?;
+        self.bots.validate().map_err(|e| format!("bots: {}", e))
+    }
+
+    pub fn character_weapon(&self, weapon: PlayerWeapon) -> CharacterWeapon {
+        self.weapons.get(weapon).clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{CharacterWeapon, GameConfig, SecondaryAction, Shield};
+    use std::time::Duration;
+
+    #[test]
+    fn default_config_round_trip() {
+        let config: GameConfig = Default::default();
+        config.validate().unwrap();
+
+        let data = serde_json::to_vec(&config).unwrap();
+        let parsed: GameConfig = serde_json::from_slice(&data).unwrap();
+        assert_eq!(parsed, config);
+    }
+
+    #[test]
+    fn shield_without_secondary_self_destructs() {
+        let data = r#"{"Shield":{"shield":{"width":48.0,"dst_from_character":32.0},"self_destruct_timeout":{"secs":2,"nanos":0}}}"#;
+        let shield: CharacterWeapon = serde_json::from_str(data).unwrap();
+        assert_eq!(
+            shield.secondary(),
+            Some(&SecondaryAction::SelfDestruct {
+                hold_duration: Duration::from_secs(2)
+            })
+        );
+    }
+
+    #[test]
+    fn invalid_config_rejected() {
+        let mut config: GameConfig = Default::default();
+        config.weapons.ball_gun = CharacterWeapon::BallGun {
+            life_duration: Duration::from_secs(1),
+            owner_invincibility_duration: Duration::from_secs(1),
+            fire_interval: Duration::ZERO,
+            velocity: 100.,
+            projectile_health: 1,
+            damage: 1.,
+            radius: 4.,
+            magazine: None,
+            secondary: None,
+        };
+        assert!(config.validate().is_err());
+
+        let mut config: GameConfig = Default::default();
+        config.weapons.shield = CharacterWeapon::Shield {
+            shield: Shield {
+                width: 48.,
+                dst_from_character: 32.,
+            },
+            secondary: Some(SecondaryAction::ChargedShot {
+                charge_duration: Duration::from_secs(1),
+                max_multiplier: 2.,
+            }),
+        };
+        assert!(config.validate().is_err());
+
+        let mut config: GameConfig = Default::default();
+        config.weapons.shield = config.weapons.ball_gun.clone();
+        assert!(config.validate().is_err());
+    }
+}
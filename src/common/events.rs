@@ -0,0 +1,200 @@
+use super::{PlayerInfo, PlayerWeapon, Point, Team, ZoneHolder};
+use serde::{Deserialize, Serialize};
+use std::{collections::VecDeque, num::NonZero};
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub enum GameEvent {
+    Kill {
+        killer: NonZero<u64>,
+        victim: NonZero<u64>,
+        weapon: PlayerWeapon,
+    },
+    /// Character of `player_id` lost `amount` of health and armor
+    Damage {
+        player_id: NonZero<u64>,
+        attacker: NonZero<u64>,
+        amount: f32,
+    },
+    Suicide {
+        player_id: NonZero<u64>,
+    },
+    Join {
+        player_id: NonZero<u64>,
+    },
+    /// Contains player info because player is already removed from registry when event is received
+    Leave {
+        player: PlayerInfo,
+    },
+    MineDetonation {
+        player_id: NonZero<u64>,
+        pos: Point,
+    },
+    /// Shown as an effect instead of a feed entry
+    Explosion {
+        player_id: NonZero<u64>,
+        pos: Point,
+        radius: f32,
+    },
+    FlagTaken {
+        player_id: NonZero<u64>,
+        team: Team,
+    },
+    FlagDropped {
+        team: Team,
+    },
+    FlagReturned {
+        player_id: NonZero<u64>,
+        team: Team,
+    },
+    FlagCaptured {
+        player_id: NonZero<u64>,
+        team: Team,
+    },
+    ZoneCaptured {
+        holder: ZoneHolder,
+    },
+    ZoneMoved,
+    RoundStarted {
+        round: u32,
+    },
+    RoundEnded {
+        winner: Option<NonZero<u64>>,
+        winning_team: Option<Team>,
+    },
+}
+
+/// Recent game events. Each event has an id so that every connection can send each event exactly once
+#[derive(Debug, Clone, Default)]
+pub struct EventLog {
+    next_id: u64,
+    events: VecDeque<(u64, GameEvent)>,
+}
+
+impl EventLog {
+    const CAPACITY: usize = 256;
+
+    pub fn push(&mut self, event: GameEvent) {
+        if self.events.len() == Self::CAPACITY {
+            self.events.pop_front();
+        }
+        self.events.push_back((self.next_id, event));
+        self.next_id += 1;
+    }
+
+    /// Id which will be assigned to the next pushed event
+    pub fn next_id(&self) -> u64 {
+        self.next_id
+    }
+
+    /// Events with ids greater or equal to `id`
+    pub fn since(&self, id: u64) -> impl Iterator<Item = &GameEvent> {
+        self.events
+            .iter()
+            .filter(move |(event_id, _)| *event_id >= id)
+            .map(|(_, event)| event)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{EventLog, GameEvent};
+    use crate::common::fixtures::{ball, character};
+    use crate::common::{GameConfig, GameState, PlayerWeapon, Point};
+    use rand::rng;
+    use std::{num::NonZero, time::Duration};
+
+    fn zone_moved_count(events: &EventLog, id: u64) -> usize {
+        events
+            .since(id)
+            .filter(|e| matches!(e, GameEvent::ZoneMoved))
+            .count()
+    }
+
+    #[test]
+    fn since() {
+        let mut events = EventLog::default();
+        assert_eq!(events.since(0).count(), 0);
+        for _ in 0..3 {
+            events.push(GameEvent::ZoneMoved);
+        }
+        assert_eq!(events.next_id(), 3);
+        assert_eq!(zone_moved_count(&events, 0), 3);
+        assert_eq!(zone_moved_count(&events, 2), 1);
+        assert_eq!(zone_moved_count(&events, 3), 0);
+
+        for _ in 0..EventLog::CAPACITY {
+            events.push(GameEvent::ZoneMoved);
+        }
+        assert_eq!(events.next_id(), EventLog::CAPACITY as u64 + 3);
+        assert_eq!(zone_moved_count(&events, 0), EventLog::CAPACITY);
+        assert_eq!(zone_moved_count(&events, events.next_id() - 1), 1);
+    }
+
+    #[test]
+    fn kill_cause() {
+        let config = GameConfig {
+            spawn_protection: Duration::ZERO,
+            ..Default::default()
+        };
+        let killer = NonZero::new(1).unwrap();
+        let victim = NonZero::new(2).unwrap();
+        let pos = Point { x: 100., y: 100. };
+        let mut game_state = GameState::new();
+        game_state.create(
+            character(pos, config.character_weapon(PlayerWeapon::BallGun)),
+            victim,
+            &config.health,
+        );
+        game_state
+            .find_character_by_player_id_mut(victim)
+            .unwrap()
+            .health = 1.;
+        game_state.create(ball(pos), killer, &config.health);
+        let id = game_state.events().next_id();
+
+        game_state.proceed(Duration::from_millis(10), &config, &mut rng());
+
+        let cause = game_state.account_kill(victim).unwrap();
+        assert_eq!(cause.killer, killer);
+        assert_eq!(cause.weapon, PlayerWeapon::BallGun);
+        assert!(game_state.events().since(id).any(|e| matches!(
+            e,
+            GameEvent::Kill { killer: k, victim: v, weapon: PlayerWeapon::BallGun }
+                if *k == killer && *v == victim
+        )));
+        assert!(game_state.account_kill(victim).is_none());
+    }
+
+    #[test]
+    fn suicide_cause() {
+        let config = GameConfig::default();
+        let player_id = NonZero::new(1).unwrap();
+        let mut game_state = GameState::new();
+        game_state.create(
+            character(
+                Point { x: 100., y: 100. },
+                config.character_weapon(PlayerWeapon::MineGun),
+            ),
+            player_id,
+            &config.health,
+        );
+        let id = game_state.entities().next().unwrap().id;
+        let event_id = game_state.events().next_id();
+
+        game_state.register_suicide(id, player_id, PlayerWeapon::MineGun);
+        // Character which is already killed is ignored
+        game_state.register_suicide(id, player_id, PlayerWeapon::BallGun);
+
+        let cause = game_state.account_kill(player_id).unwrap();
+        assert_eq!(cause.killer, player_id);
+        assert_eq!(cause.weapon, PlayerWeapon::MineGun);
+        assert_eq!(
+            game_state
+                .events()
+                .since(event_id)
+                .filter(|e| matches!(e, GameEvent::Suicide { player_id: p } if *p == player_id))
+                .count(),
+            1
+        );
+    }
+}
@@ -0,0 +1,154 @@
+//! Entities shared by unit tests of game state and bots
+
+use super::{
+    CharacterWeapon, Color, Complex, EntityCreateInfo, EntityRole, EntityTail, GameConfig,
+    PlayerWeapon, Point, ProjectileKind,
+};
+use std::time::Duration;
+
+pub(crate) fn white() -> Color {
+    Color {
+        a: 255,
+        r: 255,
+        g: 255,
+        b: 255,
+    }
+}
+
+/// Long living ball with given speed, hit points and damage of each hit
+pub(crate) fn ball_kind(velocity: f32, health: u8, damage: f32) -> ProjectileKind {
+    ProjectileKind::Ball {
+        life_duration: Duration::from_secs(60),
+        owner_invincibility_duration: Duration::ZERO,
+        velocity,
+        health,
+        damage,
+        radius: 4.,
+    }
+}
+
+/// Resting ball with one hit point and damage
+pub(crate) fn ball(pos: Point) -> EntityCreateInfo {
+    ball_with(pos, ball_kind(0., 1, 1.))
+}
+
+pub(crate) fn ball_with(pos: Point, kind: ProjectileKind) -> EntityCreateInfo {
+    EntityCreateInfo {
+        pos,
+        rot: Complex { r: 1., i: 0. },
+        color: white(),
+        role: EntityRole::Projectile { kind },
+        player_weapon: PlayerWeapon::BallGun,
+        tail: None,
+    }
+}
+
+pub(crate) fn character(pos: Point, weapon: CharacterWeapon) -> EntityCreateInfo {
+    EntityCreateInfo {
+        pos,
+        rot: Complex { r: 1., i: 0. },
+        color: white(),
+        role: EntityRole::Character { weapon },
+        player_weapon: PlayerWeapon::BallGun,
+        tail: None,
+    }
+}
+
+pub(crate) fn mine(pos: Point) -> EntityCreateInfo {
+    EntityCreateInfo {
+        role: EntityRole::Projectile {
+            kind: ProjectileKind::Mine {
+                life_duration: Duration::from_secs(60),
+                owner_invincibility_duration: Duration::ZERO,
+                activation_duration: Duration::from_secs(60),
+                velocity: 0.,
+                acceleration: 0.,
+                radius: 6.,
+                detection_radius: 10.,
+                explosion_radius: 10.,
+                explosion_damage: 0.,
+                debris_kind: Box::new(ball_kind(0., 1, 1.)),
+                debris_count: 4,
+            },
+        },
+        player_weapon: PlayerWeapon::MineGun,
+        ..ball(pos)
+    }
+}
+
+pub(crate) fn ray(pos: Point) -> EntityCreateInfo {
+    EntityCreateInfo {
+        role: EntityRole::Projectile {
+            kind: ProjectileKind::Ray {
+                life_duration: Duration::from_secs(1),
+                owner_invincibility_duration: Duration::ZERO,
+                tail_freeze_duration: Duration::from_secs(1),
+                velocity: 2000.,
+                health: 1,
+                damage: 1.,
+            },
+        },
+        player_weapon: PlayerWeapon::PulseGun,
+        tail: Some(EntityTail {
+            end: pos,
+            rotation: Complex { r: 1., i: 0. },
+            reflection_points: Default::default(),
+        }),
+        ..ball(pos)
+    }
+}
+
+/// Missile fired from default missile launcher
+pub(crate) fn missile(pos: Point) -> EntityCreateInfo {
+    let CharacterWeapon::MissileLauncher {
+        life_duration,
+        owner_invincibility_duration,
+        start_velocity,
+        acceleration,
+        turn_rate,
+        lock_on_angle,
+        fuel_duration,
+        projectile_health,
+        damage,
+        radius,
+        ..
+    } = GameConfig::default().character_weapon(PlayerWeapon::MissileLauncher)
+    else {
+        panic!("Unexpected weapon");
+    };
+    EntityCreateInfo {
+        role: EntityRole::Projectile {
+            kind: ProjectileKind::Missile {
+                life_duration,
+                owner_invincibility_duration,
+                velocity: start_velocity,
+                acceleration,
+                turn_rate,
+                lock_on_angle,
+                fuel_duration,
+                health: projectile_health,
+                damage,
+                radius,
+            },
+        },
+        player_weapon: PlayerWeapon::MissileLauncher,
+        ..ball(pos)
+    }
+}
+
+pub(crate) fn grenade(pos: Point) -> EntityCreateInfo {
+    EntityCreateInfo {
+        role: EntityRole::Projectile {
+            kind: ProjectileKind::Grenade {
+                fuse_duration: Duration::from_secs(60),
+                velocity: 0.,
+                deceleration: 0.,
+                radius: 5.,
+                explosion_radius: 80.,
+                explosion_damage: 1.,
+            },
+        },
+        player_weapon: PlayerWeapon::GrenadeLauncher,
+        ..ball(pos)
+    }
+}
@@ -1,4 +1,4 @@
-use rand::Rng;
+use rand::{seq::SliceRandom as _, Rng};
 use serde::{Deserialize, Serialize};
 use std::{
     cell::{Ref, RefCell, RefMut},
@@ -6,23 +6,28 @@ use std::{
     f32::consts::PI,
     num::NonZero,
     ops::{Deref, DerefMut},
-    time::{Duration, Instant},
+    sync::Arc,
+    time::Duration,
 };
 
 use super::{
-    Collide as _, Complex, DynSizeSegments as _, Point, Rect, Segment, Segments, Vector, I,
+    Buff, Collide as _, Complex, DynSizeSegments as _, EventLog, FriendlyFire, GameConfig,
+    GameEvent, GameMode, HealthConfig, InteractionEffect, Magazine, Map, MatchState,
+    MatchTransition, MovementConfig, MovementInput, MovementState, PickupKind, PickupsConfig,
+    PlayerInfo, PlayerRegistry, PlayerWeapon, Point, ProjectileClass, ProjectileInteractions, Rect,
+    Scoreboard, Segment, Segments, Team, Vector, WeaponState, Zone, ZoneHolder, I, MOVEMENT_STEP,
 };
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
-pub(crate) struct Color {
-    pub(crate) a: u8,
-    pub(crate) r: u8,
-    pub(crate) g: u8,
-    pub(crate) b: u8,
+pub struct Color {
+    pub a: u8,
+    pub r: u8,
+    pub g: u8,
+    pub b: u8,
 }
 
 impl Color {
-    pub(crate) fn with_a(self, a: u8) -> Self {
+    pub fn with_a(self, a: u8) -> Self {
         Self {
             a,
             r: self.r,
@@ -31,7 +36,7 @@ impl Color {
         }
     }
 
-    pub(crate) fn with_r(self, r: u8) -> Self {
+    pub fn with_r(self, r: u8) -> Self {
         Self {
             a: self.a,
             r,
@@ -42,13 +47,13 @@ impl Color {
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
-pub(crate) struct Shield {
-    pub(crate) width: f32,
-    pub(crate) dst_from_character: f32,
+pub struct Shield {
+    pub width: f32,
+    pub dst_from_character: f32,
 }
 
 impl Shield {
-    pub(crate) fn segment(&self, character_pos: Point, character_rot: Complex) -> Segment {
+    pub fn segment(&self, character_pos: Point, character_rot: Complex) -> Segment {
         let c = character_pos + Vector::polar(character_rot, self.dst_from_character);
         Segment {
             p0: c + Vector::polar(character_rot * (-I), self.width / 2.),
@@ -58,14 +63,21 @@ impl Shield {
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
-pub(crate) enum CharacterWeapon {
+pub enum CharacterWeapon {
     BallGun {
         life_duration: Duration,
         owner_invincibility_duration: Duration,
         fire_interval: Duration,
         velocity: f32,
         projectile_health: u8,
+        /// Damage of each hit of projectile
+        #[serde(default = "ProjectileKind::default_damage")]
+        damage: f32,
         radius: f32,
+        #[serde(default)]
+        magazine: Option<Magazine>,
+        #[serde(default)]
+        secondary: Option<SecondaryAction>,
     },
     RayGun {
         life_duration: Duration,
@@ -74,10 +86,19 @@ pub(crate) enum CharacterWeapon {
         fire_interval: Duration,
         velocity: f32,
         projectile_health: u8,
+        /// Damage of each hit of projectile
+        #[serde(default = "ProjectileKind::default_damage")]
+        damage: f32,
+        #[serde(default)]
+        magazine: Option<Magazine>,
+        #[serde(default)]
+        secondary: Option<SecondaryAction>,
     },
     Shield {
         shield: Shield,
-        self_destruct_timeout: Duration,
+        /// Configs written before secondary actions self-destruct after holding for 2 seconds
+        #[serde(default = "CharacterWeapon::default_shield_secondary")]
+        secondary: Option<SecondaryAction>,
     },
     MineGun {
         fire_interval: Duration,
@@ -89,18 +110,130 @@ pub(crate) enum CharacterWeapon {
         radius: f32,
         detection_radius: f32,
         explosion_radius: f32,
+        #[serde(default)]
+        explosion_damage: f32,
         debris_kind: Box<ProjectileKind>,
         debris_count: u8,
+        #[serde(default)]
+        magazine: Option<Magazine>,
+        #[serde(default)]
+        secondary: Option<SecondaryAction>,
     },
+    MissileLauncher {
+        fire_interval: Duration,
+        life_duration: Duration,
+        owner_invincibility_duration: Duration,
+        start_velocity: f32,
+        acceleration: f32,
+        turn_rate: f32,
+        lock_on_angle: f32,
+        fuel_duration: Duration,
+        projectile_health: u8,
+        /// Damage of each hit of projectile
+        #[serde(default = "ProjectileKind::default_damage")]
+        damage: f32,
+        radius: f32,
+        #[serde(default)]
+        magazine: Option<Magazine>,
+        #[serde(default)]
+        secondary: Option<SecondaryAction>,
+    },
+    GrenadeLauncher {
+        fire_interval: Duration,
+        fuse_duration: Duration,
+        start_velocity: f32,
+        deceleration: f32,
+        radius: f32,
+        explosion_radius: f32,
+        explosion_damage: f32,
+        #[serde(default)]
+        magazine: Option<Magazine>,
+        #[serde(default)]
+        secondary: Option<SecondaryAction>,
+    },
+}
+
+impl CharacterWeapon {
+    fn default_shield_secondary() -> Option<SecondaryAction> {
+        Some(SecondaryAction::SelfDestruct {
+            hold_duration: Duration::from_secs(2),
+        })
+    }
+
+    pub fn fire_interval(&self) -> Option<Duration> {
+        match self {
+            CharacterWeapon::BallGun { fire_interval, .. } => Some(*fire_interval),
+            CharacterWeapon::RayGun { fire_interval, .. } => Some(*fire_interval),
+            CharacterWeapon::Shield { .. } => None,
+            CharacterWeapon::MineGun { fire_interval, .. } => Some(*fire_interval),
+            CharacterWeapon::MissileLauncher { fire_interval, .. } => Some(*fire_interval),
+            CharacterWeapon::GrenadeLauncher { fire_interval, .. } => Some(*fire_interval),
+        }
+    }
+
+    pub fn magazine(&self) -> Option<&Magazine> {
+        match self {
+            CharacterWeapon::BallGun { magazine, .. }
+            | CharacterWeapon::RayGun { magazine, .. }
+            | CharacterWeapon::MineGun { magazine, .. }
+            | CharacterWeapon::MissileLauncher { magazine, .. }
+            | CharacterWeapon::GrenadeLauncher { magazine, .. } => magazine.as_ref(),
+            CharacterWeapon::Shield { .. } => None,
+        }
+    }
+
+    pub fn secondary(&self) -> Option<&SecondaryAction> {
+        match self {
+            CharacterWeapon::BallGun { secondary, .. }
+            | CharacterWeapon::RayGun { secondary, .. }
+            | CharacterWeapon::Shield { secondary, .. }
+            | CharacterWeapon::MineGun { secondary, .. }
+            | CharacterWeapon::MissileLauncher { secondary, .. }
+            | CharacterWeapon::GrenadeLauncher { secondary, .. } => secondary.as_ref(),
+        }
+    }
+}
+
+/// Performed by weapon on secondary fire
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub enum SecondaryAction {
+    /// Character bursts into a ring of balls once button is held for `hold_duration`
+    SelfDestruct { hold_duration: Duration },
+    /// All mines of the player detonate when button is pressed
+    DetonateMines,
+    /// Ray gun shot fired when button is released. Health and velocity of the ray
+    /// grow with time button is held up to `max_multiplier` after `charge_duration`
+    ChargedShot {
+        charge_duration: Duration,
+        max_multiplier: f32,
+    },
+}
+
+impl SecondaryAction {
+    /// Multiplier of charged shot after button is held for `held`
+    pub fn charge_multiplier(
+        charge_duration: Duration,
+        max_multiplier: f32,
+        held: Duration,
+    ) -> f32 {
+        if charge_duration.is_zero() {
+            max_multiplier
+        } else {
+            1. + (max_multiplier - 1.) * held.div_duration_f32(charge_duration).min(1.)
+        }
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
-pub(crate) enum ProjectileKind {
+pub enum ProjectileKind {
     Ball {
         life_duration: Duration,
         owner_invincibility_duration: Duration,
         velocity: f32,
+        /// Number of hits projectile survives
         health: u8,
+        #[serde(default = "ProjectileKind::default_damage")]
+        damage: f32,
         radius: f32,
     },
     Ray {
@@ -109,6 +242,8 @@ pub(crate) enum ProjectileKind {
         tail_freeze_duration: Duration,
         velocity: f32,
         health: u8,
+        #[serde(default = "ProjectileKind::default_damage")]
+        damage: f32,
     },
     Mine {
         life_duration: Duration,
@@ -119,67 +254,242 @@ pub(crate) enum ProjectileKind {
         radius: f32,
         detection_radius: f32,
         explosion_radius: f32,
+        /// Damage at the center of explosion
+        #[serde(default)]
+        explosion_damage: f32,
         debris_kind: Box<ProjectileKind>,
         debris_count: u8,
     },
+    /// Steers toward the nearest enemy character in lock-on cone while it has fuel
+    Missile {
+        life_duration: Duration,
+        owner_invincibility_duration: Duration,
+        velocity: f32,
+        /// Velocity change per second while missile has fuel
+        acceleration: f32,
+        /// Max rotation speed in radians per second
+        turn_rate: f32,
+        /// Half angle of cone in front of missile in which target is picked, in radians
+        lock_on_angle: f32,
+        fuel_duration: Duration,
+        health: u8,
+        #[serde(default = "ProjectileKind::default_damage")]
+        damage: f32,
+        radius: f32,
+    },
+    /// Bounces until fuse burns out and then explodes
+    Grenade {
+        fuse_duration: Duration,
+        velocity: f32,
+        /// Velocity loss per second
+        deceleration: f32,
+        radius: f32,
+        explosion_radius: f32,
+        explosion_damage: f32,
+    },
+}
+
+impl ProjectileKind {
+    fn default_damage() -> f32 {
+        1.
+    }
+
+    /// Damage to character hit by projectile
+    pub fn damage(&self) -> f32 {
+        match self {
+            ProjectileKind::Ball { damage, .. }
+            | ProjectileKind::Ray { damage, .. }
+            | ProjectileKind::Missile { damage, .. } => *damage,
+            ProjectileKind::Mine { .. } | ProjectileKind::Grenade { .. } => 0.,
+        }
+    }
+
+    pub fn class(&self) -> ProjectileClass {
+        match self {
+            ProjectileKind::Ball { .. } => ProjectileClass::Ball,
+            ProjectileKind::Ray { .. } => ProjectileClass::Ray,
+            ProjectileKind::Mine { .. } => ProjectileClass::Mine,
+            ProjectileKind::Missile { .. } => ProjectileClass::Missile,
+            ProjectileKind::Grenade { .. } => ProjectileClass::Grenade,
+        }
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
-pub(crate) enum EntityRole {
-    Character { weapon: CharacterWeapon },
-    Projectile { kind: ProjectileKind },
+pub enum EntityRole {
+    Character {
+        weapon: CharacterWeapon,
+    },
+    Projectile {
+        kind: ProjectileKind,
+    },
+    /// Capture the flag mode flag. Owned by `WORLD_PLAYER_ID`
+    Flag {
+        team: Team,
+        home: Point,
+        carrier: Option<NonZero<u64>>,
+    },
+    /// Collected by character which touches it
+    Pickup {
+        kind: PickupKind,
+    },
 }
 
+/// Count of random points considered for spawn if map has no spawn points
+const SPAWN_CANDIDATE_COUNT: usize = 16;
+
+/// Random points tried before falling back when walls cover most of the map
+const RANDOM_POINT_ATTEMPTS: usize = 64;
+
+/// Time during which projectile path is considered dangerous for spawn
+const SPAWN_LOOKAHEAD: Duration = Duration::from_secs(1);
+
+/// Owner of entities which do not belong to any player
+pub const WORLD_PLAYER_ID: NonZero<u64> = NonZero::<u64>::MAX;
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
-pub(crate) struct EntityTail {
-    pub(crate) end: Point,
-    pub(crate) rotation: Complex,
-    pub(crate) reflection_points: VecDeque<Point>,
+pub struct EntityTail {
+    pub end: Point,
+    pub rotation: Complex,
+    pub reflection_points: VecDeque<Point>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
-pub(crate) struct Entity {
-    pub(crate) id: u32,
-    pub(crate) player_id: NonZero<u64>,
+pub struct Entity {
+    pub id: u32,
+    pub player_id: NonZero<u64>,
+    /// Time since creation or respawn in game state time. Only tracked by server
+    #[serde(skip)]
+    pub age: Duration,
+    pub pos: Point,
+    pub rot: Complex,
+    pub color: Color,
+    pub role: EntityRole,
+    /// Weapon chosen by player for characters or weapon which produced projectile
+    pub player_weapon: PlayerWeapon,
+    /// Remaining hit points. Projectiles lose one point per hit
+    pub health: f32,
+    /// Absorbs damage before health
+    pub armor: f32,
+    /// Time until health regeneration starts
+    pub regen_cooldown: Duration,
+    pub tail: Option<EntityTail>,
+    pub activated: bool,
+    /// Character has spawn protection and can not be damaged
+    pub protected: bool,
+    /// Active effects of collected pickups
+    pub buffs: Vec<Buff>,
+    /// Cooldown and ammo of character weapon. Only owning client receives it in `PlayerState`
+    #[serde(skip)]
+    pub weapon_state: WeaponState,
+    /// Velocity and dash cooldown of character
+    pub movement: MovementState,
+    /// Movement keys last sent by player. Applied on each movement step
+    #[serde(skip)]
+    pub movement_input: MovementInput,
+    /// Ability button is held by player, so character dashes when it can
     #[serde(skip)]
-    pub(crate) birth_instant: Option<Instant>,
-    pub(crate) pos: Point,
-    pub(crate) rot: Complex,
-    pub(crate) color: Color,
-    pub(crate) role: EntityRole,
-    pub(crate) health: u8,
-    pub(crate) tail: Option<EntityTail>,
-    pub(crate) activated: bool,
+    pub dash_held: bool,
 }
 
 impl Entity {
-    pub(crate) fn lerp(a: Self, b: Self, t: f64) -> Self {
+    pub fn lerp(a: Self, b: Self, t: f64) -> Self {
         Entity {
             id: b.id,
             player_id: b.player_id,
-            birth_instant: b.birth_instant,
+            age: b.age,
             pos: Point::lerp(a.pos, b.pos, t),
             rot: Complex::lerp(a.rot, b.rot, t),
             color: b.color,
             role: b.role,
+            player_weapon: b.player_weapon,
             health: b.health,
+            armor: b.armor,
+            regen_cooldown: b.regen_cooldown,
             tail: b.tail,
             activated: b.activated,
+            protected: b.protected,
+            buffs: b.buffs,
+            weapon_state: b.weapon_state,
+            movement: b.movement,
+            movement_input: b.movement_input,
+            dash_held: b.dash_held,
         }
     }
 
-    pub(crate) fn inscribed_circle_radius(&self) -> f32 {
+    pub fn inscribed_circle_radius(&self) -> f32 {
         match &self.role {
             EntityRole::Character { .. } => 8.,
+            EntityRole::Flag { .. } => 12.,
+            EntityRole::Pickup { .. } => 10.,
             EntityRole::Projectile { kind } => match kind {
                 ProjectileKind::Ball { radius, .. } => *radius,
                 ProjectileKind::Ray { .. } => 2.,
                 ProjectileKind::Mine { radius, .. } => *radius,
+                ProjectileKind::Missile { radius, .. } => *radius,
+                ProjectileKind::Grenade { radius, .. } => *radius,
             },
         }
     }
 
-    pub(crate) fn vertices(&self) -> [Point; 4] {
+    /// Cause of kills made by this entity
+    pub fn kill_cause(&self) -> KillCause {
+        KillCause {
+            killer: self.player_id,
+            weapon: self.player_weapon,
+        }
+    }
+
+    /// Path of ray from tail end to head. Single point segment for other entities
+    fn trace(&self) -> Vec<Segment> {
+        match (&self.role, &self.tail) {
+            (
+                EntityRole::Projectile {
+                    kind: ProjectileKind::Ray { .. },
+                },
+                Some(tail),
+            ) => {
+                let points: Vec<_> = [tail.end]
+                    .into_iter()
+                    .chain(tail.reflection_points.iter().copied())
+                    .chain([self.pos])
+                    .collect();
+                points.segments().collect()
+            }
+            _ => vec![Segment {
+                p0: self.pos,
+                p1: self.pos,
+            }],
+        }
+    }
+
+    /// Axis aligned box which contains whole entity including ray trace
+    fn bounding_rect(&self) -> Rect {
+        let radius = self.inscribed_circle_radius();
+        let (min, max) = self.trace().into_iter().flat_map(|x| [x.p0, x.p1]).fold(
+            (self.pos, self.pos),
+            |(min, max), p| {
+                (
+                    Point {
+                        x: min.x.min(p.x),
+                        y: min.y.min(p.y),
+                    },
+                    Point {
+                        x: max.x.max(p.x),
+                        y: max.y.max(p.y),
+                    },
+                )
+            },
+        );
+        Rect {
+            x: min.x - radius,
+            y: min.y - radius,
+            w: max.x - min.x + radius * 2.,
+            h: max.y - min.y + radius * 2.,
+        }
+    }
+
+    pub fn vertices(&self) -> [Point; 4] {
         [
             self.pos + Vector { x: -8., y: -8. } * self.rot,
             self.pos + Vector { x: 8., y: -8. } * self.rot,
@@ -190,80 +500,301 @@ impl Entity {
 }
 
 #[derive(Debug, Serialize, Deserialize)]
-pub(crate) struct EntityCreateInfo {
-    pub(crate) pos: Point,
-    pub(crate) rot: Complex,
-    pub(crate) color: Color,
-    pub(crate) role: EntityRole,
-    pub(crate) tail: Option<EntityTail>,
+pub struct EntityCreateInfo {
+    pub pos: Point,
+    pub rot: Complex,
+    pub color: Color,
+    pub role: EntityRole,
+    pub player_weapon: PlayerWeapon,
+    pub tail: Option<EntityTail>,
+}
+
+/// Player and weapon which killed an entity
+#[derive(Debug, Serialize, Deserialize, Clone, Copy)]
+pub struct KillCause {
+    pub killer: NonZero<u64>,
+    pub weapon: PlayerWeapon,
+}
+
+/// Damage dealt to character by a projectile or explosion
+#[derive(Debug, Clone, Copy)]
+pub struct Hit {
+    pub damage: f32,
+    pub cause: KillCause,
+    /// Attacker is a teammate of character
+    pub friendly: bool,
+}
+
+/// Segments which reflect projectiles during one game state proceed
+struct Obstacles<'a> {
+    shields: &'a [Segment],
+    walls: &'a [Segment],
+}
+
+/// Area damage to characters which falls off linearly from center to edge.
+/// Walls and shields between center and character block it
+#[derive(Debug, Clone, Copy)]
+pub struct Explosion {
+    pub pos: Point,
+    pub radius: f32,
+    /// Damage at the center
+    pub damage: f32,
+    pub cause: KillCause,
+}
+
+impl Explosion {
+    /// Part of center damage and knockback which is applied `distance` away from center
+    pub fn falloff(&self, distance: f32) -> f32 {
+        (1. - distance.max(0.) / self.radius).max(0.)
+    }
+
+    /// Damage to character which is `distance` away from center
+    pub fn damage_at(&self, distance: f32) -> f32 {
+        self.damage * self.falloff(distance)
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
-pub(crate) struct GameState {
+pub struct GameState {
     entities: Vec<RefCell<Entity>>,
-    world_bounds: Rect,
+    /// Sent to clients once on connect instead of with each broadcast
+    #[serde(skip)]
+    map: Arc<Map>,
     next_entity_id: u32,
-    kills: Vec<u32>,
+    kills: Vec<(u32, KillCause)>,
+    players: PlayerRegistry,
+    scoreboard: Scoreboard,
+    match_state: MatchState,
+    /// King of the hill zone. Exists only in this mode
+    zone: Option<Zone>,
+    /// Time until next pickup appears. Starts from spawn interval on first proceed
+    #[serde(skip)]
+    pickup_spawn_remaining: Option<Duration>,
+    #[serde(skip)]
+    events: EventLog,
+    /// Total time game state was proceeded. Not sent to clients
+    #[serde(skip)]
+    time: Duration,
+    /// Time not yet covered by movement steps
+    #[serde(skip)]
+    movement_remaining: Duration,
 }
 
 impl GameState {
-    pub(crate) fn new() -> Self {
+    pub fn new() -> Self {
         Self {
             entities: vec![],
-            world_bounds: Rect {
-                x: 32.,
-                y: 32. + 16.,
-                w: 800. - 64.,
-                h: 600. - 64.,
-            },
+            map: Default::default(),
             next_entity_id: 0,
             kills: Default::default(),
+            players: Default::default(),
+            scoreboard: Default::default(),
+            match_state: Default::default(),
+            zone: None,
+            pickup_spawn_remaining: None,
+            events: Default::default(),
+            time: Duration::ZERO,
+            movement_remaining: Duration::ZERO,
         }
     }
 
-    pub(crate) fn world_bounds(&self) -> Rect {
-        self.world_bounds
+    pub fn world_bounds(&self) -> Rect {
+        self.map.bounds
+    }
+
+    pub fn map(&self) -> &Arc<Map> {
+        &self.map
+    }
+
+    pub fn set_map(&mut self, map: Arc<Map>) {
+        self.map = map;
     }
 
-    pub(crate) fn random_point_inside_bounds<R: Rng>(&self, rng: &mut R) -> Point {
+    pub fn random_point_inside_bounds<R: Rng>(&self, rng: &mut R) -> Point {
+        let bounds = self.map.bounds;
         Point {
-            x: rng.random_range(self.world_bounds.x..(self.world_bounds.x + self.world_bounds.w)),
-            y: rng.random_range(self.world_bounds.y..(self.world_bounds.y + self.world_bounds.h)),
+            x: rng.random_range(bounds.x..(bounds.x + bounds.w)),
+            y: rng.random_range(bounds.y..(bounds.y + bounds.h)),
+        }
+    }
+
+    /// Falls back to first map spawn point or to bounds center if no point is found
+    fn random_point_outside_walls<R: Rng>(&self, rng: &mut R) -> Point {
+        (0..RANDOM_POINT_ATTEMPTS)
+            .map(|_| self.random_point_inside_bounds(rng))
+            .find(|x| !self.map.wall_at(*x))
+            .unwrap_or_else(|| {
+                self.map
+                    .spawn_points
+                    .first()
+                    .copied()
+                    .unwrap_or(self.map.bounds.center())
+            })
+    }
+
+    /// Distance from `point` to the closest enemy character or path of foreign projectile.
+    /// Larger is safer
+    fn spawn_safety(&self, point: Point, player_id: NonZero<u64>) -> f32 {
+        self.entities()
+            .filter(|e| e.player_id != player_id)
+            .filter_map(|e| match &e.role {
+                EntityRole::Character { .. } => {
+                    if e.health <= 0. || self.players.teammates(e.player_id, player_id) {
+                        None
+                    } else {
+                        Some((point - e.pos).len())
+                    }
+                }
+                EntityRole::Projectile { kind } => match kind {
+                    ProjectileKind::Ball { velocity, .. }
+                    | ProjectileKind::Ray { velocity, .. }
+                    | ProjectileKind::Missile { velocity, .. } => {
+                        let path = Segment {
+                            p0: e.pos,
+                            p1: e.pos
+                                + Vector::polar(e.rot, velocity * SPAWN_LOOKAHEAD.as_secs_f32()),
+                        };
+                        Some(path.distance_to(point) - e.inscribed_circle_radius())
+                    }
+                    ProjectileKind::Mine {
+                        explosion_radius, ..
+                    }
+                    | ProjectileKind::Grenade {
+                        explosion_radius, ..
+                    } => Some((point - e.pos).len() - explosion_radius),
+                },
+                EntityRole::Flag { .. } | EntityRole::Pickup { .. } => None,
+            })
+            .fold(f32::INFINITY, f32::min)
+    }
+
+    /// Safest of map spawn points or of random points outside of walls if map has none
+    pub fn spawn_point<R: Rng>(&self, player_id: NonZero<u64>, rng: &mut R) -> Point {
+        let mut candidates = if self.map.spawn_points.is_empty() {
+            (0..SPAWN_CANDIDATE_COUNT)
+                .map(|_| self.random_point_outside_walls(rng))
+                .collect()
+        } else {
+            self.map.spawn_points.clone()
+        };
+        candidates.shuffle(rng);
+        candidates
+            .into_iter()
+            .map(|x| (x, self.spawn_safety(x, player_id)))
+            .max_by(|a, b| a.1.total_cmp(&b.1))
+            .unwrap()
+            .0
+    }
+
+    /// Moves character out of world bounds and walls
+    pub fn push_out_of_walls(&self, character: &mut Entity) {
+        for bound in self.map.bounds.edges() {
+            if let Some(exit_vec) = character.vertices().segments_ringe().collide(&[bound]) {
+                character.pos += exit_vec;
+            }
+        }
+        for wall in &self.map.walls {
+            if let Some(exit_vec) = character
+                .vertices()
+                .segments_ringe()
+                .as_slice()
+                .collide(wall.as_slice())
+            {
+                character.pos += exit_vec;
+            }
+        }
+    }
+
+    /// Moves character by one movement step with its held input and pushes it out of obstacles.
+    /// Server and client prediction step characters the same way
+    pub fn step_character(&self, character: &mut Entity, config: &MovementConfig) {
+        let speed_multiplier = Buff::speed_multiplier(&character.buffs);
+        let displacement = config.step(
+            &mut character.movement,
+            character.movement_input,
+            character.dash_held,
+            MOVEMENT_STEP,
+            speed_multiplier,
+        );
+        character.pos += displacement;
+        self.push_out_of_characters(character);
+        self.push_out_of_walls(character);
+    }
+
+    /// Pushes character and other characters which it overlaps apart by half of overlap each
+    pub fn push_out_of_characters(&self, character: &mut Entity) {
+        for other in &self.entities {
+            let Ok(mut other) = other.try_borrow_mut() else {
+                continue;
+            };
+            if other.id == character.id || !matches!(other.role, EntityRole::Character { .. }) {
+                continue;
+            }
+            if let Some(exit_vec) = character
+                .vertices()
+                .segments_ringe()
+                .collide(&other.vertices().segments_ringe())
+            {
+                character.pos += exit_vec * 0.5;
+                other.pos -= exit_vec * 0.5;
+                self.push_out_of_walls(&mut other);
+            }
         }
     }
 
-    pub(crate) fn create(&mut self, entity: EntityCreateInfo, player_id: NonZero<u64>) {
+    /// Characters are created with full health from `health`
+    pub fn create(
+        &mut self,
+        entity: EntityCreateInfo,
+        player_id: NonZero<u64>,
+        health: &HealthConfig,
+    ) {
         self.entities.push(RefCell::new(Entity {
             id: self.next_entity_id,
             player_id,
-            birth_instant: Some(Instant::now()),
+            age: Duration::ZERO,
             pos: entity.pos,
             rot: entity.rot,
             color: entity.color,
             health: match &entity.role {
-                EntityRole::Character { .. } => 3,
+                EntityRole::Character { .. } => health.max,
+                EntityRole::Flag { .. } | EntityRole::Pickup { .. } => 0.,
                 EntityRole::Projectile { kind } => match kind {
-                    ProjectileKind::Ball { health, .. } => *health,
-                    ProjectileKind::Ray { health, .. } => *health,
-                    ProjectileKind::Mine { .. } => 0,
+                    ProjectileKind::Ball { health, .. } => *health as f32,
+                    ProjectileKind::Ray { health, .. } => *health as f32,
+                    ProjectileKind::Mine { .. } => 0.,
+                    ProjectileKind::Missile { health, .. } => *health as f32,
+                    ProjectileKind::Grenade { .. } => 0.,
                 },
             },
+            armor: 0.,
+            regen_cooldown: Duration::ZERO,
+            weapon_state: match &entity.role {
+                EntityRole::Character { weapon } => WeaponState::new(weapon.magazine()),
+                _ => Default::default(),
+            },
             role: entity.role,
+            player_weapon: entity.player_weapon,
             tail: entity.tail,
             activated: false,
+            protected: false,
+            buffs: vec![],
+            movement: Default::default(),
+            movement_input: Default::default(),
+            dash_held: false,
         }));
         self.next_entity_id += 1;
     }
 
-    pub(crate) fn entities<'a>(&'a self) -> impl Iterator<Item = Ref<'a, Entity>> {
+    pub fn entities<'a>(&'a self) -> impl Iterator<Item = Ref<'a, Entity>> {
         self.entities.iter().filter_map(|x| x.try_borrow().ok())
     }
 
-    pub(crate) fn entities_mut<'a>(&'a self) -> impl Iterator<Item = RefMut<'a, Entity>> {
+    pub fn entities_mut<'a>(&'a self) -> impl Iterator<Item = RefMut<'a, Entity>> {
         self.entities.iter().filter_map(|x| x.try_borrow_mut().ok())
     }
 
-    pub(crate) fn find_character_by_player_id_mut<'a>(
+    pub fn find_character_by_player_id_mut<'a>(
         &'a self,
         player_id: NonZero<u64>,
     ) -> Option<RefMut<'a, Entity>> {
@@ -278,11 +809,7 @@ impl GameState {
             })
     }
 
-    pub(crate) fn add_or_replace_character_by_player_id(
-        &mut self,
-        player_id: NonZero<u64>,
-        e: Entity,
-    ) {
+    pub fn add_or_replace_character_by_player_id(&mut self, player_id: NonZero<u64>, e: Entity) {
         match self.find_character_by_player_id_mut(player_id) {
             Some(mut entity) => return *entity = e,
             None => {}
@@ -290,7 +817,7 @@ impl GameState {
         self.entities.push(RefCell::new(e))
     }
 
-    pub(crate) fn add_or_replace_by_id(&mut self, id: u32, e: Entity) {
+    pub fn add_or_replace_by_id(&mut self, id: u32, e: Entity) {
         match self.find_by_id_mut(id) {
             Some(mut entity) => return *entity = e,
             None => {}
@@ -298,7 +825,7 @@ impl GameState {
         self.entities.push(RefCell::new(e))
     }
 
-    pub(crate) fn find_by_id_mut<'a>(&'a self, id: u32) -> Option<RefMut<'a, Entity>> {
+    pub fn find_by_id_mut<'a>(&'a self, id: u32) -> Option<RefMut<'a, Entity>> {
         self.entities
             .iter()
             .find_map(|x| match x.try_borrow_mut().ok() {
@@ -313,54 +840,19 @@ impl GameState {
             })
     }
 
+    /// Reflects motion from first crossed segment. Shields are checked before walls
     fn reflect(
         position: &mut Point,
         rotation: &mut Complex,
-        bounds: &Rect,
         shields: &[Segment],
+        walls: &[Segment],
         motion_segment: Segment,
     ) -> bool {
-        for shield in shields {
-            if let Some(r) = shield.ray_cast(motion_segment) {
+        for segment in shields.iter().chain(walls) {
+            if let Some(r) = segment.ray_cast(motion_segment) {
                 if r.intersects() {
                     *position = r.intersection_point_u_mul(0.99);
-                    *rotation = rotation.reflect_from(shield.vec());
-                    return true;
-                }
-            }
-        }
-
-        for (i, edge) in bounds.edges().into_iter().enumerate() {
-            if let Some(r) = edge.ray_cast(motion_segment) {
-                if r.intersects() {
-                    *position = r.intersection_point();
-                    match i {
-                        0 => {
-                            *rotation = Complex {
-                                r: rotation.r,
-                                i: rotation.i.abs(),
-                            }
-                        }
-                        1 => {
-                            *rotation = Complex {
-                                r: -rotation.r.abs(),
-                                i: rotation.i,
-                            }
-                        }
-                        2 => {
-                            *rotation = Complex {
-                                r: rotation.r,
-                                i: -rotation.i.abs(),
-                            }
-                        }
-                        3 => {
-                            *rotation = Complex {
-                                r: rotation.r.abs(),
-                                i: rotation.i,
-                            }
-                        }
-                        _ => panic!("Wups!!!"),
-                    }
+                    *rotation = rotation.reflect_from(segment.vec());
                     return true;
                 }
             }
@@ -369,12 +861,31 @@ impl GameState {
         false
     }
 
-    pub(crate) fn proceed(&mut self, dt: Duration) {
-        let now = Instant::now();
-        let mut create_infos: Vec<(EntityCreateInfo, NonZero<u64>)> = Default::default();
+    /// Turns `rotation` by at most `max_angle` toward the nearest target
+    /// which is inside of cone with half angle `lock_on_angle`
+    fn steer(
+        position: Point,
+        rotation: Complex,
+        targets: &[Point],
+        lock_on_angle: f32,
+        max_angle: f32,
+    ) -> Complex {
+        let forward = Vector::polar(rotation, 1.);
+        let angle = targets
+            .iter()
+            .map(|target| *target - position)
+            .map(|x| (x.len(), forward.cross(x).atan2(forward.dot(x))))
+            .filter(|(_, angle)| angle.abs() <= lock_on_angle)
+            .min_by(|a, b| a.0.total_cmp(&b.0))
+            .map(|(_, angle)| angle);
+        match angle {
+            Some(angle) => rotation * Complex::from_rad(angle.clamp(-max_angle, max_angle)),
+            None => rotation,
+        }
+    }
 
-        let shields: Vec<Segment> = self
-            .entities
+    fn shields(&self) -> Vec<Segment> {
+        self.entities
             .iter()
             .map(|x| {
                 let x = x.borrow();
@@ -389,23 +900,337 @@ impl GameState {
                 }
             })
             .flatten()
+            .collect()
+    }
+
+    /// Damages and knocks back characters around explosion center and reports explosion to
+    /// clients
+    pub fn explode(&mut self, explosion: Explosion, config: &GameConfig) {
+        let blockers: Vec<Segment> = self
+            .shields()
+            .into_iter()
+            .chain(self.map.segments())
+            .collect();
+        for character in &self.entities {
+            let mut character = character.borrow_mut();
+            if !matches!(character.role, EntityRole::Character { .. })
+                || character.health <= 0.
+                || character.protected
+            {
+                continue;
+            }
+            let line = Segment {
+                p0: explosion.pos,
+                p1: character.pos,
+            };
+            if blockers
+                .iter()
+                .any(|x| x.ray_cast(line).is_some_and(|r| r.intersects()))
+            {
+                continue;
+            }
+            let friendly = self
+                .players
+                .teammates(character.player_id, explosion.cause.killer);
+            let offset = character.pos - explosion.pos;
+            let distance = offset.len() - character.inscribed_circle_radius();
+            if offset.len() > 0. {
+                let impulse = config.knockback.explosion * explosion.falloff(distance);
+                character.movement.push(offset.normalize() * impulse);
+            }
+            let damage = explosion.damage_at(distance);
+            if damage > 0. {
+                Self::damage_character(
+                    &mut self.kills,
+                    &mut self.scoreboard,
+                    &mut self.events,
+                    &mut character,
+                    Hit {
+                        damage,
+                        cause: explosion.cause,
+                        friendly,
+                    },
+                    config,
+                );
+            }
+        }
+        self.events.push(GameEvent::Explosion {
+            player_id: explosion.cause.killer,
+            pos: explosion.pos,
+            radius: explosion.radius,
+        });
+    }
+
+    /// Debris spread evenly around detonated mine
+    fn mine_debris(mine: &Entity) -> Vec<(EntityCreateInfo, NonZero<u64>)> {
+        let EntityRole::Projectile {
+            kind:
+                ProjectileKind::Mine {
+                    debris_kind,
+                    debris_count,
+                    ..
+                },
+        } = &mine.role
+        else {
+            return vec![];
+        };
+        (0..*debris_count)
+            .map(|i| {
+                let rot = Complex::from_rad((i as f32 / *debris_count as f32) * 2. * PI);
+                (
+                    EntityCreateInfo {
+                        pos: mine.pos,
+                        rot,
+                        color: mine.color.clone(),
+                        role: EntityRole::Projectile {
+                            kind: debris_kind.deref().clone(),
+                        },
+                        player_weapon: mine.player_weapon,
+                        tail: Some(EntityTail {
+                            end: mine.pos,
+                            rotation: rot,
+                            reflection_points: Default::default(),
+                        }),
+                    },
+                    mine.player_id,
+                )
+            })
+            .collect()
+    }
+
+    /// Explosion of detonated mine which is caused by its owner
+    fn mine_explosion(mine: &Entity) -> Option<Explosion> {
+        match &mine.role {
+            EntityRole::Projectile {
+                kind:
+                    ProjectileKind::Mine {
+                        explosion_radius,
+                        explosion_damage,
+                        ..
+                    },
+            } => Some(Explosion {
+                pos: mine.pos,
+                radius: *explosion_radius,
+                damage: *explosion_damage,
+                cause: mine.kill_cause(),
+            }),
+            _ => None,
+        }
+    }
+
+    /// Detonates all mines of the player as if enemy stepped on them
+    pub fn detonate_mines(&mut self, player_id: NonZero<u64>, config: &GameConfig) {
+        let mut create_infos = vec![];
+        let mut explosions = vec![];
+        for mine in &self.entities {
+            let mine = mine.borrow();
+            if mine.player_id == player_id
+                && matches!(
+                    mine.role,
+                    EntityRole::Projectile {
+                        kind: ProjectileKind::Mine { .. }
+                    }
+                )
+                && !self.kills.iter().any(|(id, _)| *id == mine.id)
+            {
+                create_infos.extend(Self::mine_debris(&mine));
+                explosions.extend(Self::mine_explosion(&mine));
+                self.events.push(GameEvent::MineDetonation {
+                    player_id,
+                    pos: mine.pos,
+                });
+                self.kills.push((mine.id, mine.kill_cause()));
+            }
+        }
+        for explosion in explosions {
+            self.explode(explosion, config);
+        }
+        for (create_info, player_id) in create_infos {
+            self.create(create_info, player_id, &config.health);
+        }
+    }
+
+    /// Takes one health from projectile. Projectiles without health are destroyed at once
+    fn damage_projectile(
+        kills: &mut Vec<(u32, KillCause)>,
+        projectile: &mut Entity,
+        cause: KillCause,
+    ) -> bool {
+        projectile.health = (projectile.health - 1.).max(0.);
+        if projectile.health <= 0. {
+            kills.push((projectile.id, cause));
+        }
+        projectile.health <= 0.
+    }
+
+    /// Applies interaction rules to touching projectiles of enemies.
+    /// Candidate pairs are found by sweeping bounding boxes along x axis
+    fn proceed_projectile_interactions(
+        &mut self,
+        interactions: &ProjectileInteractions,
+        create_infos: &mut Vec<(EntityCreateInfo, NonZero<u64>)>,
+        explosions: &mut Vec<Explosion>,
+    ) {
+        let mut candidates: Vec<(usize, Rect)> = self
+            .entities
+            .iter()
+            .enumerate()
+            .filter_map(|(i, e)| {
+                let e = e.borrow();
+                match &e.role {
+                    EntityRole::Projectile { kind }
+                        if interactions.involves(kind.class())
+                            && !self.kills.iter().any(|(id, _)| *id == e.id) =>
+                    {
+                        Some((i, e.bounding_rect()))
+                    }
+                    _ => None,
+                }
+            })
+            .collect();
+        candidates.sort_by(|a, b| a.1.x.total_cmp(&b.1.x));
+
+        let mut destroyed = vec![false; self.entities.len()];
+        for (i, (a_index, a_rect)) in candidates.iter().enumerate() {
+            for (b_index, b_rect) in &candidates[i + 1..] {
+                if destroyed[*a_index] || b_rect.x > a_rect.x + a_rect.w {
+                    break;
+                }
+                if destroyed[*b_index]
+                    || b_rect.y > a_rect.y + a_rect.h
+                    || a_rect.y > b_rect.y + b_rect.h
+                {
+                    continue;
+                }
+                let mut a = self.entities[*a_index].borrow_mut();
+                let mut b = self.entities[*b_index].borrow_mut();
+                if a.player_id == b.player_id || self.players.teammates(a.player_id, b.player_id) {
+                    continue;
+                }
+                let (
+                    EntityRole::Projectile { kind: a_kind },
+                    EntityRole::Projectile { kind: b_kind },
+                ) = (&a.role, &b.role)
+                else {
+                    continue;
+                };
+                let Some((effect, swapped)) = interactions.find(a_kind.class(), b_kind.class())
+                else {
+                    continue;
+                };
+                let radius = a.inscribed_circle_radius() + b.inscribed_circle_radius();
+                let b_trace = b.trace();
+                if !a
+                    .trace()
+                    .into_iter()
+                    .any(|x| b_trace.iter().any(|y| x.distance_to_segment(*y) < radius))
+                {
+                    continue;
+                }
+
+                let ((first, first_index), (second, second_index)) = if swapped {
+                    ((&mut *b, *b_index), (&mut *a, *a_index))
+                } else {
+                    ((&mut *a, *a_index), (&mut *b, *b_index))
+                };
+                match effect {
+                    InteractionEffect::Collide => {
+                        let first_cause = first.kill_cause();
+                        destroyed[first_index] =
+                            Self::damage_projectile(&mut self.kills, first, second.kill_cause());
+                        destroyed[second_index] =
+                            Self::damage_projectile(&mut self.kills, second, first_cause);
+                    }
+                    InteractionEffect::Cut => {
+                        second.health = 0.;
+                        self.kills.push((second.id, first.kill_cause()));
+                        destroyed[second_index] = true;
+                    }
+                    InteractionEffect::Trigger => {
+                        let EntityRole::Projectile { kind } = &second.role else {
+                            continue;
+                        };
+                        match kind {
+                            ProjectileKind::Mine { .. } => {
+                                create_infos.extend(Self::mine_debris(second));
+                                explosions.extend(Self::mine_explosion(second));
+                                self.events.push(GameEvent::MineDetonation {
+                                    player_id: second.player_id,
+                                    pos: second.pos,
+                                });
+                            }
+                            ProjectileKind::Grenade {
+                                explosion_radius,
+                                explosion_damage,
+                                ..
+                            } => explosions.push(Explosion {
+                                pos: second.pos,
+                                radius: *explosion_radius,
+                                damage: *explosion_damage,
+                                cause: second.kill_cause(),
+                            }),
+                            _ => continue,
+                        }
+                        self.kills.push((second.id, first.kill_cause()));
+                        destroyed[second_index] = true;
+                    }
+                }
+            }
+        }
+    }
+
+    pub fn proceed<R: Rng>(&mut self, dt: Duration, config: &GameConfig, rng: &mut R) {
+        self.time += dt;
+        self.movement_remaining += dt;
+        while self.movement_remaining >= MOVEMENT_STEP {
+            self.movement_remaining -= MOVEMENT_STEP;
+            for character in &self.entities {
+                let mut character = character.borrow_mut();
+                if matches!(character.role, EntityRole::Character { .. }) && character.health > 0. {
+                    self.step_character(&mut character, &config.movement);
+                }
+            }
+        }
+        for mut e in self.entities_mut() {
+            e.age += dt;
+        }
+        let mut create_infos: Vec<(EntityCreateInfo, NonZero<u64>)> = Default::default();
+        let mut explosions: Vec<Explosion> = Default::default();
+        let combat_allowed = self.match_state.combat_allowed();
+
+        let shields = self.shields();
+        let walls: Vec<Segment> = self.map.segments().collect();
+        let obstacles = Obstacles {
+            shields: &shields,
+            walls: &walls,
+        };
+        let characters: Vec<_> = self
+            .entities()
+            .filter(|e| matches!(e.role, EntityRole::Character { .. }) && e.health > 0.)
+            .map(|e| (e.player_id, e.pos))
             .collect();
 
         self.entities.retain(|entity| -> bool {
             let mut entity = entity.borrow_mut();
             match entity.role.clone() {
-                EntityRole::Character { .. } => true,
+                EntityRole::Character { .. }
+                | EntityRole::Flag { .. }
+                | EntityRole::Pickup { .. } => true,
                 EntityRole::Projectile { kind } => {
                     let velosity = match kind {
                         ProjectileKind::Ball { velocity, .. } => velocity,
                         ProjectileKind::Ray { velocity, .. } => velocity,
                         ProjectileKind::Mine { velocity, .. } => velocity,
+                        ProjectileKind::Missile { velocity, .. } => velocity,
+                        ProjectileKind::Grenade { velocity, .. } => velocity,
                     };
 
                     let life_duration = match kind {
                         ProjectileKind::Ball { life_duration, .. } => life_duration,
                         ProjectileKind::Ray { life_duration, .. } => life_duration,
                         ProjectileKind::Mine { life_duration, .. } => life_duration,
+                        ProjectileKind::Missile { life_duration, .. } => life_duration,
+                        ProjectileKind::Grenade { fuse_duration, .. } => fuse_duration,
                     };
 
                     fn step(
@@ -413,8 +1238,7 @@ impl GameState {
                         rotation: &mut Complex,
                         reflection_points: Option<&mut VecDeque<Point>>,
                         tail: bool,
-                        bounds: &Rect,
-                        shields: &[Segment],
+                        obstacles: &Obstacles,
                         velosity: f32,
                         dt: &Duration,
                     ) {
@@ -423,7 +1247,13 @@ impl GameState {
                             p1: *position
                                 + Vector::polar(*rotation, velosity * 2. * dt.as_secs_f32()),
                         };
-                        if GameState::reflect(position, rotation, bounds, shields, motion_segment) {
+                        if GameState::reflect(
+                            position,
+                            rotation,
+                            obstacles.shields,
+                            obstacles.walls,
+                            motion_segment,
+                        ) {
                             if let Some(reflection_points) = reflection_points {
                                 if tail {
                                     reflection_points.pop_front();
@@ -447,48 +1277,39 @@ impl GameState {
                             } => {
                                 let tail = entity.tail.as_mut().unwrap();
 
-                                if now - entity.birth_instant.unwrap() < *life_duration {
+                                if entity.age < *life_duration {
                                     step(
                                         &mut entity.pos,
                                         &mut entity.rot,
                                         Some(&mut tail.reflection_points),
                                         false,
-                                        &self.world_bounds,
-                                        &shields,
+                                        &obstacles,
                                         velosity,
                                         &dt,
                                     );
                                 }
 
-                                if now - entity.birth_instant.unwrap() > *tail_freeze_duration {
+                                if entity.age > *tail_freeze_duration {
                                     step(
                                         &mut tail.end,
                                         &mut tail.rotation,
                                         Some(&mut tail.reflection_points),
                                         true,
-                                        &self.world_bounds,
-                                        &shields,
+                                        &obstacles,
                                         velosity,
                                         &dt,
                                     );
                                 }
-                                now - entity.birth_instant.unwrap()
-                                    < (*life_duration + *tail_freeze_duration)
+                                entity.age < (*life_duration + *tail_freeze_duration)
                             }
                             ProjectileKind::Mine {
                                 life_duration,
-                                owner_invincibility_duration,
                                 activation_duration,
                                 velocity,
                                 acceleration,
-                                radius,
-                                detection_radius,
-                                explosion_radius,
-                                debris_kind,
-                                debris_count,
+                                ..
                             } => {
-                                entity.activated =
-                                    now - entity.birth_instant.unwrap() > *activation_duration;
+                                entity.activated = entity.age > *activation_duration;
 
                                 let new_velocity = *velocity + *acceleration * dt.as_secs_f32();
                                 if velocity.signum() == new_velocity.signum() {
@@ -500,66 +1321,192 @@ impl GameState {
                                     &mut entity.rot,
                                     None,
                                     false,
-                                    &self.world_bounds,
-                                    &shields,
+                                    &obstacles,
                                     velosity,
                                     &dt,
                                 );
 
-                                now - entity.birth_instant.unwrap() < *life_duration
+                                entity.age < *life_duration
                             }
-                            _ => {
+                            ProjectileKind::Missile {
+                                velocity,
+                                acceleration,
+                                turn_rate,
+                                lock_on_angle,
+                                fuel_duration,
+                                ..
+                            } => {
+                                if entity.age < *fuel_duration {
+                                    *velocity =
+                                        (*velocity + *acceleration * dt.as_secs_f32()).max(0.);
+                                    let targets: Vec<_> = characters
+                                        .iter()
+                                        .filter(|(player_id, _)| {
+                                            *player_id != entity.player_id
+                                                && !self
+                                                    .players
+                                                    .teammates(*player_id, entity.player_id)
+                                        })
+                                        .map(|(_, pos)| *pos)
+                                        .collect();
+                                    entity.rot = GameState::steer(
+                                        entity.pos,
+                                        entity.rot,
+                                        &targets,
+                                        *lock_on_angle,
+                                        *turn_rate * dt.as_secs_f32(),
+                                    );
+                                }
+
+                                let motion_segment = Segment {
+                                    p0: entity.pos,
+                                    p1: entity.pos
+                                        + Vector::polar(
+                                            entity.rot,
+                                            *velocity * 2. * dt.as_secs_f32(),
+                                        ),
+                                };
+                                if shields.iter().any(|shield| {
+                                    shield
+                                        .ray_cast(motion_segment)
+                                        .is_some_and(|r| r.intersects())
+                                }) {
+                                    return false;
+                                }
+
                                 step(
                                     &mut entity.pos,
                                     &mut entity.rot,
                                     None,
                                     false,
-                                    &self.world_bounds,
-                                    &shields,
-                                    velosity,
+                                    &obstacles,
+                                    *velocity,
                                     &dt,
                                 );
-                                now - entity.birth_instant.unwrap() < life_duration
+                                entity.age < life_duration
                             }
-                        },
-                        _ => true,
-                    }
-                }
-            }
-        });
-
-        for character in &self.entities {
-            let mut character = character.borrow_mut();
+                            ProjectileKind::Grenade {
+                                velocity,
+                                deceleration,
+                                explosion_radius,
+                                explosion_damage,
+                                ..
+                            } => {
+                                *velocity = (*velocity - *deceleration * dt.as_secs_f32()).max(0.);
+
+                                step(
+                                    &mut entity.pos,
+                                    &mut entity.rot,
+                                    None,
+                                    false,
+                                    &obstacles,
+                                    *velocity,
+                                    &dt,
+                                );
+
+                                if entity.age < life_duration {
+                                    true
+                                } else {
+                                    explosions.push(Explosion {
+                                        pos: entity.pos,
+                                        radius: *explosion_radius,
+                                        damage: *explosion_damage,
+                                        cause: entity.kill_cause(),
+                                    });
+                                    false
+                                }
+                            }
+                            _ => {
+                                step(
+                                    &mut entity.pos,
+                                    &mut entity.rot,
+                                    None,
+                                    false,
+                                    &obstacles,
+                                    velosity,
+                                    &dt,
+                                );
+                                entity.age < life_duration
+                            }
+                        },
+                        _ => true,
+                    }
+                }
+            }
+        });
+
+        for character in &self.entities {
+            let mut character = character.borrow_mut();
+            if let EntityRole::Character { weapon } = &character.role {
+                let magazine = weapon.magazine().copied();
+                character.protected = character.age < config.spawn_protection;
+                character.weapon_state.proceed(dt, magazine.as_ref());
+                if character.health > 0. {
+                    character.health =
+                        config
+                            .health
+                            .regenerated(character.health, character.regen_cooldown, dt);
+                    character.regen_cooldown = character.regen_cooldown.saturating_sub(dt);
+                }
+            }
             match character.role {
-                EntityRole::Character { .. } => {
+                EntityRole::Character { .. } if !character.protected && combat_allowed => {
                     for projectile in &self.entities {
                         if let Ok(mut projectile) = projectile.try_borrow_mut() {
                             let mut acc: Option<Vector> = None;
-                            match & projectile.role {
-                                EntityRole::Projectile { kind } => match kind {
+                            let friendly = self
+                                .players
+                                .teammates(character.player_id, projectile.player_id);
+                            if let EntityRole::Projectile { kind } = &projectile.role {
+                                match kind {
                                     ProjectileKind::Ball {
                                         owner_invincibility_duration,
                                         ..
+                                    }
+                                    | ProjectileKind::Missile {
+                                        owner_invincibility_duration,
+                                        ..
                                     } => {
                                         if projectile.player_id != character.player_id
-                                            || (now - projectile.birth_instant.unwrap())
-                                                > *owner_invincibility_duration
+                                            || projectile.age > *owner_invincibility_duration
                                         {
-                                            if character.health != 0
-                                                && projectile.health != 0
+                                            if character.health > 0.
+                                                && projectile.health > 0.
                                                 && (character.pos - projectile.pos).len()
                                                     < (character.inscribed_circle_radius()
                                                         + projectile.inscribed_circle_radius())
+                                                && Self::damage_character(
+                                                    &mut self.kills,
+                                                    &mut self.scoreboard,
+                                                    &mut self.events,
+                                                    &mut character,
+                                                    Hit {
+                                                        damage: kind.damage(),
+                                                        cause: projectile.kill_cause(),
+                                                        friendly,
+                                                    },
+                                                    config,
+                                                )
                                             {
-                                                character.health -= 1;
-                                                projectile.health -= 1;
-
-                                                if character.health == 0 {
-                                                    self.kills.push(character.id);
+                                                let offset = character.pos - projectile.pos;
+                                                if matches!(kind, ProjectileKind::Ball { .. })
+                                                    && offset.len() > 0.
+                                                {
+                                                    character.movement.push(
+                                                        offset.normalize() * config.knockback.ball,
+                                                    );
                                                 }
 
-                                                if projectile.health == 0 {
-                                                    self.kills.push(projectile.id);
+                                                projectile.health -= 1.;
+
+                                                if projectile.health <= 0. {
+                                                    self.kills.push((
+                                                        projectile.id,
+                                                        KillCause {
+                                                            killer: character.player_id,
+                                                            weapon: character.player_weapon,
+                                                        },
+                                                    ));
                                                 }
                                                 break;
                                             }
@@ -572,10 +1519,9 @@ impl GameState {
                                         let tail = projectile.tail.as_ref().unwrap();
 
                                         if projectile.player_id != character.player_id
-                                            || (now - projectile.birth_instant.unwrap())
-                                                > *owner_invincibility_duration
+                                            || projectile.age > *owner_invincibility_duration
                                         {
-                                            if character.health != 0 && projectile.health != 0 {
+                                            if character.health > 0. && projectile.health > 0. {
                                                 let projectile_trace: Vec<_> = [tail.end]
                                                     .into_iter()
                                                     .chain(
@@ -590,16 +1536,28 @@ impl GameState {
                                                             &character.vertices().segments_ringe(),
                                                         )
                                                         .is_some()
-                                                }) {
-                                                    character.health -= 1;
-                                                    projectile.health -= 1;
+                                                }) && Self::damage_character(
+                                                    &mut self.kills,
+                                                    &mut self.scoreboard,
+                                                    &mut self.events,
+                                                    &mut character,
+                                                    Hit {
+                                                        damage: kind.damage(),
+                                                        cause: projectile.kill_cause(),
+                                                        friendly,
+                                                    },
+                                                    config,
+                                                ) {
+                                                    projectile.health -= 1.;
 
-                                                    if character.health == 0 {
-                                                        self.kills.push(character.id);
-                                                    }
-
-                                                    if projectile.health == 0 {
-                                                        self.kills.push(projectile.id);
+                                                    if projectile.health <= 0. {
+                                                        self.kills.push((
+                                                            projectile.id,
+                                                            KillCause {
+                                                                killer: character.player_id,
+                                                                weapon: character.player_weapon,
+                                                            },
+                                                        ));
                                                     }
                                                     break;
                                                 }
@@ -607,67 +1565,60 @@ impl GameState {
                                         }
                                     }
                                     ProjectileKind::Mine {
-                                        life_duration,
                                         owner_invincibility_duration,
-                                        activation_duration,
-                                        velocity,
                                         acceleration,
-                                        radius,
                                         detection_radius,
                                         explosion_radius,
-                                        debris_kind,
-                                        debris_count,
+                                        ..
                                     } => {
+                                        // Damage of debris and explosion is limited separately
                                         if projectile.activated
+                                            && (!friendly
+                                                || config.friendly_fire != FriendlyFire::Off)
                                             && (projectile.player_id != character.player_id
-                                                || (now - projectile.birth_instant.unwrap())
-                                                    > *owner_invincibility_duration)
+                                                || projectile.age > *owner_invincibility_duration)
                                         {
-                                            if (character.pos - projectile.pos).len()
-                                            < (character.inscribed_circle_radius()
-                                                + *detection_radius) {
-                                                    acc = Some((character.pos - projectile.pos).normalize() * -2. * *acceleration * dt.as_secs_f32());
-                                                }
-
-
-                                            if (character.pos - projectile.pos).len()
-                                                < (character.inscribed_circle_radius()
-                                                    + explosion_radius)
+                                            let offset = character.pos - projectile.pos;
+                                            if offset.len()
+                                                < character.inscribed_circle_radius()
+                                                    + *detection_radius
                                             {
-                                                for i in 0..*debris_count {
-                                                    let rot = Complex::from_rad(
-                                                        (i as f32 / *debris_count as f32) * 2. * PI,
-                                                    );
-                                                    create_infos.push((
-                                                        EntityCreateInfo {
-                                                            pos: projectile.pos,
-                                                            rot,
-                                                            color: projectile.color.clone(),
-                                                            role: EntityRole::Projectile {
-                                                                kind: debris_kind.deref().clone(),
-                                                            },
+                                                acc = Some(
+                                                    offset.normalize()
+                                                        * -2.
+                                                        * *acceleration
+                                                        * dt.as_secs_f32(),
+                                                );
+                                            }
 
-                                                            tail: Some(EntityTail {
-                                                                end: projectile.pos,
-                                                                rotation: rot,
-                                                                reflection_points: Default::default(
-                                                                ),
-                                                            }),
-                                                        },
-                                                        projectile.player_id,
-                                                    ));
-                                                }
+                                            if offset.len()
+                                                < character.inscribed_circle_radius()
+                                                    + *explosion_radius
+                                            {
+                                                create_infos.extend(Self::mine_debris(&projectile));
+                                                explosions
+                                                    .extend(Self::mine_explosion(&projectile));
 
-                                                self.kills.push(projectile.id);
+                                                self.events.push(GameEvent::MineDetonation {
+                                                    player_id: projectile.player_id,
+                                                    pos: projectile.pos,
+                                                });
+                                                self.kills.push((
+                                                    projectile.id,
+                                                    KillCause {
+                                                        killer: character.player_id,
+                                                        weapon: character.player_weapon,
+                                                    },
+                                                ));
                                                 break;
                                             }
                                         }
                                     }
-                                },
-                                _ => {}
+                                    ProjectileKind::Grenade { .. } => {}
+                                }
                             }
                             if let Some(acc) = acc {
-                            projectile.pos += acc;
+                                projectile.pos += acc;
                             }
                         }
                     }
@@ -676,9 +1627,21 @@ impl GameState {
             }
         }
 
+        self.proceed_projectile_interactions(
+            &config.projectile_interactions,
+            &mut create_infos,
+            &mut explosions,
+        );
+
+        if combat_allowed {
+            for explosion in explosions {
+                self.explode(explosion, config);
+            }
+        }
+
         self.entities.retain(|e| {
             let e = e.borrow();
-            if let Some(index) = self.kills.iter().position(|x| *x == e.id) {
+            if let Some(index) = self.kills.iter().position(|(id, _)| *id == e.id) {
                 match e.role {
                     EntityRole::Projectile { .. } => {
                         self.kills.remove(index);
@@ -691,23 +1654,532 @@ impl GameState {
         });
 
         for (create_info, player_id) in create_infos {
-            self.create(create_info, player_id);
+            self.create(create_info, player_id, &config.health);
+        }
+
+        self.proceed_flags(config);
+        self.proceed_zone(dt, config, rng);
+        self.proceed_pickups(dt, &config.pickups, &config.health, rng);
+    }
+
+    /// Counts down buffs, spawns pickups on timer and gives them to characters which touch them
+    fn proceed_pickups<R: Rng>(
+        &mut self,
+        dt: Duration,
+        config: &PickupsConfig,
+        health: &HealthConfig,
+        rng: &mut R,
+    ) {
+        for mut e in self.entities_mut() {
+            Buff::proceed(&mut e.buffs, dt);
+        }
+
+        let pickups: Vec<_> = self
+            .entities()
+            .filter_map(|e| match &e.role {
+                EntityRole::Pickup { kind } => {
+                    Some((e.id, e.pos, e.inscribed_circle_radius(), kind.clone()))
+                }
+                _ => None,
+            })
+            .collect();
+
+        if pickups.len() < config.max_count && !config.kinds.is_empty() {
+            let remaining = self
+                .pickup_spawn_remaining
+                .get_or_insert(config.spawn_interval);
+            *remaining = remaining.saturating_sub(dt);
+            if remaining.is_zero() {
+                *remaining = config.spawn_interval;
+                let free_points: Vec<_> = self
+                    .map
+                    .pickup_points
+                    .iter()
+                    .filter(|x| !pickups.iter().any(|(_, pos, ..)| (**x - *pos).len() < 1.))
+                    .collect();
+                let pos = if self.map.pickup_points.is_empty() {
+                    Some(self.random_point_outside_walls(rng))
+                } else if free_points.is_empty() {
+                    None
+                } else {
+                    Some(*free_points[rng.random_range(0..free_points.len())])
+                };
+                if let Some(pos) = pos {
+                    let kind = config.kinds[rng.random_range(0..config.kinds.len())].clone();
+                    self.create(
+                        EntityCreateInfo {
+                            pos,
+                            rot: Complex { r: 1., i: 0. },
+                            color: Color {
+                                a: 255,
+                                r: 255,
+                                g: 255,
+                                b: 255,
+                            },
+                            role: EntityRole::Pickup { kind },
+                            player_weapon: PlayerWeapon::BallGun,
+                            tail: None,
+                        },
+                        WORLD_PLAYER_ID,
+                        health,
+                    );
+                }
+            }
+        }
+
+        let mut collected = vec![];
+        for mut character in self.entities_mut() {
+            if !matches!(character.role, EntityRole::Character { .. })
+                || character.health <= 0.
+                || self.kills.iter().any(|(id, _)| *id == character.id)
+            {
+                continue;
+            }
+            for (id, pos, radius, kind) in &pickups {
+                if collected.contains(id)
+                    || (character.pos - *pos).len() >= character.inscribed_circle_radius() + radius
+                {
+                    continue;
+                }
+                match kind {
+                    PickupKind::Health { amount } => {
+                        if character.health >= health.max {
+                            continue;
+                        }
+                        character.health = (character.health + amount).min(health.max);
+                    }
+                    PickupKind::Armor { amount } => {
+                        if character.armor >= health.max_armor {
+                            continue;
+                        }
+                        character.armor = (character.armor + amount).min(health.max_armor);
+                    }
+                    _ => Buff::apply(&mut character.buffs, kind.buff().unwrap()),
+                }
+                collected.push(*id);
+            }
+        }
+        self.entities
+            .retain(|e| !collected.contains(&e.borrow().id));
+    }
+
+    pub fn zone(&self) -> Option<&Zone> {
+        self.zone.as_ref()
+    }
+
+    /// Places zone in king of the hill mode, awards points to its holder and relocates it
+    fn proceed_zone<R: Rng>(&mut self, dt: Duration, config: &GameConfig, rng: &mut R) {
+        let GameMode::KingOfTheHill { teams } = config.mode else {
+            self.zone = None;
+            return;
+        };
+        let zone_config = &config.king_of_the_hill;
+        let world_bounds = self.map.bounds;
+        let zone = self
+            .zone
+            .get_or_insert_with(|| Zone::new(zone_config, world_bounds, rng));
+        if !self.match_state.combat_allowed() {
+            return;
+        }
+
+        let occupants: Vec<_> = self
+            .entities
+            .iter()
+            .map(|e| e.borrow())
+            .filter(|e| {
+                matches!(e.role, EntityRole::Character { .. })
+                    && e.health > 0.
+                    && zone.contains(e.pos, e.inscribed_circle_radius())
+            })
+            .filter_map(|e| match (teams, self.players.team(e.player_id)) {
+                (true, Some(team)) => Some(ZoneHolder::Team(team)),
+                (true, None) => None,
+                (false, _) => Some(ZoneHolder::Player(e.player_id)),
+            })
+            .collect();
+
+        let update = zone.proceed(dt, zone_config, &occupants);
+        if let Some(holder) = zone.holder {
+            if update.captured {
+                self.events.push(GameEvent::ZoneCaptured { holder });
+            }
+            if update.points != 0 {
+                self.scoreboard.record_zone_points(holder, update.points);
+            }
+        }
+        if update.relocate {
+            *zone = Zone::new(zone_config, world_bounds, rng);
+            self.events.push(GameEvent::ZoneMoved);
+        }
+    }
+
+    /// Home base of team flag in capture the flag mode
+    pub fn flag_home(&self, team: Team) -> Point {
+        if let Some(pos) = self.map.flag_base(team) {
+            return pos;
+        }
+        let bounds = self.map.bounds;
+        let y = bounds.y + bounds.h / 2.;
+        match team {
+            Team::Red => Point {
+                x: bounds.x + 48.,
+                y,
+            },
+            Team::Blue => Point {
+                x: bounds.x + bounds.w - 48.,
+                y,
+            },
+        }
+    }
+
+    /// Places flags in capture the flag mode and handles their pickup, drop, return and capture
+    fn proceed_flags(&mut self, config: &GameConfig) {
+        let has_flags = config.mode == GameMode::CaptureTheFlag;
+        self.entities
+            .retain(|e| has_flags || !matches!(e.borrow().role, EntityRole::Flag { .. }));
+        if !has_flags {
+            return;
+        }
+
+        for team in Team::ALL {
+            if !self
+                .entities()
+                .any(|e| matches!(e.role, EntityRole::Flag { team: x, .. } if x == team))
+            {
+                let home = self.flag_home(team);
+                self.create(
+                    EntityCreateInfo {
+                        pos: home,
+                        rot: Complex { r: 1., i: 0. },
+                        color: team.color(),
+                        role: EntityRole::Flag {
+                            team,
+                            home,
+                            carrier: None,
+                        },
+                        player_weapon: PlayerWeapon::BallGun,
+                        tail: None,
+                    },
+                    WORLD_PLAYER_ID,
+                    &config.health,
+                );
+            }
+        }
+
+        if !self.match_state.combat_allowed() {
+            return;
+        }
+
+        let characters: Vec<_> = self
+            .entities()
+            .filter(|e| {
+                matches!(e.role, EntityRole::Character { .. })
+                    && e.health > 0.
+                    && !self.kills.iter().any(|(id, _)| *id == e.id)
+            })
+            .map(|e| {
+                (
+                    e.player_id,
+                    e.pos,
+                    e.inscribed_circle_radius(),
+                    self.players.team(e.player_id),
+                )
+            })
+            .collect();
+
+        for flag in &self.entities {
+            let mut flag = flag.borrow_mut();
+            let radius = flag.inscribed_circle_radius();
+            let flag = flag.deref_mut();
+            let EntityRole::Flag {
+                team,
+                home,
+                carrier,
+            } = &mut flag.role
+            else {
+                continue;
+            };
+
+            if let Some(player_id) = *carrier {
+                match characters.iter().find(|(id, ..)| *id == player_id) {
+                    Some((_, pos, ..)) => flag.pos = *pos,
+                    None => {
+                        *carrier = None;
+                        self.events.push(GameEvent::FlagDropped { team: *team });
+                    }
+                }
+                continue;
+            }
+
+            for (player_id, pos, character_radius, player_team) in &characters {
+                if (*pos - flag.pos).len() >= radius + character_radius {
+                    continue;
+                }
+                match player_team {
+                    Some(player_team) if player_team != team => {
+                        *carrier = Some(*player_id);
+                        flag.pos = *pos;
+                        self.events.push(GameEvent::FlagTaken {
+                            player_id: *player_id,
+                            team: *team,
+                        });
+                        break;
+                    }
+                    Some(_) if flag.pos != *home => {
+                        flag.pos = *home;
+                        self.events.push(GameEvent::FlagReturned {
+                            player_id: *player_id,
+                            team: *team,
+                        });
+                        break;
+                    }
+                    _ => {}
+                }
+            }
+        }
+
+        let flags_at_home: Vec<_> = self
+            .entities()
+            .filter_map(|e| match e.role {
+                EntityRole::Flag {
+                    team,
+                    home,
+                    carrier: None,
+                } if e.pos == home => Some((team, home, e.inscribed_circle_radius())),
+                _ => None,
+            })
+            .collect();
+
+        for flag in &self.entities {
+            let mut flag = flag.borrow_mut();
+            let flag = flag.deref_mut();
+            let EntityRole::Flag {
+                team,
+                home,
+                carrier: Some(player_id),
+            } = flag.role
+            else {
+                continue;
+            };
+            let player_team = self.players.team(player_id);
+            if flags_at_home.iter().any(|(x, pos, radius)| {
+                Some(*x) == player_team && (*pos - flag.pos).len() < radius * 2.
+            }) {
+                flag.pos = home;
+                flag.role = EntityRole::Flag {
+                    team,
+                    home,
+                    carrier: None,
+                };
+                self.scoreboard.record_capture(player_id);
+                self.events
+                    .push(GameEvent::FlagCaptured { player_id, team });
+            }
+        }
+    }
+
+    /// Returns false if entity is already killed
+    fn register_kill(&mut self, id: u32, cause: KillCause) -> bool {
+        if self.kills.iter().any(|(x, _)| *x == id) {
+            return false;
+        }
+        self.kills.push((id, cause));
+        true
+    }
+
+    /// Whether character has health and is not waiting to be removed after a kill
+    pub fn alive(&self, character: &Entity) -> bool {
+        character.health > 0. && !self.kills.iter().any(|(id, _)| *id == character.id)
+    }
+
+    /// Registers kill of a character which is killed by its own player deliberately. Ignored if
+    /// the character is already killed
+    pub fn register_suicide(&mut self, id: u32, player_id: NonZero<u64>, weapon: PlayerWeapon) {
+        if !self.register_kill(
+            id,
+            KillCause {
+                killer: player_id,
+                weapon,
+            },
+        ) {
+            return;
+        }
+        self.scoreboard.record_suicide(player_id);
+        self.events.push(GameEvent::Suicide { player_id });
+    }
+
+    /// Applies hit to alive character. Armor absorbs damage first, damage from teammates is
+    /// limited by friendly fire setting. Returns false if the hit is not allowed at all
+    fn damage_character(
+        kills: &mut Vec<(u32, KillCause)>,
+        scoreboard: &mut Scoreboard,
+        events: &mut EventLog,
+        character: &mut Entity,
+        hit: Hit,
+        config: &GameConfig,
+    ) -> bool {
+        let damage = if hit.friendly {
+            config
+                .friendly_fire
+                .limit_damage(character.health + character.armor, hit.damage)
+        } else {
+            hit.damage
+        };
+        if hit.friendly && damage <= 0. {
+            return false;
+        }
+        let absorbed = damage.min(character.armor);
+        character.armor -= absorbed;
+        character.health = (character.health - (damage - absorbed)).max(0.);
+        character.regen_cooldown = config.health.regen_delay();
+        events.push(GameEvent::Damage {
+            player_id: character.player_id,
+            attacker: hit.cause.killer,
+            amount: damage,
+        });
+        if character.health <= 0. {
+            Self::record_character_kill(
+                kills,
+                scoreboard,
+                events,
+                character,
+                hit.cause,
+                config.mode,
+            );
+        }
+        true
+    }
+
+    fn record_character_kill(
+        kills: &mut Vec<(u32, KillCause)>,
+        scoreboard: &mut Scoreboard,
+        events: &mut EventLog,
+        character: &Entity,
+        cause: KillCause,
+        mode: GameMode,
+    ) {
+        kills.push((character.id, cause));
+        scoreboard.record_kill(character.player_id, cause.killer, mode);
+        events.push(GameEvent::Kill {
+            killer: cause.killer,
+            victim: character.player_id,
+            weapon: cause.weapon,
+        });
+    }
+
+    pub fn players(&self) -> &PlayerRegistry {
+        &self.players
+    }
+
+    pub fn add_player(&mut self, info: PlayerInfo) {
+        let player_id = info.player_id;
+        self.scoreboard.add_player(player_id, info.team);
+        self.players.add(info);
+        self.events.push(GameEvent::Join { player_id });
+    }
+
+    /// Removes player with its character and stats
+    pub fn remove_player(&mut self, player_id: NonZero<u64>) {
+        self.entities.retain(|e| {
+            let e = e.borrow();
+            !matches!(e.role, EntityRole::Character { .. }) || e.player_id != player_id
+        });
+        self.scoreboard.remove_player(player_id);
+        if let Some(player) = self.players.remove(player_id) {
+            self.events.push(GameEvent::Leave { player });
         }
     }
 
-    pub(crate) fn register_kill(&mut self, id: u32) {
-        assert!(!self.kills.contains(&id));
-        self.kills.push(id);
+    pub fn events(&self) -> &EventLog {
+        &self.events
+    }
+
+    pub fn time(&self) -> Duration {
+        self.time
+    }
+
+    pub fn scoreboard(&self) -> &Scoreboard {
+        &self.scoreboard
+    }
+
+    pub fn match_state(&self) -> &MatchState {
+        &self.match_state
+    }
+
+    /// Advances match timers and restarts map when new round begins
+    pub fn proceed_match<R: Rng>(&mut self, dt: Duration, config: &GameConfig, rng: &mut R) {
+        match self
+            .match_state
+            .proceed(dt, &config.match_rules, &self.scoreboard)
+        {
+            Some(MatchTransition::RoundStarted) => {
+                self.restart(&config.health, rng);
+                self.events.push(GameEvent::RoundStarted {
+                    round: self.match_state.round(),
+                });
+            }
+            Some(MatchTransition::RoundEnded) => {
+                self.events.push(GameEvent::RoundEnded {
+                    winner: self.scoreboard.ranked().first().map(|x| x.player_id),
+                    winning_team: self.scoreboard.leading_team(),
+                });
+            }
+            None => {}
+        }
     }
 
-    pub(crate) fn account_kill(&mut self, player_id: NonZero<u64>) -> bool {
-        let orig_len = self.entities.len();
+    /// Removes projectiles and pickups, moves alive characters to random points, returns flags,
+    /// replaces zone and resets scores.
+    /// Characters waiting for kill to be accounted are kept as is
+    fn restart<R: Rng>(&mut self, health: &HealthConfig, rng: &mut R) {
+        self.entities.retain(|e| {
+            let e = e.borrow();
+            matches!(
+                e.role,
+                EntityRole::Character { .. } | EntityRole::Flag { .. }
+            )
+        });
+        for e in &self.entities {
+            let mut e = e.borrow_mut();
+            if let EntityRole::Flag { team, home, .. } = e.role {
+                e.pos = home;
+                e.role = EntityRole::Flag {
+                    team,
+                    home,
+                    carrier: None,
+                };
+            } else if !self.kills.iter().any(|(id, _)| *id == e.id) {
+                e.pos = self.spawn_point(e.player_id, rng);
+                e.rot = Complex { r: 1., i: 0. };
+                e.health = health.max;
+                e.armor = 0.;
+                e.regen_cooldown = Duration::ZERO;
+                e.age = Duration::ZERO;
+                e.buffs.clear();
+                // Knockback sum is kept, so that client does not replay it again
+                e.movement = MovementState {
+                    knockback: e.movement.knockback,
+                    ..Default::default()
+                };
+                if let EntityRole::Character { weapon } = &e.role {
+                    e.weapon_state = WeaponState::new(weapon.magazine());
+                }
+            }
+        }
+        self.zone = None;
+        self.pickup_spawn_remaining = None;
+        self.scoreboard.reset();
+    }
+
+    /// Removes killed character of the player. Returns kill cause if character was killed
+    pub fn account_kill(&mut self, player_id: NonZero<u64>) -> Option<KillCause> {
+        let mut cause = None;
         self.entities.retain(|e| {
             let e = e.borrow();
             match e.role {
                 EntityRole::Character { .. } if e.player_id == player_id => {
-                    if let Some(index) = self.kills.iter().position(|x| *x == e.id) {
-                        self.kills.remove(index);
+                    if let Some(index) = self.kills.iter().position(|(id, _)| *id == e.id) {
+                        cause = Some(self.kills.remove(index).1);
                         return false;
                     }
                 }
@@ -715,10 +2187,10 @@ impl GameState {
             }
             true
         });
-        orig_len != self.entities.len()
+        cause
     }
 
-    pub(crate) fn lerp_merge(
+    pub fn lerp_merge(
         result: &mut Self,
         a: &Self,
         b: &Self,
@@ -739,10 +2211,20 @@ impl GameState {
     }
 }
 
+impl Default for GameState {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
-pub(crate) struct PlayerState {
-    pub(crate) color: Color,
-    pub(crate) killed: bool,
+pub struct PlayerState {
+    pub color: Color,
+    pub killed: bool,
+    /// Weapons of loadout accepted by server at last respawn
+    pub loadout: Vec<PlayerWeapon>,
+    /// Cooldown and ammo of own character weapon
+    pub weapon_state: WeaponState,
 }
 
 impl Default for PlayerState {
@@ -755,6 +2237,1137 @@ impl Default for PlayerState {
                 b: 0,
             },
             killed: Default::default(),
+            loadout: Default::default(),
+            weapon_state: Default::default(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        Entity, EntityCreateInfo, EntityRole, Explosion, GameState, Hit, KillCause, ProjectileKind,
+        SecondaryAction,
+    };
+    use crate::common::fixtures::{
+        ball, ball_kind, ball_with, character, grenade, mine, missile, ray, white,
+    };
+    use crate::common::{
+        Buff, Complex, FriendlyFire, GameConfig, GameEvent, GameMode, HealthConfig,
+        InteractionEffect, InteractionRule, Map, MatchPhase, MatchRules, MovementInput, PickupKind,
+        PickupsConfig, PlayerInfo, PlayerWeapon, Point, ProjectileClass, ProjectileInteractions,
+        Rect, RegenConfig, Segment, Team, Vector, ZoneHolder,
+    };
+    use rand::rng;
+    use std::{num::NonZero, sync::Arc, time::Duration};
+
+    fn projectile_classes(game_state: &GameState) -> Vec<ProjectileClass> {
+        game_state
+            .entities()
+            .filter_map(|e| match &e.role {
+                EntityRole::Projectile { kind } => Some(kind.class()),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn walls() {
+        let p = [
+            Point { x: 200., y: 200. },
+            Point { x: 250., y: 200. },
+            Point { x: 250., y: 250. },
+            Point { x: 200., y: 250. },
+        ];
+        let mut map: Map = Default::default();
+        map.walls.push(
+            (0..4)
+                .map(|i| Segment {
+                    p0: p[i],
+                    p1: p[(i + 1) % 4],
+                })
+                .collect(),
+        );
+        let mut game_state = GameState::new();
+        game_state.set_map(Arc::new(map));
+        let player_id = NonZero::new(1).unwrap();
+
+        let projectile = ball_with(Point { x: 185., y: 225. }, ball_kind(200., 1, 1.));
+        game_state.create(projectile, player_id, &Default::default());
+        game_state.proceed(Duration::from_millis(50), &Default::default(), &mut rng());
+        let projectile = game_state.entities().next().unwrap().clone();
+        assert!(projectile.pos.x < 200.);
+        assert!(projectile.rot.r < 0.);
+
+        let config: GameConfig = Default::default();
+        let mut entity = game_state.entities().next().unwrap().clone();
+        entity.role = EntityRole::Character {
+            weapon: config.character_weapon(PlayerWeapon::BallGun),
+        };
+        entity.pos = Point { x: 205., y: 225. };
+        game_state.push_out_of_walls(&mut entity);
+        assert!(entity.pos.x <= 200.);
+        assert!(!game_state.map().wall_at(entity.pos));
+
+        let mut map: Map = Default::default();
+        let bounds = map.bounds;
+        map.walls.push(
+            Rect {
+                x: bounds.x - 1.,
+                y: bounds.y - 1.,
+                w: bounds.w + 2.,
+                h: bounds.h + 2.,
+            }
+            .edges()
+            .to_vec(),
+        );
+        game_state.set_map(Arc::new(map));
+        assert_eq!(
+            game_state.spawn_point(player_id, &mut rng()),
+            bounds.center(),
+            "falls back when walls cover whole map"
+        );
+    }
+
+    #[test]
+    fn round_start_restarts_map() {
+        let config = GameConfig {
+            match_rules: MatchRules {
+                min_players: 2,
+                countdown_duration: Duration::from_secs(1),
+                ..Default::default()
+            },
+            spawn_protection: Duration::ZERO,
+            ..Default::default()
+        };
+        let dt = Duration::from_millis(500);
+        let a = NonZero::new(1).unwrap();
+        let b = NonZero::new(2).unwrap();
+        let mut game_state = GameState::new();
+        for (player_id, x) in [(a, 100.), (b, 300.)] {
+            game_state.add_player(PlayerInfo {
+                player_id,
+                name: player_id.to_string(),
+                color: white(),
+                team: None,
+            });
+            game_state.create(
+                character(
+                    Point { x, y: 100. },
+                    config.character_weapon(PlayerWeapon::BallGun),
+                ),
+                player_id,
+                &config.health,
+            );
+        }
+        game_state
+            .find_character_by_player_id_mut(b)
+            .unwrap()
+            .health = 1.;
+        game_state.create(ball(Point { x: 300., y: 100. }), a, &config.health);
+        game_state.proceed(Duration::from_millis(10), &config, &mut rng());
+        let id = game_state.find_character_by_player_id_mut(a).unwrap().id;
+        game_state.register_suicide(id, a, PlayerWeapon::Shield);
+        game_state.create(ball(Point { x: 200., y: 200. }), a, &config.health);
+        assert_eq!(game_state.scoreboard().stats(a).unwrap().suicides, 1);
+        assert_eq!(game_state.scoreboard().stats(b).unwrap().deaths, 1);
+
+        let mut rng = rng();
+        for _ in 0..3 {
+            game_state.proceed_match(dt, &config, &mut rng);
+        }
+
+        assert!(matches!(
+            game_state.match_state().phase(),
+            MatchPhase::Round { .. }
+        ));
+        assert_eq!(
+            game_state
+                .entities()
+                .filter(|e| matches!(e.role, EntityRole::Character { .. }))
+                .count(),
+            2
+        );
+        assert_eq!(game_state.entities().count(), 2);
+        assert!(game_state
+            .scoreboard()
+            .ranked()
+            .iter()
+            .all(|x| x.suicides == 0 && x.deaths == 0));
+        assert!(game_state.account_kill(a).is_some());
+        assert!(game_state.account_kill(b).is_some());
+    }
+
+    #[test]
+    fn no_combat_during_countdown() {
+        let config = GameConfig {
+            mode: GameMode::KingOfTheHill { teams: false },
+            match_rules: MatchRules {
+                min_players: 2,
+                countdown_duration: Duration::from_secs(60),
+                ..Default::default()
+            },
+            spawn_protection: Duration::ZERO,
+            ..Default::default()
+        };
+        let dt = Duration::from_secs(1);
+        let a = NonZero::new(1).unwrap();
+        let b = NonZero::new(2).unwrap();
+        let mut game_state = GameState::new();
+        for player_id in [a, b] {
+            game_state.add_player(PlayerInfo {
+                player_id,
+                name: player_id.to_string(),
+                color: white(),
+                team: None,
+            });
+        }
+        game_state.proceed_match(dt, &config, &mut rng());
+        assert!(matches!(
+            game_state.match_state().phase(),
+            MatchPhase::Countdown { .. }
+        ));
+
+        game_state.proceed(Duration::ZERO, &config, &mut rng());
+        let center = game_state.zone().unwrap().center;
+        game_state.create(
+            character(center, config.character_weapon(PlayerWeapon::BallGun)),
+            a,
+            &config.health,
+        );
+        game_state.create(ball(center), b, &config.health);
+        for _ in 0..10 {
+            game_state.proceed(dt, &config, &mut rng());
+        }
+
+        assert_eq!(
+            game_state
+                .find_character_by_player_id_mut(a)
+                .unwrap()
+                .health,
+            config.health.max
+        );
+        assert_eq!(game_state.zone().unwrap().holder, None);
+        assert_eq!(game_state.scoreboard().stats(a).unwrap().zone_points, 0);
+    }
+
+    #[test]
+    fn safe_spawn_and_protection() {
+        let spawn_points = [
+            Point { x: 100., y: 100. },
+            Point { x: 400., y: 100. },
+            Point { x: 400., y: 400. },
+        ];
+        let map = Map {
+            spawn_points: spawn_points.to_vec(),
+            ..Default::default()
+        };
+        let mut game_state = GameState::new();
+        game_state.set_map(Arc::new(map));
+        let config: GameConfig = Default::default();
+        let player_id = NonZero::new(1).unwrap();
+        let enemy = NonZero::new(2).unwrap();
+
+        game_state.create(
+            character(
+                spawn_points[0],
+                config.character_weapon(PlayerWeapon::BallGun),
+            ),
+            enemy,
+            &config.health,
+        );
+        let projectile = ball_with(Point { x: 300., y: 100. }, ball_kind(200., 1, 1.));
+        game_state.create(projectile, enemy, &config.health);
+        for _ in 0..8 {
+            assert_eq!(
+                game_state.spawn_point(player_id, &mut rng()),
+                spawn_points[2]
+            );
+        }
+
+        let pos = game_state.spawn_point(player_id, &mut rng());
+        game_state.create(
+            character(pos, config.character_weapon(PlayerWeapon::BallGun)),
+            player_id,
+            &config.health,
+        );
+        game_state.create(ball(pos), enemy, &config.health);
+        game_state.proceed(Duration::from_millis(10), &config, &mut rng());
+        let character = game_state
+            .find_character_by_player_id_mut(player_id)
+            .unwrap()
+            .clone();
+        assert!(character.protected);
+        assert_eq!(character.health, 3.);
+
+        let config = GameConfig {
+            spawn_protection: Duration::ZERO,
+            ..config
+        };
+        game_state.proceed(Duration::from_millis(10), &config, &mut rng());
+        let character = game_state
+            .find_character_by_player_id_mut(player_id)
+            .unwrap()
+            .clone();
+        assert!(!character.protected);
+        assert_eq!(character.health, 2.);
+    }
+
+    #[test]
+    fn pickups() {
+        let pos = Point { x: 200., y: 200. };
+        let map = Map {
+            pickup_points: vec![pos],
+            ..Default::default()
+        };
+        let mut game_state = GameState::new();
+        game_state.set_map(Arc::new(map));
+        let mut config = GameConfig {
+            pickups: PickupsConfig {
+                spawn_interval: Duration::from_secs(1),
+                max_count: 1,
+                kinds: vec![PickupKind::Health { amount: 5. }],
+            },
+            ..Default::default()
+        };
+        let dt = Duration::from_millis(10);
+        let player_id = NonZero::new(1).unwrap();
+        game_state.create(
+            character(pos, config.character_weapon(PlayerWeapon::BallGun)),
+            player_id,
+            &config.health,
+        );
+        let pickup_count = |game_state: &GameState| {
+            game_state
+                .entities()
+                .filter(|e| matches!(e.role, EntityRole::Pickup { .. }))
+                .count()
+        };
+
+        game_state.proceed(dt, &config, &mut rng());
+        assert_eq!(
+            pickup_count(&game_state),
+            0,
+            "spawn interval has not passed yet"
+        );
+        game_state.proceed(Duration::from_secs(1), &config, &mut rng());
+        assert_eq!(pickup_count(&game_state), 1);
+        game_state.proceed(dt, &config, &mut rng());
+        assert_eq!(pickup_count(&game_state), 1, "health is already full");
+
+        game_state
+            .find_character_by_player_id_mut(player_id)
+            .unwrap()
+            .health = 1.;
+        game_state.proceed(dt, &config, &mut rng());
+        assert_eq!(pickup_count(&game_state), 0);
+        let character = game_state
+            .find_character_by_player_id_mut(player_id)
+            .unwrap()
+            .clone();
+        assert_eq!(character.health, config.health.max);
+
+        config.pickups.kinds = vec![PickupKind::SpeedBoost {
+            multiplier: 2.,
+            duration: Duration::from_millis(50),
+        }];
+        game_state.proceed(Duration::from_secs(1), &config, &mut rng());
+        game_state.proceed(dt, &config, &mut rng());
+        let character = game_state
+            .find_character_by_player_id_mut(player_id)
+            .unwrap()
+            .clone();
+        assert_eq!(Buff::speed_multiplier(&character.buffs), 2.);
+
+        game_state.proceed(Duration::from_millis(50), &config, &mut rng());
+        let character = game_state
+            .find_character_by_player_id_mut(player_id)
+            .unwrap()
+            .clone();
+        assert!(character.buffs.is_empty());
+    }
+
+    #[test]
+    fn homing_missile() {
+        let pos = Point { x: 100., y: 100. };
+        let forward = Complex { r: 1., i: 0. };
+        let above = Point { x: 200., y: 90. };
+        let behind = Point { x: 50., y: 100. };
+
+        let rot = GameState::steer(pos, forward, &[above, behind], 1., 0.01);
+        assert!((rot.i - (-0.01f32).sin()).abs() < 1e-5);
+        let rot = GameState::steer(pos, forward, &[above], 1., 1.);
+        assert!((Vector::polar(rot, 1.).cross(above - pos)).abs() < 1e-3);
+        assert_eq!(GameState::steer(pos, forward, &[behind], 1., 1.), forward);
+
+        let config: GameConfig = Default::default();
+        let a = NonZero::new(1).unwrap();
+        let b = NonZero::new(2).unwrap();
+        let dt = Duration::from_millis(10);
+        let projectile_count = |game_state: &GameState| {
+            game_state
+                .entities()
+                .filter(|e| matches!(e.role, EntityRole::Projectile { .. }))
+                .count()
+        };
+
+        let mut game_state = GameState::new();
+        game_state.create(missile(pos), a, &config.health);
+        let step = Duration::from_millis(100);
+        game_state.proceed(step, &config, &mut rng());
+        let EntityRole::Projectile {
+            kind: ProjectileKind::Missile { velocity, .. },
+        } = missile(pos).role
+        else {
+            panic!("Unexpected projectile");
+        };
+        let missile_pos = game_state.entities().next().unwrap().pos;
+        assert!(
+            missile_pos.x > pos.x + velocity * step.as_secs_f32(),
+            "moves with accelerated velocity"
+        );
+
+        let mut game_state = GameState::new();
+        game_state.create(missile(pos), a, &config.health);
+        game_state.create(ball(pos), b, &config.health);
+        game_state.proceed(dt, &config, &mut rng());
+        assert_eq!(projectile_count(&game_state), 0);
+
+        let config = GameConfig {
+            spawn_protection: Duration::ZERO,
+            ..config.clone()
+        };
+        let mut game_state = GameState::new();
+        game_state.create(
+            EntityCreateInfo {
+                rot: Complex { r: -1., i: 0. },
+                ..character(
+                    pos + Vector { x: 60., y: 0. },
+                    config.character_weapon(PlayerWeapon::Shield),
+                )
+            },
+            b,
+            &config.health,
+        );
+        game_state.create(missile(pos), a, &config.health);
+        for _ in 0..50 {
+            game_state.proceed(dt, &config, &mut rng());
+        }
+        assert_eq!(projectile_count(&game_state), 0);
+        assert_eq!(
+            game_state
+                .find_character_by_player_id_mut(b)
+                .unwrap()
+                .health,
+            config.health.max
+        );
+    }
+
+    #[test]
+    fn explosion() {
+        let explosion = Explosion {
+            pos: Point { x: 150., y: 225. },
+            radius: 150.,
+            damage: 4.,
+            cause: KillCause {
+                killer: NonZero::new(4).unwrap(),
+                weapon: PlayerWeapon::GrenadeLauncher,
+            },
+        };
+        assert_eq!(explosion.damage_at(0.), 4.);
+        assert_eq!(explosion.damage_at(75.), 2.);
+        assert_eq!(explosion.damage_at(150.), 0.);
+        assert_eq!(explosion.damage_at(200.), 0.);
+
+        let p = [
+            Point { x: 200., y: 200. },
+            Point { x: 250., y: 200. },
+            Point { x: 250., y: 250. },
+            Point { x: 200., y: 250. },
+        ];
+        let map = Map {
+            walls: vec![(0..4)
+                .map(|i| Segment {
+                    p0: p[i],
+                    p1: p[(i + 1) % 4],
+                })
+                .collect()],
+            ..Default::default()
+        };
+        let config: GameConfig = Default::default();
+        let weapon = config.character_weapon(PlayerWeapon::BallGun);
+        let health = |game_state: &GameState, player_id| {
+            game_state
+                .find_character_by_player_id_mut(player_id)
+                .unwrap()
+                .health
+        };
+        let near = NonZero::new(1).unwrap();
+        let far = NonZero::new(2).unwrap();
+        let behind_wall = NonZero::new(3).unwrap();
+
+        let mut game_state = GameState::new();
+        game_state.set_map(Arc::new(map));
+        game_state.create(
+            character(Point { x: 170., y: 225. }, weapon.clone()),
+            near,
+            &config.health,
+        );
+        game_state.create(
+            character(Point { x: 150., y: 290. }, weapon.clone()),
+            far,
+            &config.health,
+        );
+        game_state.create(
+            character(Point { x: 270., y: 225. }, weapon.clone()),
+            behind_wall,
+            &config.health,
+        );
+        game_state.explode(explosion, &config);
+        assert_eq!(health(&game_state, near), 0.);
+        assert!((health(&game_state, far) - 0.52).abs() < 1e-3);
+        assert_eq!(health(&game_state, behind_wall), config.health.max);
+        assert_eq!(game_state.kills.len(), 1);
+
+        let config = GameConfig {
+            spawn_protection: Duration::ZERO,
+            ..config.clone()
+        };
+        let mut game_state = GameState::new();
+        game_state.create(
+            character(Point { x: 100., y: 130. }, weapon),
+            near,
+            &config.health,
+        );
+        let mut fused_grenade = grenade(Point { x: 100., y: 100. });
+        if let EntityRole::Projectile {
+            kind: ProjectileKind::Grenade { fuse_duration, .. },
+        } = &mut fused_grenade.role
+        {
+            *fuse_duration = Duration::ZERO;
+        }
+        game_state.create(fused_grenade, far, &config.health);
+        game_state.proceed(Duration::from_millis(10), &config, &mut rng());
+        assert!(!game_state
+            .entities()
+            .any(|e| matches!(e.role, EntityRole::Projectile { .. })));
+        let damaged = health(&game_state, near);
+        assert!(damaged > config.health.max - 1. && damaged < config.health.max);
+    }
+
+    #[test]
+    fn balls_destroy_mines() {
+        let pos = Point { x: 100., y: 100. };
+        let a = NonZero::new(1).unwrap();
+        let b = NonZero::new(2).unwrap();
+        let config: GameConfig = Default::default();
+        let dt = Duration::from_millis(10);
+
+        let mut game_state = GameState::new();
+        game_state.create(ball(pos), a, &config.health);
+        game_state.create(mine(pos + Vector { x: 8., y: 0. }), b, &config.health);
+        game_state.create(mine(pos + Vector { x: 0., y: 50. }), b, &config.health);
+        game_state.proceed(dt, &config, &mut rng());
+        assert_eq!(projectile_classes(&game_state), vec![ProjectileClass::Mine]);
+
+        let sturdy_ball = ball_with(pos, ball_kind(0., 2, 1.));
+        let mut game_state = GameState::new();
+        game_state.create(sturdy_ball, a, &config.health);
+        game_state.create(mine(pos), b, &config.health);
+        game_state.create(mine(pos), a, &config.health);
+        game_state.proceed(dt, &config, &mut rng());
+        assert_eq!(
+            projectile_classes(&game_state),
+            vec![ProjectileClass::Ball, ProjectileClass::Mine]
+        );
+        assert_eq!(game_state.entities().next().unwrap().health, 1.);
+    }
+
+    #[test]
+    fn rays_cut_balls() {
+        let pos = Point { x: 100., y: 100. };
+        let a = NonZero::new(1).unwrap();
+        let b = NonZero::new(2).unwrap();
+        let config: GameConfig = Default::default();
+
+        let mut game_state = GameState::new();
+        game_state.create(ray(pos), a, &config.health);
+        game_state.create(ball(pos + Vector { x: 10., y: 0. }), b, &config.health);
+        game_state.create(ball(pos + Vector { x: 10., y: 20. }), b, &config.health);
+        game_state.proceed(Duration::from_millis(10), &config, &mut rng());
+        assert_eq!(
+            projectile_classes(&game_state),
+            vec![ProjectileClass::Ray, ProjectileClass::Ball]
+        );
+    }
+
+    #[test]
+    fn projectiles_trigger_mines() {
+        let pos = Point { x: 100., y: 100. };
+        let a = NonZero::new(1).unwrap();
+        let b = NonZero::new(2).unwrap();
+        let config: GameConfig = Default::default();
+        let dt = Duration::from_millis(10);
+
+        let mut game_state = GameState::new();
+        game_state.create(ray(pos), a, &config.health);
+        game_state.create(mine(pos + Vector { x: 10., y: 0. }), b, &config.health);
+        game_state.proceed(dt, &config, &mut rng());
+        let classes = projectile_classes(&game_state);
+        assert_eq!(classes[0], ProjectileClass::Ray);
+        assert_eq!(classes[1..], [ProjectileClass::Ball; 4]);
+
+        let mut game_state = GameState::new();
+        game_state.create(missile(pos), a, &config.health);
+        game_state.create(mine(pos), b, &config.health);
+        game_state.proceed(dt, &config, &mut rng());
+        assert_eq!(
+            projectile_classes(&game_state),
+            vec![
+                ProjectileClass::Missile,
+                ProjectileClass::Ball,
+                ProjectileClass::Ball,
+                ProjectileClass::Ball,
+                ProjectileClass::Ball
+            ]
+        );
+
+        let config = GameConfig {
+            spawn_protection: Duration::ZERO,
+            projectile_interactions: ProjectileInteractions {
+                rules: vec![InteractionRule {
+                    first: ProjectileClass::Ball,
+                    second: ProjectileClass::Grenade,
+                    effect: InteractionEffect::Trigger,
+                }],
+            },
+            ..config.clone()
+        };
+        config.validate().unwrap();
+        let mut game_state = GameState::new();
+        game_state.create(
+            character(
+                pos + Vector { x: 0., y: 30. },
+                config.character_weapon(PlayerWeapon::BallGun),
+            ),
+            a,
+            &config.health,
+        );
+        game_state.create(ball(pos), a, &config.health);
+        game_state.create(grenade(pos), b, &config.health);
+        game_state.proceed(dt, &config, &mut rng());
+        assert_eq!(projectile_classes(&game_state), vec![ProjectileClass::Ball]);
+        assert!(
+            game_state
+                .find_character_by_player_id_mut(a)
+                .unwrap()
+                .health
+                < config.health.max
+        );
+
+        let config = GameConfig {
+            spawn_protection: Duration::ZERO,
+            ..Default::default()
+        };
+        let mut game_state = GameState::new();
+        game_state.create(
+            character(
+                pos + Vector { x: 0., y: 30. },
+                config.character_weapon(PlayerWeapon::BallGun),
+            ),
+            a,
+            &config.health,
+        );
+        let mut exploding_mine = mine(pos + Vector { x: 10., y: 0. });
+        if let EntityRole::Projectile {
+            kind:
+                ProjectileKind::Mine {
+                    explosion_radius,
+                    explosion_damage,
+                    ..
+                },
+        } = &mut exploding_mine.role
+        {
+            *explosion_radius = 50.;
+            *explosion_damage = 1.;
+        }
+        game_state.create(exploding_mine, b, &config.health);
+        game_state.create(ray(pos), a, &config.health);
+        let id = game_state.events().next_id();
+        game_state.proceed(dt, &config, &mut rng());
+        assert!(game_state
+            .events()
+            .since(id)
+            .any(|e| matches!(e, GameEvent::Explosion { player_id, .. } if *player_id == b)));
+        assert!(
+            game_state
+                .find_character_by_player_id_mut(a)
+                .unwrap()
+                .health
+                < config.health.max
+        );
+    }
+
+    #[test]
+    fn secondary_actions() {
+        let charge_duration = Duration::from_secs(2);
+        assert_eq!(
+            SecondaryAction::charge_multiplier(charge_duration, 3., Duration::ZERO),
+            1.
+        );
+        assert_eq!(
+            SecondaryAction::charge_multiplier(charge_duration, 3., Duration::from_secs(1)),
+            2.
+        );
+        assert_eq!(
+            SecondaryAction::charge_multiplier(charge_duration, 3., Duration::from_secs(5)),
+            3.
+        );
+
+        let a = NonZero::new(1).unwrap();
+        let b = NonZero::new(2).unwrap();
+        let mut game_state = GameState::new();
+        game_state.create(mine(Point { x: 100., y: 100. }), a, &Default::default());
+        game_state.create(mine(Point { x: 300., y: 300. }), b, &Default::default());
+        game_state.detonate_mines(a, &Default::default());
+        game_state.detonate_mines(a, &Default::default());
+        game_state.proceed(Duration::from_millis(10), &Default::default(), &mut rng());
+        let classes = projectile_classes(&game_state);
+        assert_eq!(classes[0], ProjectileClass::Mine);
+        assert_eq!(classes[1..], [ProjectileClass::Ball; 4]);
+        assert!(game_state
+            .entities()
+            .any(|e| e.player_id == b && e.pos == Point { x: 300., y: 300. }));
+    }
+
+    #[test]
+    fn weapon_state() {
+        let config: GameConfig = Default::default();
+        let weapon = config.character_weapon(PlayerWeapon::GrenadeLauncher);
+        let magazine = *weapon.magazine().unwrap();
+        let player_id = NonZero::new(1).unwrap();
+        let mut game_state = GameState::new();
+        game_state.create(
+            character(Point { x: 100., y: 100. }, weapon),
+            player_id,
+            &config.health,
+        );
+        {
+            let mut character = game_state
+                .find_character_by_player_id_mut(player_id)
+                .unwrap();
+            assert_eq!(character.weapon_state.ammo.unwrap().loaded, magazine.size);
+            character
+                .weapon_state
+                .start_cooldown(Duration::from_millis(20));
+            character.weapon_state.consume_round(Some(&magazine));
+            assert!(!character.weapon_state.ready());
+        }
+
+        game_state.proceed(Duration::from_millis(20), &config, &mut rng());
+        let character = game_state
+            .find_character_by_player_id_mut(player_id)
+            .unwrap();
+        assert!(character.weapon_state.ready());
+        assert_eq!(
+            character.weapon_state.ammo.unwrap().loaded,
+            magazine.size - 1
+        );
+    }
+
+    #[test]
+    fn collision_and_knockback() {
+        let config = GameConfig {
+            spawn_protection: Duration::ZERO,
+            ..Default::default()
+        };
+        let weapon = config.character_weapon(PlayerWeapon::BallGun);
+        let a = NonZero::new(1).unwrap();
+        let b = NonZero::new(2).unwrap();
+        let c = NonZero::new(3).unwrap();
+        let mut game_state = GameState::new();
+        game_state.create(
+            character(Point { x: 100., y: 100. }, weapon.clone()),
+            a,
+            &config.health,
+        );
+        game_state.create(
+            character(Point { x: 110., y: 100. }, weapon.clone()),
+            b,
+            &config.health,
+        );
+        game_state.create(
+            character(Point { x: 308., y: 100. }, weapon.clone()),
+            c,
+            &config.health,
+        );
+        {
+            let mut character = game_state.find_character_by_player_id_mut(a).unwrap();
+            game_state.push_out_of_characters(&mut character);
+            assert!((character.pos.x - 97.).abs() < 1e-3);
+        }
+        let pos = |game_state: &GameState, player_id| {
+            game_state
+                .find_character_by_player_id_mut(player_id)
+                .unwrap()
+                .pos
+        };
+        assert!((pos(&game_state, b).x - 113.).abs() < 1e-3);
+
+        game_state.create(ball(Point { x: 300., y: 100. }), b, &config.health);
+        game_state.proceed(Duration::from_millis(10), &config, &mut rng());
+        let impulse = |game_state: &GameState, player_id| {
+            game_state
+                .find_character_by_player_id_mut(player_id)
+                .unwrap()
+                .movement
+                .impulse
+        };
+        assert!((impulse(&game_state, c).x - config.knockback.ball).abs() < 1e-3);
+
+        game_state.explode(
+            Explosion {
+                pos: Point { x: 97., y: 160. },
+                radius: 150.,
+                damage: 0.,
+                cause: KillCause {
+                    killer: c,
+                    weapon: PlayerWeapon::GrenadeLauncher,
+                },
+            },
+            &config,
+        );
+        let impulse = impulse(&game_state, a);
+        assert_eq!(impulse.x, 0.);
+        assert!(impulse.y < 0. && impulse.y > -config.knockback.explosion);
+    }
+
+    #[test]
+    fn movement_on_tick() {
+        let config = GameConfig::default();
+        let player_id = NonZero::new(1).unwrap();
+        let proceed = |dts: &[Duration], input: MovementInput, impulse: Vector| {
+            let mut game_state = GameState::new();
+            game_state.create(
+                character(
+                    Point { x: 100., y: 300. },
+                    config.character_weapon(PlayerWeapon::BallGun),
+                ),
+                player_id,
+                &config.health,
+            );
+            {
+                let mut character = game_state
+                    .find_character_by_player_id_mut(player_id)
+                    .unwrap();
+                character.movement_input = input;
+                character.movement.push(impulse);
+            }
+            for dt in dts {
+                game_state.proceed(*dt, &config, &mut rng());
+            }
+            let character = game_state
+                .find_character_by_player_id_mut(player_id)
+                .unwrap();
+            (character.pos, character.movement)
+        };
+        let right = MovementInput {
+            right: true,
+            ..Default::default()
+        };
+        let zero = Vector { x: 0., y: 0. };
+
+        // Held input is stepped the same way however time is split into ticks
+        let (coarse, _) = proceed(&[Duration::from_millis(250); 2], right, zero);
+        let (fine, _) = proceed(&[Duration::from_millis(10); 50], right, zero);
+        assert_eq!(coarse, fine);
+        // Max speed is reached in a tenth of a second
+        assert!(coarse.x > 200. && coarse.x < 250.);
+
+        // Knockback of idle character is applied on the next step without any input
+        let impulse = Vector { x: 200., y: 0. };
+        let (pos, movement) = proceed(&[Duration::from_millis(20)], Default::default(), impulse);
+        assert!(pos.x > 100.);
+        assert_eq!(movement.impulse.len(), 0.);
+        assert_eq!(movement.knockback.x, impulse.x);
+    }
+
+    #[test]
+    fn friendly_fire() {
+        let pos = Point { x: 100., y: 100. };
+        for (friendly_fire, expected_health) in [
+            (FriendlyFire::Off, 1.),
+            (FriendlyFire::Reduced { factor: 0.25 }, 0.75),
+            (FriendlyFire::NonLethal, 1.),
+            (FriendlyFire::Full, 0.),
+        ] {
+            let config = GameConfig {
+                mode: GameMode::TeamDeathmatch,
+                friendly_fire,
+                spawn_protection: Duration::ZERO,
+                ..Default::default()
+            };
+            assert!(config.validate().is_ok());
+            let mut game_state = GameState::new();
+            for i in 1..=2 {
+                game_state.add_player(PlayerInfo {
+                    player_id: NonZero::new(i).unwrap(),
+                    name: format!("player{}", i),
+                    color: Team::Red.color(),
+                    team: Some(Team::Red),
+                });
+            }
+            let victim = NonZero::new(1).unwrap();
+            game_state.create(
+                character(pos, config.character_weapon(PlayerWeapon::BallGun)),
+                victim,
+                &config.health,
+            );
+            game_state
+                .find_character_by_player_id_mut(victim)
+                .unwrap()
+                .health = 1.;
+            game_state.create(ball(pos), NonZero::new(2).unwrap(), &config.health);
+
+            game_state.proceed(Duration::from_millis(10), &config, &mut rng());
+
+            assert_eq!(
+                game_state.entities().next().unwrap().health,
+                expected_health,
+                "{:?}",
+                friendly_fire
+            );
+            assert_eq!(
+                game_state.account_kill(victim).is_some(),
+                expected_health == 0.
+            );
+        }
+
+        for factor in [0., 1.5, f32::NAN] {
+            let config = GameConfig {
+                friendly_fire: FriendlyFire::Reduced { factor },
+                ..Default::default()
+            };
+            assert!(config.validate().is_err(), "{}", factor);
+        }
+    }
+
+    #[test]
+    fn armor_and_regeneration() {
+        let config = GameConfig {
+            spawn_protection: Duration::ZERO,
+            friendly_fire: FriendlyFire::NonLethal,
+            health: HealthConfig {
+                max: 4.,
+                max_armor: 2.,
+                regen: Some(RegenConfig {
+                    delay: Duration::from_secs(1),
+                    rate: 2.,
+                }),
+            },
+            ..Default::default()
+        };
+        let pos = Point { x: 100., y: 100. };
+        let player_id = NonZero::new(1).unwrap();
+        let mut game_state = GameState::new();
+        game_state.create(
+            character(pos, config.character_weapon(PlayerWeapon::BallGun)),
+            player_id,
+            &config.health,
+        );
+        game_state
+            .find_character_by_player_id_mut(player_id)
+            .unwrap()
+            .armor = 1.;
+        let heavy_ball = ball_with(pos, ball_kind(0., 1, 2.5));
+        game_state.create(heavy_ball, NonZero::new(2).unwrap(), &config.health);
+        game_state.proceed(Duration::from_millis(10), &config, &mut rng());
+
+        let character = game_state
+            .find_character_by_player_id_mut(player_id)
+            .unwrap()
+            .clone();
+        assert_eq!(character.armor, 0.);
+        assert_eq!(character.health, 2.5);
+        assert!(game_state.events().since(0).any(|x| matches!(
+            x,
+            GameEvent::Damage { amount, .. } if *amount == 2.5
+        )));
+
+        // Teammate can not take the last health point
+        let hit = Hit {
+            damage: 5.,
+            cause: character.kill_cause(),
+            friendly: true,
+        };
+        {
+            let mut character = game_state.entities[0].borrow_mut();
+            assert!(GameState::damage_character(
+                &mut game_state.kills,
+                &mut game_state.scoreboard,
+                &mut game_state.events,
+                &mut character,
+                hit,
+                &config,
+            ));
+            assert_eq!(character.health, 1.);
+            assert!(!GameState::damage_character(
+                &mut game_state.kills,
+                &mut game_state.scoreboard,
+                &mut game_state.events,
+                &mut character,
+                hit,
+                &config,
+            ));
+        }
+
+        let health = |game_state: &GameState| {
+            game_state
+                .find_character_by_player_id_mut(player_id)
+                .unwrap()
+                .health
+        };
+        game_state.proceed(Duration::from_millis(500), &config, &mut rng());
+        assert_eq!(health(&game_state), 1.);
+        game_state.proceed(Duration::from_millis(500), &config, &mut rng());
+        game_state.proceed(Duration::from_millis(500), &config, &mut rng());
+        assert_eq!(health(&game_state), 2.);
+        for _ in 0..4 {
+            game_state.proceed(Duration::from_millis(500), &config, &mut rng());
+        }
+        assert_eq!(health(&game_state), config.health.max);
+    }
+
+    fn flag(game_state: &GameState, team: Team) -> Entity {
+        game_state
+            .entities()
+            .find(|e| matches!(e.role, EntityRole::Flag { team: x, .. } if x == team))
+            .unwrap()
+            .clone()
+    }
+
+    fn carrier(flag: &Entity) -> Option<NonZero<u64>> {
+        match flag.role {
+            EntityRole::Flag { carrier, .. } => carrier,
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn capture_the_flag() {
+        let config = GameConfig {
+            mode: GameMode::CaptureTheFlag,
+            ..Default::default()
+        };
+        let dt = Duration::from_millis(10);
+        let red = NonZero::new(10).unwrap();
+        let blue = NonZero::new(20).unwrap();
+        let mut game_state = GameState::new();
+        for (player_id, team) in [(red, Team::Red), (blue, Team::Blue)] {
+            game_state.add_player(PlayerInfo {
+                player_id,
+                name: team.name().to_string(),
+                color: team.color(),
+                team: Some(team),
+            });
+            game_state.create(
+                character(
+                    Point { x: 400., y: 100. },
+                    config.character_weapon(PlayerWeapon::BallGun),
+                ),
+                player_id,
+                &config.health,
+            );
+        }
+        let move_to = |game_state: &GameState, player_id, pos| {
+            game_state
+                .find_character_by_player_id_mut(player_id)
+                .unwrap()
+                .pos = pos;
+        };
+
+        game_state.proceed(dt, &config, &mut rng());
+        let red_home = game_state.flag_home(Team::Red);
+        let blue_home = game_state.flag_home(Team::Blue);
+        assert_eq!(flag(&game_state, Team::Red).pos, red_home);
+        assert_eq!(flag(&game_state, Team::Blue).pos, blue_home);
+
+        // Pickup by enemy and carry
+        move_to(&game_state, blue, red_home);
+        game_state.proceed(dt, &config, &mut rng());
+        assert_eq!(carrier(&flag(&game_state, Team::Red)), Some(blue));
+        let middle = Point { x: 400., y: 300. };
+        move_to(&game_state, blue, middle);
+        game_state.proceed(dt, &config, &mut rng());
+        assert_eq!(flag(&game_state, Team::Red).pos, middle);
+
+        // Drop on death
+        game_state
+            .find_character_by_player_id_mut(blue)
+            .unwrap()
+            .health = 0.;
+        game_state.proceed(dt, &config, &mut rng());
+        assert_eq!(carrier(&flag(&game_state, Team::Red)), None);
+        assert_eq!(flag(&game_state, Team::Red).pos, middle);
+
+        // Return by teammate
+        move_to(&game_state, red, middle);
+        game_state.proceed(dt, &config, &mut rng());
+        assert_eq!(flag(&game_state, Team::Red).pos, red_home);
+
+        // Capture
+        game_state
+            .find_character_by_player_id_mut(blue)
+            .unwrap()
+            .health = 3.;
+        move_to(&game_state, blue, red_home);
+        game_state.proceed(dt, &config, &mut rng());
+        move_to(&game_state, blue, blue_home);
+        game_state.proceed(dt, &config, &mut rng());
+        assert_eq!(carrier(&flag(&game_state, Team::Red)), None);
+        assert_eq!(flag(&game_state, Team::Red).pos, red_home);
+        assert_eq!(game_state.scoreboard().team_score(Team::Blue), 1);
+        assert_eq!(game_state.scoreboard().stats(blue).unwrap().captures, 1);
+    }
+
+    #[test]
+    fn king_of_the_hill() {
+        let config = GameConfig {
+            mode: GameMode::KingOfTheHill { teams: false },
+            ..Default::default()
+        };
+        let dt = Duration::from_secs(1);
+        let a = NonZero::new(10).unwrap();
+        let b = NonZero::new(20).unwrap();
+        let mut game_state = GameState::new();
+        for player_id in [a, b] {
+            game_state.add_player(PlayerInfo {
+                player_id,
+                name: player_id.to_string(),
+                color: white(),
+                team: None,
+            });
+        }
+
+        game_state.proceed(Duration::ZERO, &config, &mut rng());
+        let center = game_state.zone().unwrap().center;
+        for player_id in [a, b] {
+            game_state.create(
+                character(center, config.character_weapon(PlayerWeapon::BallGun)),
+                player_id,
+                &config.health,
+            );
+        }
+        game_state.proceed(dt, &config, &mut rng());
+        assert!(game_state.zone().unwrap().contested);
+
+        game_state.find_character_by_player_id_mut(b).unwrap().pos = Point { x: -100., y: -100. };
+        for _ in 0..5 {
+            game_state.proceed(dt, &config, &mut rng());
         }
+        let zone = game_state.zone().unwrap();
+        assert!(!zone.contested);
+        assert_eq!(zone.holder, Some(ZoneHolder::Player(a)));
+        assert_eq!(game_state.scoreboard().stats(a).unwrap().zone_points, 2);
     }
 }
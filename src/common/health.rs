@@ -0,0 +1,90 @@
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+/// Health regeneration which starts after a while without damage
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct RegenConfig {
+    pub delay: Duration,
+    /// Health restored per second
+    pub rate: f32,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct HealthConfig {
+    /// Health of spawned character
+    pub max: f32,
+    /// Limit of armor collected from pickups. Armor takes damage before health
+    pub max_armor: f32,
+    pub regen: Option<RegenConfig>,
+}
+
+impl Default for HealthConfig {
+    fn default() -> Self {
+        Self {
+            max: 3.,
+            max_armor: 2.,
+            regen: None,
+        }
+    }
+}
+
+impl HealthConfig {
+    pub fn validate(&self) -> Result<(), String> {
+        if !(self.max.is_finite() && self.max > 0.) {
+            return Err("`max` must be positive".to_string());
+        }
+        if !(self.max_armor.is_finite() && self.max_armor >= 0.) {
+            return Err("`max_armor` must not be negative".to_string());
+        }
+        if let Some(regen) = &self.regen {
+            if !(regen.rate.is_finite() && regen.rate > 0.) {
+                return Err("regen `rate` must be positive".to_string());
+            }
+        }
+        Ok(())
+    }
+
+    /// Time after damage until regeneration starts
+    pub fn regen_delay(&self) -> Duration {
+        self.regen.as_ref().map_or(Duration::ZERO, |x| x.delay)
+    }
+
+    /// Health after regeneration for `dt` if regeneration has started
+    pub fn regenerated(&self, health: f32, regen_cooldown: Duration, dt: Duration) -> f32 {
+        match &self.regen {
+            Some(regen) if regen_cooldown.is_zero() => {
+                (health + regen.rate * dt.as_secs_f32()).min(self.max.max(health))
+            }
+            _ => health,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{HealthConfig, RegenConfig};
+    use std::time::Duration;
+
+    #[test]
+    fn regeneration() {
+        let config = HealthConfig {
+            max: 5.,
+            max_armor: 0.,
+            regen: Some(RegenConfig {
+                delay: Duration::from_secs(3),
+                rate: 2.,
+            }),
+        };
+        config.validate().unwrap();
+        assert_eq!(config.regen_delay(), Duration::from_secs(3));
+
+        let dt = Duration::from_millis(500);
+        assert_eq!(config.regenerated(2., Duration::from_secs(1), dt), 2.);
+        assert_eq!(config.regenerated(2., Duration::ZERO, dt), 3.);
+        assert_eq!(config.regenerated(4.5, Duration::ZERO, dt), 5.);
+
+        let config: HealthConfig = Default::default();
+        assert_eq!(config.regenerated(2., Duration::ZERO, dt), 2.);
+        assert_eq!(config.regen_delay(), Duration::ZERO);
+    }
+}
@@ -0,0 +1,162 @@
+use serde::{Deserialize, Serialize};
+
+/// Projectile kind without its parameters
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+pub enum ProjectileClass {
+    Ball,
+    Ray,
+    Mine,
+    Missile,
+    Grenade,
+}
+
+/// What happens when projectiles of enemies touch each other
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+pub enum InteractionEffect {
+    /// Both projectiles lose one health. Projectiles without health are destroyed
+    Collide,
+    /// `second` is destroyed while `first` is not affected
+    Cut,
+    /// `second` detonates. It must be a mine or a grenade
+    Trigger,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct InteractionRule {
+    pub first: ProjectileClass,
+    pub second: ProjectileClass,
+    pub effect: InteractionEffect,
+}
+
+/// Projectiles of pairs which have no rule pass through each other
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct ProjectileInteractions {
+    pub rules: Vec<InteractionRule>,
+}
+
+impl Default for ProjectileInteractions {
+    fn default() -> Self {
+        let rule = |first, second, effect| InteractionRule {
+            first,
+            second,
+            effect,
+        };
+        Self {
+            rules: vec![
+                rule(
+                    ProjectileClass::Ball,
+                    ProjectileClass::Mine,
+                    InteractionEffect::Collide,
+                ),
+                rule(
+                    ProjectileClass::Ball,
+                    ProjectileClass::Missile,
+                    InteractionEffect::Collide,
+                ),
+                rule(
+                    ProjectileClass::Ray,
+                    ProjectileClass::Ball,
+                    InteractionEffect::Cut,
+                ),
+                rule(
+                    ProjectileClass::Ray,
+                    ProjectileClass::Mine,
+                    InteractionEffect::Trigger,
+                ),
+                rule(
+                    ProjectileClass::Missile,
+                    ProjectileClass::Mine,
+                    InteractionEffect::Trigger,
+                ),
+            ],
+        }
+    }
+}
+
+impl ProjectileInteractions {
+    pub fn validate(&self) -> Result<(), String> {
+        for (i, rule) in self.rules.iter().enumerate() {
+            if rule.effect == InteractionEffect::Trigger
+                && !matches!(
+                    rule.second,
+                    ProjectileClass::Mine | ProjectileClass::Grenade
+                )
+            {
+                return Err(format!(
+                    "rule {}: only mines and grenades can be triggered",
+                    i
+                ));
+            }
+            if self.rules[..i].iter().any(|x| {
+                (x.first, x.second) == (rule.first, rule.second)
+                    || (x.first, x.second) == (rule.second, rule.first)
+            }) {
+                return Err(format!(
+                    "rule {}: pair {:?} and {:?} already has a rule",
+                    i, rule.first, rule.second
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// Effect for projectiles `a` and `b` and whether `b` is the first one in the rule
+    pub fn find(
+        &self,
+        a: ProjectileClass,
+        b: ProjectileClass,
+    ) -> Option<(InteractionEffect, bool)> {
+        self.rules.iter().find_map(|x| {
+            if (x.first, x.second) == (a, b) {
+                Some((x.effect, false))
+            } else if (x.first, x.second) == (b, a) {
+                Some((x.effect, true))
+            } else {
+                None
+            }
+        })
+    }
+
+    /// Whether projectiles of this class interact with anything
+    pub fn involves(&self, class: ProjectileClass) -> bool {
+        self.rules
+            .iter()
+            .any(|x| x.first == class || x.second == class)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{InteractionEffect, InteractionRule, ProjectileClass, ProjectileInteractions};
+
+    #[test]
+    fn rules() {
+        let mut interactions: ProjectileInteractions = Default::default();
+        interactions.validate().unwrap();
+        assert_eq!(
+            interactions.find(ProjectileClass::Mine, ProjectileClass::Ray),
+            Some((InteractionEffect::Trigger, true))
+        );
+        assert_eq!(
+            interactions.find(ProjectileClass::Ray, ProjectileClass::Ray),
+            None
+        );
+        assert!(!interactions.involves(ProjectileClass::Grenade));
+
+        interactions.rules.push(InteractionRule {
+            first: ProjectileClass::Mine,
+            second: ProjectileClass::Ball,
+            effect: InteractionEffect::Cut,
+        });
+        assert!(interactions.validate().is_err());
+
+        let interactions = ProjectileInteractions {
+            rules: vec![InteractionRule {
+                first: ProjectileClass::Mine,
+                second: ProjectileClass::Ball,
+                effect: InteractionEffect::Trigger,
+            }],
+        };
+        assert!(interactions.validate().is_err());
+    }
+}
@@ -0,0 +1,133 @@
+use super::{GameConfig, PlayerWeapon, WeaponState};
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct LoadoutConfig {
+    /// Max number of weapons chosen at respawn
+    pub size: usize,
+    /// Time after weapon switch during which character can not fire
+    pub switch_duration: Duration,
+}
+
+impl Default for LoadoutConfig {
+    fn default() -> Self {
+        Self {
+            size: 2,
+            switch_duration: Duration::from_millis(500),
+        }
+    }
+}
+
+impl LoadoutConfig {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.size == 0 {
+            return Err("`size` must not be zero".to_string());
+        }
+        Ok(())
+    }
+}
+
+/// Weapons chosen by player at respawn. Kept by server with state of weapons which are not held
+#[derive(Debug, Clone)]
+pub struct Loadout {
+    slots: Vec<(PlayerWeapon, WeaponState)>,
+    active: usize,
+}
+
+impl Loadout {
+    /// Drops duplicates and weapons which do not fit in loadout. Falls back to ball gun if
+    /// nothing is left
+    pub fn new(weapons: &[PlayerWeapon], config: &GameConfig) -> Self {
+        let mut slots: Vec<(PlayerWeapon, WeaponState)> = vec![];
+        for weapon in weapons {
+            if slots.len() < config.loadout.size && slots.iter().all(|(x, _)| x != weapon) {
+                let state = WeaponState::new(config.character_weapon(*weapon).magazine());
+                slots.push((*weapon, state));
+            }
+        }
+        if slots.is_empty() {
+            let weapon = PlayerWeapon::BallGun;
+            slots.push((
+                weapon,
+                WeaponState::new(config.character_weapon(weapon).magazine()),
+            ));
+        }
+        Self { slots, active: 0 }
+    }
+
+    pub fn weapons(&self) -> Vec<PlayerWeapon> {
+        self.slots.iter().map(|(x, _)| *x).collect()
+    }
+
+    pub fn active(&self) -> PlayerWeapon {
+        self.slots[self.active].0
+    }
+
+    /// Stores state of held weapon and makes weapon in `slot` active. Returns state of the
+    /// new weapon with switch delay applied
+    pub fn switch(
+        &mut self,
+        slot: usize,
+        held: WeaponState,
+        config: &GameConfig,
+    ) -> Result<WeaponState, String> {
+        if slot >= self.slots.len() {
+            return Err(format!("slot {} is out of loadout", slot));
+        }
+        if slot == self.active {
+            return Err(format!("slot {} is already active", slot));
+        }
+        self.slots[self.active].1 = held;
+        self.active = slot;
+        let mut state = self.slots[slot].1;
+        state.start_cooldown(state.cooldown.max(config.loadout.switch_duration));
+        Ok(state)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Loadout;
+    use crate::common::{GameConfig, PlayerWeapon, WeaponState};
+
+    #[test]
+    fn switching() {
+        let config: GameConfig = Default::default();
+        let loadout = Loadout::new(&[], &config);
+        assert_eq!(loadout.weapons(), [PlayerWeapon::BallGun]);
+
+        let mut loadout = Loadout::new(
+            &[
+                PlayerWeapon::GrenadeLauncher,
+                PlayerWeapon::GrenadeLauncher,
+                PlayerWeapon::Shield,
+                PlayerWeapon::RayGun,
+            ],
+            &config,
+        );
+        assert_eq!(
+            loadout.weapons(),
+            [PlayerWeapon::GrenadeLauncher, PlayerWeapon::Shield]
+        );
+        assert_eq!(loadout.active(), PlayerWeapon::GrenadeLauncher);
+
+        let magazine = *config
+            .character_weapon(PlayerWeapon::GrenadeLauncher)
+            .magazine()
+            .unwrap();
+        assert!(loadout.switch(0, Default::default(), &config).is_err());
+
+        let mut grenades = WeaponState::new(Some(&magazine));
+        grenades.consume_round(Some(&magazine));
+        let shield = loadout.switch(1, grenades, &config).unwrap();
+        assert_eq!(loadout.active(), PlayerWeapon::Shield);
+        assert_eq!(shield.cooldown, config.loadout.switch_duration);
+        assert!(loadout.switch(1, shield, &config).is_err());
+        assert!(loadout.switch(2, shield, &config).is_err());
+
+        let restored = loadout.switch(0, shield, &config).unwrap();
+        assert_eq!(restored.ammo.unwrap().loaded, magazine.size - 1);
+        assert_eq!(restored.cooldown, config.loadout.switch_duration);
+    }
+}
@@ -0,0 +1,228 @@
+use super::{Point, Rect, Segment, Team, Vector};
+use serde::{Deserialize, Serialize};
+use std::f32::consts::TAU;
+
+/// Static level geometry. Loaded by server from map file and sent to clients on connect
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct Map {
+    pub bounds: Rect,
+    /// Convex polygons given by their edges in order. Projectiles are reflected from them
+    /// and characters are pushed out of them
+    #[serde(default)]
+    pub walls: Vec<Vec<Segment>>,
+    /// Characters spawn at random point inside bounds if there are none
+    #[serde(default)]
+    pub spawn_points: Vec<Point>,
+    /// Pickups appear at random point inside bounds if there are none
+    #[serde(default)]
+    pub pickup_points: Vec<Point>,
+    /// Flags of teams without a base are placed near left and right edges of bounds
+    #[serde(default)]
+    pub flag_bases: Vec<FlagBase>,
+}
+
+/// Home position of team flag in capture the flag mode
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+pub struct FlagBase {
+    pub team: Team,
+    pub pos: Point,
+}
+
+impl Default for Map {
+    fn default() -> Self {
+        Self {
+            bounds: Rect {
+                x: 32.,
+                y: 32. + 16.,
+                w: 800. - 64.,
+                h: 600. - 64.,
+            },
+            walls: vec![],
+            spawn_points: vec![],
+            pickup_points: vec![],
+            flag_bases: vec![],
+        }
+    }
+}
+
+impl Map {
+    pub fn validate(&self) -> Result<(), String> {
+        let bounds = self.bounds;
+        if !(bounds.w.is_finite() && bounds.w > 0. && bounds.h.is_finite() && bounds.h > 0.) {
+            return Err("`bounds` must have positive size".to_string());
+        }
+        for (i, wall) in self.walls.iter().enumerate() {
+            if wall.len() < 3 {
+                return Err(format!("wall {}: must have at least 3 edges", i));
+            }
+            for (j, edge) in wall.iter().enumerate() {
+                if !(edge.p0.x.is_finite() && edge.p0.y.is_finite()) {
+                    return Err(format!("wall {}: edge {} is not finite", i, j));
+                }
+                if edge.p1 != wall[(j + 1) % wall.len()].p0 {
+                    return Err(format!("wall {}: edge {} is not connected to next", i, j));
+                }
+            }
+            if !Self::convex(wall) {
+                return Err(format!("wall {}: is not convex", i));
+            }
+        }
+        let flag_points: Vec<_> = self.flag_bases.iter().map(|x| x.pos).collect();
+        for (name, points) in [
+            ("spawn point", &self.spawn_points),
+            ("pickup point", &self.pickup_points),
+            ("flag base", &flag_points),
+        ] {
+            for (i, point) in points.iter().enumerate() {
+                if !self.inside_bounds(*point) {
+                    return Err(format!("{} {} is out of bounds", name, i));
+                }
+                if self.wall_at(*point) {
+                    return Err(format!("{} {} is inside of a wall", name, i));
+                }
+            }
+        }
+        for team in Team::ALL {
+            if self.flag_bases.iter().filter(|x| x.team == team).count() > 1 {
+                return Err(format!("{} team has more than one flag base", team.name()));
+            }
+        }
+        Ok(())
+    }
+
+    pub fn flag_base(&self, team: Team) -> Option<Point> {
+        self.flag_bases
+            .iter()
+            .find(|x| x.team == team)
+            .map(|x| x.pos)
+    }
+
+    /// Whether closed polygon turns in one direction and goes around exactly once
+    fn convex(edges: &[Segment]) -> bool {
+        let turns: Vec<_> = edges
+            .iter()
+            .zip(edges.iter().cycle().skip(1))
+            .map(|(a, b)| Vector::cross(a.vec(), b.vec()).atan2(Vector::dot(a.vec(), b.vec())))
+            .collect();
+        let total: f32 = turns.iter().sum();
+        !(turns.iter().any(|x| *x > 0.) && turns.iter().any(|x| *x < 0.))
+            && (total.abs() - TAU).abs() < 1e-3
+    }
+
+    pub fn inside_bounds(&self, point: Point) -> bool {
+        point.x > self.bounds.x
+            && point.x < self.bounds.x + self.bounds.w
+            && point.y > self.bounds.y
+            && point.y < self.bounds.y + self.bounds.h
+    }
+
+    /// Whether point is inside of any wall
+    pub fn wall_at(&self, point: Point) -> bool {
+        self.walls.iter().any(|wall| {
+            let sides: Vec<_> = wall
+                .iter()
+                .map(|edge| Vector::cross(edge.vec(), point - edge.p0))
+                .collect();
+            sides.iter().all(|x| *x > 0.) || sides.iter().all(|x| *x < 0.)
+        })
+    }
+
+    /// Bounds edges followed by edges of all walls
+    pub fn segments(&self) -> impl Iterator<Item = Segment> + '_ {
+        self.bounds
+            .edges()
+            .into_iter()
+            .chain(self.walls.iter().flatten().copied())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{FlagBase, Map};
+    use crate::common::{Point, Segment, Team};
+
+    fn polygon(points: &[(f32, f32)]) -> Vec<Segment> {
+        let p: Vec<_> = points.iter().map(|&(x, y)| Point { x, y }).collect();
+        (0..p.len())
+            .map(|i| Segment {
+                p0: p[i],
+                p1: p[(i + 1) % p.len()],
+            })
+            .collect()
+    }
+
+    fn square(x: f32, y: f32, size: f32) -> Vec<Segment> {
+        polygon(&[(x, y), (x + size, y), (x + size, y + size), (x, y + size)])
+    }
+
+    #[test]
+    fn validation() {
+        let mut map: Map = Default::default();
+        map.walls.push(square(100., 100., 50.));
+        map.spawn_points.push(Point { x: 300., y: 300. });
+        map.validate().unwrap();
+
+        assert!(map.wall_at(Point { x: 120., y: 130. }));
+        assert!(!map.wall_at(Point { x: 160., y: 130. }));
+        assert_eq!(map.segments().count(), 8);
+
+        let data = serde_json::to_vec(&map).unwrap();
+        let parsed: Map = serde_json::from_slice(&data).unwrap();
+        assert_eq!(parsed, map);
+
+        let mut invalid = map.clone();
+        invalid.spawn_points.push(Point { x: 125., y: 125. });
+        assert!(invalid.validate().is_err());
+
+        let mut invalid = map.clone();
+        invalid.spawn_points.push(Point { x: 0., y: 0. });
+        assert!(invalid.validate().is_err());
+
+        let mut invalid = map.clone();
+        invalid.walls[0].swap(0, 1);
+        assert!(invalid.validate().is_err());
+
+        let mut invalid = map.clone();
+        invalid.walls.push(polygon(&[
+            (200., 200.),
+            (300., 200.),
+            (250., 230.),
+            (300., 260.),
+            (200., 260.),
+        ]));
+        assert!(invalid.validate().is_err(), "concave");
+
+        let mut invalid = map.clone();
+        invalid.walls.push(polygon(&[
+            (250., 200.),
+            (280., 290.),
+            (200., 235.),
+            (300., 235.),
+            (220., 290.),
+        ]));
+        assert!(invalid.validate().is_err(), "self-intersecting");
+
+        map.flag_bases.push(FlagBase {
+            team: Team::Red,
+            pos: Point { x: 80., y: 300. },
+        });
+        map.validate().unwrap();
+        assert_eq!(map.flag_base(Team::Red), Some(Point { x: 80., y: 300. }));
+        assert_eq!(map.flag_base(Team::Blue), None);
+
+        let mut invalid = map.clone();
+        invalid.flag_bases[0].pos = Point { x: 125., y: 125. };
+        assert!(invalid.validate().is_err());
+
+        let mut invalid = map.clone();
+        invalid.flag_bases[0].pos = Point { x: 0., y: 300. };
+        assert!(invalid.validate().is_err());
+
+        let mut invalid = map;
+        invalid.flag_bases.push(FlagBase {
+            team: Team::Red,
+            pos: Point { x: 700., y: 300. },
+        });
+        assert!(invalid.validate().is_err());
+    }
+}
@@ -0,0 +1,247 @@
+use super::{PlayerStats, Scoreboard};
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct MatchRules {
+    /// Players required to leave warmup
+    pub min_players: usize,
+    pub countdown_duration: Duration,
+    pub round_duration: Duration,
+    /// Round ends early when some player or team reaches this score
+    pub score_limit: Option<i64>,
+    pub results_duration: Duration,
+}
+
+impl Default for MatchRules {
+    fn default() -> Self {
+        Self {
+            min_players: 2,
+            countdown_duration: Duration::from_secs(5),
+            round_duration: Duration::from_secs(5 * 60),
+            score_limit: Some(20),
+            results_duration: Duration::from_secs(10),
+        }
+    }
+}
+
+impl MatchRules {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.min_players == 0 {
+            return Err("`min_players` must not be zero".to_string());
+        }
+        if self.round_duration.is_zero() {
+            return Err("`round_duration` must not be zero".to_string());
+        }
+        if self.score_limit.is_some_and(|x| x <= 0) {
+            return Err("`score_limit` must be positive".to_string());
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub enum MatchPhase {
+    /// Free play until enough players are connected. Scores are reset when round starts
+    Warmup,
+    Countdown {
+        remaining: Duration,
+    },
+    Round {
+        remaining: Duration,
+    },
+    /// Final standings of finished round
+    Results {
+        remaining: Duration,
+        standings: Vec<PlayerStats>,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MatchTransition {
+    RoundStarted,
+    RoundEnded,
+}
+
+/// Match state machine. Part of game state so it is broadcasted to clients with it
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct MatchState {
+    phase: MatchPhase,
+    round: u32,
+}
+
+impl Default for MatchState {
+    fn default() -> Self {
+        Self {
+            phase: MatchPhase::Warmup,
+            round: 0,
+        }
+    }
+}
+
+impl MatchState {
+    pub fn phase(&self) -> &MatchPhase {
+        &self.phase
+    }
+
+    /// Number of current or last finished round
+    pub fn round(&self) -> u32 {
+        self.round
+    }
+
+    /// Characters can fire, take damage and score only during warmup and round
+    pub fn combat_allowed(&self) -> bool {
+        matches!(self.phase, MatchPhase::Warmup | MatchPhase::Round { .. })
+    }
+
+    /// Advances timers. Returns transition which game state has to react on
+    pub fn proceed(
+        &mut self,
+        dt: Duration,
+        rules: &MatchRules,
+        scoreboard: &Scoreboard,
+    ) -> Option<MatchTransition> {
+        let enough_players = scoreboard.player_count() >= rules.min_players;
+
+        match &mut self.phase {
+            MatchPhase::Warmup => {
+                if enough_players {
+                    self.phase = MatchPhase::Countdown {
+                        remaining: rules.countdown_duration,
+                    };
+                }
+                None
+            }
+            MatchPhase::Countdown { remaining } => {
+                if !enough_players {
+                    self.phase = MatchPhase::Warmup;
+                    None
+                } else if *remaining <= dt {
+                    self.phase = MatchPhase::Round {
+                        remaining: rules.round_duration,
+                    };
+                    self.round += 1;
+                    Some(MatchTransition::RoundStarted)
+                } else {
+                    *remaining -= dt;
+                    None
+                }
+            }
+            MatchPhase::Round { remaining } => {
+                let ranked = scoreboard.ranked();
+                let score_limit_reached = rules
+                    .score_limit
+                    .is_some_and(|limit| scoreboard.top_score().is_some_and(|x| x >= limit));
+                if score_limit_reached || *remaining <= dt {
+                    self.phase = MatchPhase::Results {
+                        remaining: rules.results_duration,
+                        standings: ranked.into_iter().cloned().collect(),
+                    };
+                    Some(MatchTransition::RoundEnded)
+                } else {
+                    *remaining -= dt;
+                    None
+                }
+            }
+            MatchPhase::Results { remaining, .. } => {
+                if *remaining <= dt {
+                    self.phase = MatchPhase::Warmup;
+                } else {
+                    *remaining -= dt;
+                }
+                None
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{MatchPhase, MatchRules, MatchState, MatchTransition};
+    use crate::common::{GameMode, Scoreboard};
+    use std::{num::NonZero, time::Duration};
+
+    #[test]
+    fn round_lifecycle() {
+        let rules = MatchRules {
+            min_players: 2,
+            countdown_duration: Duration::from_secs(3),
+            round_duration: Duration::from_secs(10),
+            score_limit: None,
+            results_duration: Duration::from_secs(5),
+        };
+        let dt = Duration::from_secs(1);
+        let mut scoreboard: Scoreboard = Default::default();
+        let mut state: MatchState = Default::default();
+
+        scoreboard.add_player(NonZero::new(1).unwrap(), None);
+        assert_eq!(state.proceed(dt, &rules, &scoreboard), None);
+        assert_eq!(state.phase(), &MatchPhase::Warmup);
+
+        scoreboard.add_player(NonZero::new(2).unwrap(), None);
+        state.proceed(dt, &rules, &scoreboard);
+        assert!(matches!(state.phase(), MatchPhase::Countdown { .. }));
+        assert!(!state.combat_allowed());
+
+        let transitions: Vec<_> = (0..3)
+            .filter_map(|_| state.proceed(dt, &rules, &scoreboard))
+            .collect();
+        assert_eq!(transitions, [MatchTransition::RoundStarted]);
+        assert_eq!(state.round(), 1);
+        assert!(state.combat_allowed());
+
+        let transitions: Vec<_> = (0..10)
+            .filter_map(|_| state.proceed(dt, &rules, &scoreboard))
+            .collect();
+        assert_eq!(transitions, [MatchTransition::RoundEnded]);
+        assert!(
+            matches!(state.phase(), MatchPhase::Results { standings, .. } if standings.len() == 2)
+        );
+
+        for _ in 0..5 {
+            state.proceed(dt, &rules, &scoreboard);
+        }
+        assert_eq!(state.phase(), &MatchPhase::Warmup);
+    }
+
+    #[test]
+    fn countdown_cancelled_and_score_limit() {
+        let rules = MatchRules {
+            min_players: 2,
+            countdown_duration: Duration::from_secs(3),
+            round_duration: Duration::from_secs(60),
+            score_limit: Some(2),
+            results_duration: Duration::from_secs(5),
+        };
+        let dt = Duration::from_secs(1);
+        let a = NonZero::new(1).unwrap();
+        let b = NonZero::new(2).unwrap();
+        let mut scoreboard: Scoreboard = Default::default();
+        let mut state: MatchState = Default::default();
+
+        scoreboard.add_player(a, None);
+        scoreboard.add_player(b, None);
+        state.proceed(dt, &rules, &scoreboard);
+        scoreboard.remove_player(b);
+        state.proceed(dt, &rules, &scoreboard);
+        assert_eq!(state.phase(), &MatchPhase::Warmup);
+
+        scoreboard.add_player(b, None);
+        for _ in 0..4 {
+            state.proceed(dt, &rules, &scoreboard);
+        }
+        assert!(matches!(state.phase(), MatchPhase::Round { .. }));
+
+        scoreboard.record_kill(b, a, GameMode::FreeForAll);
+        assert_eq!(state.proceed(dt, &rules, &scoreboard), None);
+        scoreboard.record_kill(b, a, GameMode::FreeForAll);
+        assert_eq!(
+            state.proceed(dt, &rules, &scoreboard),
+            Some(MatchTransition::RoundEnded)
+        );
+        match state.phase() {
+            MatchPhase::Results { standings, .. } => assert_eq!(standings[0].player_id, a),
+            phase => panic!("Unexpected phase {:?}", phase),
+        }
+    }
+}
@@ -6,9 +6,9 @@ fn lerp_f32(a: f32, b: f32, t: f64) -> f32 {
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
-pub(crate) struct Point {
-    pub(crate) x: f32,
-    pub(crate) y: f32,
+pub struct Point {
+    pub x: f32,
+    pub y: f32,
 }
 
 impl Sub for Point {
@@ -45,18 +45,18 @@ impl Sub<Vector> for Point {
 }
 
 impl Point {
-    pub(crate) fn origin() -> Point {
+    pub fn origin() -> Point {
         Point { x: 0., y: 0. }
     }
 
-    pub(crate) fn lerp(a: Self, b: Self, t: f64) -> Self {
+    pub fn lerp(a: Self, b: Self, t: f64) -> Self {
         Self {
             x: lerp_f32(a.x, b.x, t),
             y: lerp_f32(a.y, b.y, t),
         }
     }
 
-    pub(crate) fn inflate(self, radius: f32) -> Rect {
+    pub fn inflate(self, radius: f32) -> Rect {
         Rect {
             x: self.x - radius,
             y: self.y - radius,
@@ -67,46 +67,46 @@ impl Point {
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
-pub(crate) struct Complex {
-    pub(crate) r: f32,
-    pub(crate) i: f32,
+pub struct Complex {
+    pub r: f32,
+    pub i: f32,
 }
 
-pub(crate) static I: Complex = Complex { r: 0., i: 1. };
+pub static I: Complex = Complex { r: 0., i: 1. };
 
 impl Complex {
-    pub(crate) fn from_rad(v: f32) -> Self {
+    pub fn from_rad(v: f32) -> Self {
         Self {
             r: v.cos(),
             i: v.sin(),
         }
     }
 
-    pub(crate) fn len(&self) -> f32 {
+    pub fn len(&self) -> f32 {
         (self.r * self.r + self.i * self.i).sqrt()
     }
 
-    pub(crate) fn lerp(a: Self, b: Self, t: f64) -> Self {
+    pub fn lerp(a: Self, b: Self, t: f64) -> Self {
         Self {
             r: lerp_f32(a.r, b.r, t),
             i: lerp_f32(a.i, b.i, t),
         }
     }
-    pub(crate) fn inverted_r(self) -> Self {
+    pub fn inverted_r(self) -> Self {
         Self {
             r: -self.r,
             i: self.i,
         }
     }
 
-    pub(crate) fn inverted_i(self) -> Self {
+    pub fn inverted_i(self) -> Self {
         Self {
             r: self.r,
             i: -self.i,
         }
     }
 
-    pub(crate) fn reflect_from(self, l: Vector) -> Self {
+    pub fn reflect_from(self, l: Vector) -> Self {
         let reflection = Vector::polar(self, 1.).reflect_from(l);
         Self {
             r: reflection.x,
@@ -114,7 +114,7 @@ impl Complex {
         }
     }
 
-    pub(crate) fn normalize(self) -> Self {
+    pub fn normalize(self) -> Self {
         let len = self.len();
         Self {
             r: self.r / len,
@@ -135,25 +135,25 @@ impl Neg for Complex {
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone, Copy)]
-pub(crate) struct Vector {
-    pub(crate) x: f32,
-    pub(crate) y: f32,
+pub struct Vector {
+    pub x: f32,
+    pub y: f32,
 }
 
 impl Vector {
-    pub(crate) fn len(&self) -> f32 {
+    pub fn len(&self) -> f32 {
         (self.x * self.x + self.y * self.y).sqrt()
     }
 
-    pub(crate) fn cross(self, rhs: Self) -> f32 {
+    pub fn cross(self, rhs: Self) -> f32 {
         (self.x * rhs.y) - (self.y * rhs.x)
     }
 
-    pub(crate) fn dot(self, rhs: Self) -> f32 {
+    pub fn dot(self, rhs: Self) -> f32 {
         (self.x * rhs.x) + (self.y * rhs.y)
     }
 
-    pub(crate) fn normalize(self) -> Self {
+    pub fn normalize(self) -> Self {
         let len = self.len();
         Self {
             x: self.x as f32 / len,
@@ -161,7 +161,7 @@ impl Vector {
         }
     }
 
-    pub(crate) fn normalize_into_complex(self) -> Complex {
+    pub fn normalize_into_complex(self) -> Complex {
         let len = self.len();
         Complex {
             r: self.x as f32 / len,
@@ -169,32 +169,32 @@ impl Vector {
         }
     }
 
-    pub(crate) fn left_perpendicular(self) -> Self {
+    pub fn left_perpendicular(self) -> Self {
         Self {
             x: -self.y,
             y: self.x,
         }
     }
 
-    pub(crate) fn right_perpendicular(self) -> Self {
+    pub fn right_perpendicular(self) -> Self {
         Self {
             x: self.y,
             y: -self.x,
         }
     }
 
-    pub(crate) fn polar(rot: Complex, len: f32) -> Self {
+    pub fn polar(rot: Complex, len: f32) -> Self {
         Self {
             x: rot.r * len,
             y: rot.i * len,
         }
     }
 
-    pub(crate) fn project_on(self, axis: Vector) -> Vector {
+    pub fn project_on(self, axis: Vector) -> Vector {
         (self.dot(axis) / axis.dot(axis)) * axis
     }
 
-    pub(crate) fn reflect_from(self, axis: Vector) -> Self {
+    pub fn reflect_from(self, axis: Vector) -> Self {
         2. * self.project_on(axis) - self
     }
 }
@@ -307,16 +307,23 @@ impl Mul<Vector> for f32 {
     }
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone, Copy)]
-pub(crate) struct Rect {
-    pub(crate) x: f32,
-    pub(crate) y: f32,
-    pub(crate) w: f32,
-    pub(crate) h: f32,
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+pub struct Rect {
+    pub x: f32,
+    pub y: f32,
+    pub w: f32,
+    pub h: f32,
 }
 
 impl Rect {
-    pub(crate) fn points(self) -> [Point; 4] {
+    pub fn center(self) -> Point {
+        Point {
+            x: self.x + self.w / 2.,
+            y: self.y + self.h / 2.,
+        }
+    }
+
+    pub fn points(self) -> [Point; 4] {
         [
             Point {
                 x: self.x,
@@ -337,7 +344,7 @@ impl Rect {
         ]
     }
 
-    pub(crate) fn edges(self) -> [Segment; 4] {
+    pub fn edges(self) -> [Segment; 4] {
         let p = self.points();
         [
             Segment { p0: p[0], p1: p[1] },
@@ -348,45 +355,72 @@ impl Rect {
     }
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone, Copy)]
-pub(crate) struct Segment {
-    pub(crate) p0: Point,
-    pub(crate) p1: Point,
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+pub struct Segment {
+    pub p0: Point,
+    pub p1: Point,
 }
 
-pub(crate) struct RayCastScalars {
-    pub(crate) segments: (Segment, Segment),
-    pub(crate) t: f32,
-    pub(crate) u: f32,
+pub struct RayCastScalars {
+    pub segments: (Segment, Segment),
+    pub t: f32,
+    pub u: f32,
 }
 
 impl RayCastScalars {
-    pub(crate) fn intersects(&self) -> bool {
+    pub fn intersects(&self) -> bool {
         self.t > 0. && self.t < 1. && self.u > 0. && self.u < 1.
     }
-    pub(crate) fn intersects_including(&self) -> bool {
+    pub fn intersects_including(&self) -> bool {
         self.t >= 0. && self.t <= 1. && self.u >= 0. && self.u <= 1.
     }
 
-    pub(crate) fn intersection_point(&self) -> Point {
+    pub fn intersection_point(&self) -> Point {
         self.segments.0.p0 + (self.segments.0.p1 - self.segments.0.p0) * self.t
     }
 
-    pub(crate) fn intersection_point_t_mul(&self, c: f32) -> Point {
+    pub fn intersection_point_t_mul(&self, c: f32) -> Point {
         self.segments.0.p0 + (self.segments.0.p1 - self.segments.0.p0) * self.t * c
     }
 
-    pub(crate) fn intersection_point_u_mul(&self, c: f32) -> Point {
+    pub fn intersection_point_u_mul(&self, c: f32) -> Point {
         self.segments.1.p0 + (self.segments.1.p1 - self.segments.1.p0) * self.u * c
     }
 }
 
 impl Segment {
-    pub(crate) fn vec(self) -> Vector {
+    pub fn vec(self) -> Vector {
         self.p1 - self.p0
     }
 
-    pub(crate) fn project_on(self, axis: Vector) -> Segment {
+    /// Distance from point to the closest point of segment
+    pub fn distance_to(self, point: Point) -> f32 {
+        let v = self.vec();
+        let t = if v.dot(v) == 0. {
+            0.
+        } else {
+            ((point - self.p0).dot(v) / v.dot(v)).clamp(0., 1.)
+        };
+        (point - (self.p0 + v * t)).len()
+    }
+
+    /// Distance between the closest points of segments
+    pub fn distance_to_segment(self, rhs: Segment) -> f32 {
+        if self.ray_cast(rhs).is_some_and(|r| r.intersects()) {
+            0.
+        } else {
+            [
+                self.distance_to(rhs.p0),
+                self.distance_to(rhs.p1),
+                rhs.distance_to(self.p0),
+                rhs.distance_to(self.p1),
+            ]
+            .into_iter()
+            .fold(f32::INFINITY, f32::min)
+        }
+    }
+
+    pub fn project_on(self, axis: Vector) -> Segment {
         Segment {
             p0: Point::origin() + (self.p0 - Point::origin()).project_on(axis),
             p1: Point::origin() + (self.p1 - Point::origin()).project_on(axis),
@@ -424,14 +458,14 @@ impl Segment {
         }
     }
 
-    pub(crate) fn inverted(self) -> Self {
+    pub fn inverted(self) -> Self {
         Self {
             p0: self.p1,
             p1: self.p0,
         }
     }
 
-    pub(crate) fn inverted_x(self) -> Self {
+    pub fn inverted_x(self) -> Self {
         Self {
             p0: Point {
                 x: self.p1.x,
@@ -444,7 +478,7 @@ impl Segment {
         }
     }
 
-    pub(crate) fn inverted_y(self) -> Self {
+    pub fn inverted_y(self) -> Self {
         Self {
             p0: Point {
                 x: self.p0.x,
@@ -457,7 +491,7 @@ impl Segment {
         }
     }
 
-    pub(crate) fn stretch_with_fixed_center(self, t: f32) -> Self {
+    pub fn stretch_with_fixed_center(self, t: f32) -> Self {
         Self {
             p0: Point {
                 x: (self.p1.x + self.p0.x) / 2. - t * (self.p1.x - self.p0.x) / 2.,
@@ -470,7 +504,7 @@ impl Segment {
         }
     }
 
-    pub(crate) fn stretch_with_fixed_first_point_x(self, x: f32) -> Self {
+    pub fn stretch_with_fixed_first_point_x(self, x: f32) -> Self {
         Self {
             p0: Point {
                 x: self.p0.x,
@@ -483,7 +517,7 @@ impl Segment {
         }
     }
 
-    pub(crate) fn stretch_with_fixed_first_point_y(self, y: f32) -> Self {
+    pub fn stretch_with_fixed_first_point_y(self, y: f32) -> Self {
         Self {
             p0: Point {
                 x: self.p0.x,
@@ -496,7 +530,7 @@ impl Segment {
         }
     }
 
-    pub(crate) fn ray_cast(self, rhs: Self) -> Option<RayCastScalars> {
+    pub fn ray_cast(self, rhs: Self) -> Option<RayCastScalars> {
         let a = self.p0;
         let b = self.p1;
 
@@ -518,12 +552,18 @@ impl Segment {
     }
 }
 
-pub(crate) trait Collide<Rhs: ?Sized = Self> {
+pub trait Collide<Rhs: ?Sized = Self> {
     fn collide(&self, rhs: &Rhs) -> Option<Vector>;
 }
 
 impl<const C0: usize, const C1: usize> Collide<[Segment; C1]> for [Segment; C0] {
     fn collide(&self, rhs: &[Segment; C1]) -> Option<Vector> {
+        self.as_slice().collide(rhs.as_slice())
+    }
+}
+
+impl Collide<[Segment]> for [Segment] {
+    fn collide(&self, rhs: &[Segment]) -> Option<Vector> {
         let axes: Vec<_> = self
             .iter()
             .chain(rhs.iter())
@@ -583,68 +623,7 @@ impl<const C0: usize, const C1: usize> Collide<[Segment; C1]> for [Segment; C0]
     }
 }
 
-// impl Collide<[Segment]> for [Segment] {
-//     fn collide(&self, rhs: &[Segment]) -> Option<Vector> {
-//         let axes: Vec<_> = self
-//             .iter()
-//             .chain(rhs.iter())
-//             .map(|x| x.vec().left_perpendicular().normalize())
-//             .collect();
-
-//         let mut exit_vectors = Vec::with_capacity(axes.len());
-
-//         for axis in axes {
-//             let proj = |edges: &[Segment]| -> Segment {
-//                 fn min_point(p0: Point, p1: Point) -> Point {
-//                     Point {
-//                         x: p0.x.min(p1.x),
-//                         y: p0.y.min(p1.y),
-//                     }
-//                 }
-
-//                 fn max_point(p0: Point, p1: Point) -> Point {
-//                     Point {
-//                         x: p0.x.max(p1.x),
-//                         y: p0.y.max(p1.y),
-//                     }
-//                 }
-
-//                 edges
-//                     .into_iter()
-//                     .map(|edge| {
-//                         let proj = edge.project_on(axis);
-
-//                         [proj.p0, proj.p1]
-//                     })
-//                     .flatten()
-//                     .fold(None, |m: Option<Segment>, x| {
-//                         m.map_or(Some(Segment { p0: x, p1: x }), |Segment { p0, p1 }| {
-//                             Some(Segment {
-//                                 p0: min_point(p0, x),
-//                                 p1: max_point(p1, x),
-//                             })
-//                         })
-//                     })
-//                     .unwrap()
-//             };
-
-//             let proj0 = proj(self);
-//             let proj1 = proj(rhs);
-
-//             if let Some(exit_vec) = proj0.exit_vec_while_intersects_on_the_same_line(&proj1) {
-//                 exit_vectors.push(exit_vec);
-//             } else {
-//                 return None;
-//             }
-//         }
-
-//         exit_vectors
-//             .into_iter()
-//             .min_by(|a, b| a.len().partial_cmp(&b.len()).unwrap())
-//     }
-// }
-
-pub(crate) trait Segments<const C: usize> {
+pub trait Segments<const C: usize> {
     fn segments_ringe(self) -> [Segment; C];
 }
 
@@ -662,7 +641,7 @@ impl<const C: usize> Segments<C> for [Point; C] {
     }
 }
 
-pub(crate) trait DynSizeSegments {
+pub trait DynSizeSegments {
     fn segments_ringe(&self) -> impl Iterator<Item = Segment>;
 
     fn segments(&self) -> impl Iterator<Item = Segment>;
@@ -1,8 +1,40 @@
 mod game;
-pub(crate) use game::*;
+pub use game::*;
 mod packages;
-pub(crate) use packages::*;
+pub use packages::*;
 mod packeter;
-pub(crate) use packeter::*;
+pub use packeter::*;
 mod math;
-pub(crate) use math::*;
+pub use math::*;
+mod config;
+pub use config::*;
+mod score;
+pub use score::*;
+mod players;
+pub use players::*;
+mod events;
+pub use events::*;
+mod match_state;
+pub use match_state::*;
+mod teams;
+pub use teams::*;
+mod zone;
+pub use zone::*;
+mod map;
+pub use map::*;
+mod pickups;
+pub use pickups::*;
+mod interactions;
+pub use interactions::*;
+mod weapon_state;
+pub use weapon_state::*;
+mod loadout;
+pub use loadout::*;
+mod movement;
+pub use movement::*;
+mod health;
+pub use health::*;
+mod bot;
+pub use bot::*;
+#[cfg(test)]
+pub(crate) mod fixtures;
@@ -0,0 +1,332 @@
+use super::Vector;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+/// Duration of one movement step. Server steps characters with last received input on its tick
+/// and client prediction makes the same steps in real time, regardless of how often input is sent
+pub const MOVEMENT_STEP: Duration = Duration::from_nanos(1_000_000_000 / 60);
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct DashConfig {
+    /// Speed character gets in input direction
+    pub speed: f32,
+    pub cooldown: Duration,
+}
+
+/// Character movement parameters. Speeds are in units per second
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct MovementConfig {
+    pub acceleration: f32,
+    pub max_speed: f32,
+    /// Deceleration which is always applied against velocity
+    pub friction: f32,
+    pub dash: Option<DashConfig>,
+}
+
+impl Default for MovementConfig {
+    fn default() -> Self {
+        Self {
+            acceleration: 3000.,
+            max_speed: 300.,
+            friction: 1500.,
+            dash: Some(DashConfig {
+                speed: 900.,
+                cooldown: Duration::from_secs(2),
+            }),
+        }
+    }
+}
+
+impl MovementConfig {
+    pub fn validate(&self) -> Result<(), String> {
+        let non_negative = |x: f32| x.is_finite() && x >= 0.;
+        if !(non_negative(self.acceleration) && non_negative(self.friction)) {
+            return Err("`acceleration` and `friction` must not be negative".to_string());
+        }
+        if !(self.max_speed.is_finite() && self.max_speed > 0.) {
+            return Err("`max_speed` must be positive".to_string());
+        }
+        if let Some(dash) = &self.dash {
+            if !(dash.speed.is_finite() && dash.speed > 0.) {
+                return Err("dash `speed` must be positive".to_string());
+            }
+        }
+        Ok(())
+    }
+
+    /// Integrates velocity of character for `dt` and returns its displacement. Dashes if `dash`
+    /// is held and cooldown is over
+    pub fn step(
+        &self,
+        state: &mut MovementState,
+        input: MovementInput,
+        dash: bool,
+        dt: Duration,
+        speed_multiplier: f32,
+    ) -> Vector {
+        let t = dt.as_secs_f32();
+        let direction = input.normalized_direction();
+        state.dash_cooldown = state.dash_cooldown.saturating_sub(dt);
+        state.velocity = state.velocity + state.impulse;
+        state.impulse = Vector { x: 0., y: 0. };
+
+        match &self.dash {
+            Some(config) if dash && state.dash_cooldown.is_zero() && direction.len() > 0. => {
+                state.velocity = direction * (config.speed * speed_multiplier);
+                state.dash_cooldown = config.cooldown;
+            }
+            _ => {
+                let speed = state.velocity.len();
+                let slowed = (speed - self.friction * t).max(0.);
+                state.velocity = if slowed > 0. {
+                    state.velocity * (slowed / speed)
+                } else {
+                    Vector { x: 0., y: 0. }
+                };
+
+                state.velocity = state.velocity + direction * (self.acceleration * t);
+                // Speed above max is kept after dash but input can not increase it
+                let max_speed = (self.max_speed * speed_multiplier).max(slowed);
+                let speed = state.velocity.len();
+                if speed > max_speed {
+                    state.velocity = state.velocity * (max_speed / speed);
+                }
+            }
+        }
+
+        state.velocity * t
+    }
+}
+
+/// Impulses given to characters which are hit
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct KnockbackConfig {
+    pub ball: f32,
+    /// Impulse at explosion center. Falls off with distance like damage
+    pub explosion: f32,
+}
+
+impl Default for KnockbackConfig {
+    fn default() -> Self {
+        Self {
+            ball: 150.,
+            explosion: 600.,
+        }
+    }
+}
+
+impl KnockbackConfig {
+    pub fn validate(&self) -> Result<(), String> {
+        let non_negative = |x: f32| x.is_finite() && x >= 0.;
+        if !(non_negative(self.ball) && non_negative(self.explosion)) {
+            return Err("impulses must not be negative".to_string());
+        }
+        Ok(())
+    }
+}
+
+/// Movement keys held by player
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Default)]
+pub struct MovementInput {
+    pub left: bool,
+    pub right: bool,
+    pub up: bool,
+    pub down: bool,
+}
+
+impl MovementInput {
+    /// Unit vector of input direction. Zero if opposite keys cancel each other
+    pub fn normalized_direction(self) -> Vector {
+        let axis = |negative: bool, positive: bool| positive as i8 as f32 - negative as i8 as f32;
+        let direction = Vector {
+            x: axis(self.left, self.right),
+            y: axis(self.up, self.down),
+        };
+        if direction.len() > 0. {
+            direction.normalize()
+        } else {
+            direction
+        }
+    }
+
+    /// Keys which move character closest to `direction`. No keys for zero direction
+    pub fn towards(direction: Vector) -> Self {
+        // Sine of 22.5 degrees, so that diagonal keys are used within 45 degrees of a diagonal
+        let threshold = 0.38;
+        if direction.len() == 0. {
+            return Default::default();
+        }
+        let direction = direction.normalize();
+        Self {
+            left: direction.x < -threshold,
+            right: direction.x > threshold,
+            up: direction.y < -threshold,
+            down: direction.y > threshold,
+        }
+    }
+
+    pub fn is_empty(self) -> bool {
+        self.normalized_direction().len() == 0.
+    }
+}
+
+/// Velocity and dash cooldown of character
+#[derive(Debug, Serialize, Deserialize, Clone, Copy)]
+pub struct MovementState {
+    pub velocity: Vector,
+    pub dash_cooldown: Duration,
+    /// Knockback given by server which is added to velocity on the next step
+    pub impulse: Vector,
+    /// Sum of all knockback given to character. Lets client add each impulse to prediction once
+    pub knockback: Vector,
+}
+
+impl Default for MovementState {
+    fn default() -> Self {
+        Self {
+            velocity: Vector { x: 0., y: 0. },
+            dash_cooldown: Duration::ZERO,
+            impulse: Vector { x: 0., y: 0. },
+            knockback: Vector { x: 0., y: 0. },
+        }
+    }
+}
+
+impl MovementState {
+    /// Whether steps without input do not change anything
+    pub fn idle(&self) -> bool {
+        self.velocity.len() == 0. && self.dash_cooldown.is_zero() && self.impulse.len() == 0.
+    }
+
+    pub fn push(&mut self, impulse: Vector) {
+        self.impulse = self.impulse + impulse;
+        self.knockback = self.knockback + impulse;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{DashConfig, MovementConfig, MovementInput, MovementState};
+    use crate::common::Vector;
+    use std::time::Duration;
+
+    fn config() -> MovementConfig {
+        MovementConfig {
+            acceleration: 1000.,
+            max_speed: 200.,
+            friction: 500.,
+            dash: Some(DashConfig {
+                speed: 600.,
+                cooldown: Duration::from_secs(1),
+            }),
+        }
+    }
+
+    #[test]
+    fn input_direction() {
+        let input = MovementInput {
+            left: true,
+            right: true,
+            up: true,
+            ..Default::default()
+        };
+        let direction = input.normalized_direction();
+        assert_eq!((direction.x, direction.y), (0., -1.));
+
+        let input = MovementInput {
+            right: true,
+            down: true,
+            ..Default::default()
+        };
+        assert!((input.normalized_direction().len() - 1.).abs() < 1e-6);
+        assert!(MovementInput::default().is_empty());
+
+        let input = MovementInput::towards(Vector { x: 1., y: -0.9 });
+        assert!(input.right && input.up && !input.left && !input.down);
+        let input = MovementInput::towards(Vector { x: -1., y: 0.1 });
+        assert!(input.left && !input.up && !input.down);
+        assert!(MovementInput::towards(Vector { x: 0., y: 0. }).is_empty());
+    }
+
+    #[test]
+    fn acceleration_and_friction() {
+        let config = config();
+        let dt = Duration::from_millis(100);
+        let mut state: MovementState = Default::default();
+        let input = MovementInput {
+            right: true,
+            ..Default::default()
+        };
+
+        let displacement = config.step(&mut state, input, false, dt, 1.);
+        assert!((state.velocity.x - 100.).abs() < 1e-3);
+        assert!((displacement.x - 10.).abs() < 1e-3);
+
+        for _ in 0..10 {
+            config.step(&mut state, input, false, dt, 1.);
+        }
+        assert!((state.velocity.x - 200.).abs() < 1e-3);
+        config.step(&mut state, input, false, dt, 2.);
+        assert!((state.velocity.x - 250.).abs() < 1e-3);
+
+        let diagonal = MovementInput {
+            right: true,
+            down: true,
+            ..Default::default()
+        };
+        let mut diagonal_state: MovementState = Default::default();
+        for _ in 0..10 {
+            config.step(&mut diagonal_state, diagonal, false, dt, 1.);
+        }
+        assert!((diagonal_state.velocity.len() - 200.).abs() < 1e-3);
+
+        for _ in 0..5 {
+            config.step(&mut state, Default::default(), false, dt, 1.);
+        }
+        assert_eq!(state.velocity.len(), 0.);
+        assert!(state.idle());
+    }
+
+    #[test]
+    fn dash() {
+        let config = config();
+        let dt = Duration::from_millis(100);
+        let mut state: MovementState = Default::default();
+        let input = MovementInput {
+            up: true,
+            ..Default::default()
+        };
+
+        config.step(&mut state, input, true, dt, 1.);
+        assert!((state.velocity.y + 600.).abs() < 1e-3);
+        assert_eq!(state.dash_cooldown, Duration::from_secs(1));
+
+        // Dash momentum decays with friction and can not be renewed during cooldown
+        config.step(&mut state, input, true, dt, 1.);
+        assert!((state.velocity.y + 550.).abs() < 1e-3);
+        assert!(!state.idle());
+        for _ in 0..9 {
+            config.step(&mut state, Default::default(), false, dt, 1.);
+        }
+        assert!(state.dash_cooldown.is_zero());
+        config.step(&mut state, input, true, dt, 1.);
+        assert!((state.velocity.y + 600.).abs() < 1e-3);
+    }
+
+    #[test]
+    fn impulse() {
+        let config = config();
+        let dt = Duration::from_millis(100);
+        let mut state = MovementState {
+            impulse: Vector { x: 400., y: 0. },
+            ..Default::default()
+        };
+        assert!(!state.idle());
+
+        config.step(&mut state, Default::default(), false, dt, 1.);
+        assert!((state.velocity.x - 350.).abs() < 1e-3);
+        assert_eq!(state.impulse.len(), 0.);
+        config.step(&mut state, Default::default(), false, dt, 1.);
+        assert!((state.velocity.x - 300.).abs() < 1e-3);
+    }
+}
@@ -1,85 +1,214 @@
-use super::{Color, Complex, GameState, PlayerState, Vector};
+use super::{
+    Color, Complex, GameConfig, GameEvent, GameState, Map, MovementInput, PlayerState, Team,
+};
 use serde::{Deserialize, Serialize};
 use std::num::NonZero;
 
 /// Sent from server to client when it is connected
 #[derive(Debug, Serialize, Deserialize)]
-pub(crate) struct InitPackage {
-    pub(crate) player_id: NonZero<u64>,
+pub struct InitPackage {
+    pub player_id: NonZero<u64>,
 }
 
 /// Sent from server to client with fixed intervals
 #[derive(Debug, Serialize, Deserialize)]
-pub(crate) struct BroadcastPackage {
-    pub(crate) sequence_number: u32,
-    pub(crate) game_state: GameState,
-    pub(crate) player_state: PlayerState,
+pub struct BroadcastPackage {
+    pub sequence_number: u32,
+    pub game_state: GameState,
+    pub player_state: PlayerState,
 }
 
 /// Sent from client to server after init package is received
 #[derive(Debug, Serialize, Deserialize)]
-pub(crate) struct PlayerConnectedPackage {
-    pub(crate) color: Color,
+pub struct PlayerConnectedPackage {
+    pub name: String,
+    pub color: Color,
+    /// Preferred team. Server picks smallest team if not set. Ignored in modes without teams
+    pub team: Option<Team>,
+}
+
+/// Sent from server to client instead of game packages if player connected package is invalid.
+/// Connection is closed after it
+#[derive(Debug, Serialize, Deserialize)]
+pub struct JoinRejectedPackage {
+    pub reason: String,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
-pub(crate) enum PlayerWeapon {
+pub enum PlayerWeapon {
     BallGun,
     PulseGun,
     RayGun,
     Shield,
     MineGun,
+    MissileLauncher,
+    GrenadeLauncher,
 }
 
 impl PlayerWeapon {
-    pub(crate) fn rotated_left(self) -> Self {
+    pub const ALL: [PlayerWeapon; 7] = [
+        PlayerWeapon::BallGun,
+        PlayerWeapon::PulseGun,
+        PlayerWeapon::RayGun,
+        PlayerWeapon::Shield,
+        PlayerWeapon::MineGun,
+        PlayerWeapon::MissileLauncher,
+        PlayerWeapon::GrenadeLauncher,
+    ];
+
+    pub fn rotated_left(self) -> Self {
         match self {
-            PlayerWeapon::BallGun => PlayerWeapon::MineGun,
+            PlayerWeapon::BallGun => PlayerWeapon::GrenadeLauncher,
             PlayerWeapon::PulseGun => PlayerWeapon::BallGun,
             PlayerWeapon::RayGun => PlayerWeapon::PulseGun,
             PlayerWeapon::Shield => PlayerWeapon::RayGun,
             PlayerWeapon::MineGun => PlayerWeapon::Shield,
+            PlayerWeapon::MissileLauncher => PlayerWeapon::MineGun,
+            PlayerWeapon::GrenadeLauncher => PlayerWeapon::MissileLauncher,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            PlayerWeapon::BallGun => "Ball gun",
+            PlayerWeapon::PulseGun => "Pulse gun",
+            PlayerWeapon::RayGun => "Ray gun",
+            PlayerWeapon::Shield => "Shield",
+            PlayerWeapon::MineGun => "Mine gun",
+            PlayerWeapon::MissileLauncher => "Missile launcher",
+            PlayerWeapon::GrenadeLauncher => "Grenade launcher",
         }
     }
 
-    pub(crate) fn rotated_right(self) -> Self {
+    pub fn rotated_right(self) -> Self {
         match self {
             PlayerWeapon::BallGun => PlayerWeapon::PulseGun,
             PlayerWeapon::PulseGun => PlayerWeapon::RayGun,
             PlayerWeapon::RayGun => PlayerWeapon::Shield,
             PlayerWeapon::Shield => PlayerWeapon::MineGun,
-            PlayerWeapon::MineGun => PlayerWeapon::BallGun,
+            PlayerWeapon::MineGun => PlayerWeapon::MissileLauncher,
+            PlayerWeapon::MissileLauncher => PlayerWeapon::GrenadeLauncher,
+            PlayerWeapon::GrenadeLauncher => PlayerWeapon::BallGun,
         }
     }
 }
 
 #[derive(Debug, Serialize, Deserialize)]
-pub(crate) struct RespawnRequestPackage {
-    pub(crate) weapon: PlayerWeapon,
+pub struct RespawnRequestPackage {
+    /// First weapon is held after respawn. Server drops weapons which do not fit in loadout
+    pub loadout: Vec<PlayerWeapon>,
+}
+
+/// Sent from client to server to switch to another weapon of loadout while alive
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SwitchWeaponPackage {
+    pub slot: usize,
+}
+
+/// Bitfield of held action buttons
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+pub struct InputButtons(u8);
+
+impl InputButtons {
+    pub const PRIMARY: Self = Self(1);
+    pub const SECONDARY: Self = Self(1 << 1);
+    /// Dash
+    pub const ABILITY: Self = Self(1 << 2);
+    pub const RELOAD: Self = Self(1 << 3);
+
+    pub fn contains(self, buttons: Self) -> bool {
+        self.0 & buttons.0 == buttons.0
+    }
+
+    pub fn set(&mut self, buttons: Self, pressed: bool) {
+        if pressed {
+            self.0 |= buttons.0;
+        } else {
+            self.0 &= !buttons.0;
+        }
+    }
+
+    /// Buttons which are held now but were not held in `previous`
+    pub fn pressed_since(self, previous: Self) -> Self {
+        Self(self.0 & !previous.0)
+    }
 }
 
 /// Sent from client to server when player inputs something
 #[derive(Debug, Serialize, Deserialize)]
-pub(crate) struct PlayerInputPackage {
-    pub(crate) sequence_number: u32,
-    pub(crate) movement: Vector,
-    pub(crate) rotation: Complex,
-    pub(crate) left_mouse_pressed: bool,
+pub struct PlayerInputPackage {
+    pub sequence_number: u32,
+    /// Input of a single movement step
+    pub movement: MovementInput,
+    pub rotation: Complex,
+    pub buttons: InputButtons,
+}
+
+/// Sent from server to client when its character is killed
+#[derive(Debug, Serialize, Deserialize)]
+pub struct KillPackage {
+    pub killer: NonZero<u64>,
+    pub weapon: PlayerWeapon,
+}
+
+/// Sent from server to client for each game event
+#[derive(Debug, Serialize, Deserialize)]
+pub struct EventPackage {
+    pub event: GameEvent,
+}
+
+/// Sent from server to client when it is connected and each time gameplay config is reloaded
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ConfigPackage {
+    pub config: GameConfig,
 }
 
+/// Sent from server to client when it is connected
 #[derive(Debug, Serialize, Deserialize)]
-pub(crate) struct KillPackage {}
+pub struct MapPackage {
+    pub map: Map,
+}
 
 #[derive(Debug, Serialize, Deserialize)]
-pub(crate) enum ServerToClientPackage {
+pub enum ServerToClientPackage {
     Init(InitPackage),
-    Broadcast(BroadcastPackage),
+    Broadcast(Box<BroadcastPackage>),
     Kill(KillPackage),
+    Config(Box<ConfigPackage>),
+    Event(EventPackage),
+    JoinRejected(JoinRejectedPackage),
+    Map(MapPackage),
 }
 
 #[derive(Debug, Serialize, Deserialize)]
-pub(crate) enum ClientToServerPackage {
+pub enum ClientToServerPackage {
     PlayerConnected(PlayerConnectedPackage),
     RespawnRequest(RespawnRequestPackage),
     PlayerInput(PlayerInputPackage),
+    SwitchWeapon(SwitchWeaponPackage),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::InputButtons;
+
+    #[test]
+    fn input_buttons() {
+        let mut buttons: InputButtons = Default::default();
+        buttons.set(InputButtons::PRIMARY, true);
+        buttons.set(InputButtons::RELOAD, true);
+        assert!(buttons.contains(InputButtons::PRIMARY));
+        assert!(!buttons.contains(InputButtons::SECONDARY));
+        assert!(!buttons.contains(InputButtons::ABILITY));
+
+        let previous = buttons;
+        buttons.set(InputButtons::PRIMARY, false);
+        buttons.set(InputButtons::SECONDARY, true);
+        assert_eq!(buttons.pressed_since(previous), InputButtons::SECONDARY);
+        assert_eq!(previous.pressed_since(buttons), InputButtons::PRIMARY);
+        assert_eq!(
+            serde_json::to_string(&buttons).unwrap(),
+            (InputButtons::SECONDARY.0 | InputButtons::RELOAD.0).to_string()
+        );
+    }
 }
@@ -6,7 +6,7 @@ use std::{
 
 type PacketSize = u32;
 
-pub(crate) struct PacketReader {
+pub struct PacketReader {
     buffer: Vec<u8>,
 }
 
@@ -18,7 +18,7 @@ impl Default for PacketReader {
     }
 }
 
-pub(crate) struct PacketReaderIter<'a> {
+pub struct PacketReaderIter<'a> {
     reader: &'a mut PacketReader,
 }
 
@@ -46,7 +46,7 @@ impl<'a> Iterator for PacketReaderIter<'a> {
 }
 
 impl PacketReader {
-    pub(crate) fn read<'a, R: Read>(
+    pub fn read<'a, R: Read>(
         &'a mut self,
         read: &mut R,
     ) -> Result<PacketReaderIter<'a>, std::io::Error> {
@@ -54,7 +54,7 @@ impl PacketReader {
         Ok(PacketReaderIter { reader: self })
     }
 
-    pub(crate) fn read_ref<'a, R: Read>(
+    pub fn read_ref<'a, R: Read>(
         &'a mut self,
         mut read: RefMut<R>,
     ) -> Result<PacketReaderIter<'a>, std::io::Error> {
@@ -81,7 +81,7 @@ impl PacketReader {
     }
 }
 
-pub(crate) struct PacketWriter {}
+pub struct PacketWriter {}
 
 impl PacketWriter {
     pub fn write<W: Write>(write: &mut W, data: &[u8]) -> Result<(), std::io::Error> {
@@ -0,0 +1,237 @@
+use super::PlayerWeapon;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub enum PickupKind {
+    /// Restores health up to the max
+    Health { amount: f32 },
+    /// Adds armor up to the max
+    Armor { amount: f32 },
+    /// Scales character movement
+    SpeedBoost { multiplier: f32, duration: Duration },
+    /// Scales `fire_interval` of character weapon
+    RapidFire {
+        fire_interval_scale: f32,
+        duration: Duration,
+    },
+    /// Next shot is made with given weapon
+    WeaponSwap { weapon: PlayerWeapon },
+}
+
+impl PickupKind {
+    pub fn name(&self) -> &'static str {
+        match self {
+            PickupKind::Health { .. } => "Health",
+            PickupKind::Armor { .. } => "Armor",
+            PickupKind::SpeedBoost { .. } => "Speed",
+            PickupKind::RapidFire { .. } => "Rapid fire",
+            PickupKind::WeaponSwap { .. } => "Weapon swap",
+        }
+    }
+
+    /// Buff given to character which collects pickup. `None` for instant pickups
+    pub fn buff(&self) -> Option<Buff> {
+        match self {
+            PickupKind::Health { .. } | PickupKind::Armor { .. } => None,
+            PickupKind::SpeedBoost {
+                multiplier,
+                duration,
+            } => Some(Buff::SpeedBoost {
+                multiplier: *multiplier,
+                remaining: *duration,
+            }),
+            PickupKind::RapidFire {
+                fire_interval_scale,
+                duration,
+            } => Some(Buff::RapidFire {
+                fire_interval_scale: *fire_interval_scale,
+                remaining: *duration,
+            }),
+            PickupKind::WeaponSwap { weapon } => Some(Buff::WeaponSwap { weapon: *weapon }),
+        }
+    }
+}
+
+/// Effect of collected pickup which is active for a while
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub enum Buff {
+    SpeedBoost {
+        multiplier: f32,
+        remaining: Duration,
+    },
+    RapidFire {
+        fire_interval_scale: f32,
+        remaining: Duration,
+    },
+    /// Lasts until next shot
+    WeaponSwap { weapon: PlayerWeapon },
+}
+
+impl Buff {
+    pub fn name(&self) -> String {
+        match self {
+            Buff::SpeedBoost { remaining, .. } => format!("Speed {:.0}", remaining.as_secs_f32()),
+            Buff::RapidFire { remaining, .. } => {
+                format!("Rapid fire {:.0}", remaining.as_secs_f32())
+            }
+            Buff::WeaponSwap { weapon } => weapon.name().to_string(),
+        }
+    }
+
+    /// Whether buffs can not be active at the same time
+    fn same_kind(&self, rhs: &Buff) -> bool {
+        std::mem::discriminant(self) == std::mem::discriminant(rhs)
+    }
+
+    /// Adds buff replacing active buff of the same kind
+    pub fn apply(buffs: &mut Vec<Buff>, buff: Buff) {
+        buffs.retain(|x| !x.same_kind(&buff));
+        buffs.push(buff);
+    }
+
+    /// Counts down timed buffs and removes expired ones
+    pub fn proceed(buffs: &mut Vec<Buff>, dt: Duration) {
+        buffs.retain_mut(|buff| match buff {
+            Buff::SpeedBoost { remaining, .. } | Buff::RapidFire { remaining, .. } => {
+                *remaining = remaining.saturating_sub(dt);
+                !remaining.is_zero()
+            }
+            Buff::WeaponSwap { .. } => true,
+        });
+    }
+
+    pub fn speed_multiplier(buffs: &[Buff]) -> f32 {
+        buffs
+            .iter()
+            .map(|x| match x {
+                Buff::SpeedBoost { multiplier, .. } => *multiplier,
+                _ => 1.,
+            })
+            .product()
+    }
+
+    pub fn fire_interval_scale(buffs: &[Buff]) -> f32 {
+        buffs
+            .iter()
+            .map(|x| match x {
+                Buff::RapidFire {
+                    fire_interval_scale,
+                    ..
+                } => *fire_interval_scale,
+                _ => 1.,
+            })
+            .product()
+    }
+
+    pub fn swapped_weapon(buffs: &[Buff]) -> Option<PlayerWeapon> {
+        buffs.iter().find_map(|x| match x {
+            Buff::WeaponSwap { weapon } => Some(*weapon),
+            _ => None,
+        })
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct PickupsConfig {
+    /// New pickup appears after this interval while there are less than `max_count` of them
+    pub spawn_interval: Duration,
+    pub max_count: usize,
+    /// Spawned pickup kind is chosen randomly from these. Pickups are disabled if empty
+    pub kinds: Vec<PickupKind>,
+}
+
+impl Default for PickupsConfig {
+    fn default() -> Self {
+        Self {
+            spawn_interval: Duration::from_secs(10),
+            max_count: 3,
+            kinds: vec![
+                PickupKind::Health { amount: 1. },
+                PickupKind::Armor { amount: 1. },
+                PickupKind::SpeedBoost {
+                    multiplier: 1.5,
+                    duration: Duration::from_secs(8),
+                },
+                PickupKind::RapidFire {
+                    fire_interval_scale: 0.5,
+                    duration: Duration::from_secs(8),
+                },
+                PickupKind::WeaponSwap {
+                    weapon: PlayerWeapon::RayGun,
+                },
+            ],
+        }
+    }
+}
+
+impl PickupsConfig {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.spawn_interval.is_zero() {
+            return Err("`spawn_interval` must not be zero".to_string());
+        }
+        for kind in &self.kinds {
+            let valid = match kind {
+                PickupKind::Health { amount } | PickupKind::Armor { amount } => {
+                    amount.is_finite() && *amount > 0.
+                }
+                PickupKind::SpeedBoost { multiplier, .. } => {
+                    multiplier.is_finite() && *multiplier > 0.
+                }
+                PickupKind::RapidFire {
+                    fire_interval_scale,
+                    ..
+                } => fire_interval_scale.is_finite() && *fire_interval_scale > 0.,
+                PickupKind::WeaponSwap { .. } => true,
+            };
+            if !valid {
+                return Err(format!("invalid pickup kind {:?}", kind));
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Buff;
+    use crate::common::PlayerWeapon;
+    use std::time::Duration;
+
+    #[test]
+    fn buffs() {
+        let mut buffs = vec![];
+        Buff::apply(
+            &mut buffs,
+            Buff::SpeedBoost {
+                multiplier: 2.,
+                remaining: Duration::from_secs(1),
+            },
+        );
+        Buff::apply(
+            &mut buffs,
+            Buff::SpeedBoost {
+                multiplier: 1.5,
+                remaining: Duration::from_secs(2),
+            },
+        );
+        Buff::apply(
+            &mut buffs,
+            Buff::WeaponSwap {
+                weapon: PlayerWeapon::MineGun,
+            },
+        );
+        assert_eq!(buffs.len(), 2);
+        assert_eq!(Buff::speed_multiplier(&buffs), 1.5);
+        assert_eq!(Buff::fire_interval_scale(&buffs), 1.);
+        assert_eq!(Buff::swapped_weapon(&buffs), Some(PlayerWeapon::MineGun));
+
+        Buff::proceed(&mut buffs, Duration::from_secs(2));
+        assert_eq!(
+            buffs,
+            vec![Buff::WeaponSwap {
+                weapon: PlayerWeapon::MineGun
+            }]
+        );
+    }
+}
@@ -0,0 +1,126 @@
+use super::{Color, Team};
+use serde::{Deserialize, Serialize};
+use std::num::NonZero;
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct PlayerInfo {
+    pub player_id: NonZero<u64>,
+    pub name: String,
+    pub color: Color,
+    /// Team of player in team modes
+    pub team: Option<Team>,
+}
+
+/// Players connected to server. Part of game state so it is broadcasted to clients with it
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct PlayerRegistry {
+    players: Vec<PlayerInfo>,
+}
+
+impl PlayerRegistry {
+    pub const MAX_NAME_LEN: usize = 16;
+
+    /// Checks that name is not empty and not too long. Can be used by client before connecting
+    pub fn validate_name_format(name: &str) -> Result<(), String> {
+        if name.trim() != name {
+            return Err("Name must not start or end with spaces".to_string());
+        }
+        if name.is_empty() {
+            return Err("Name must not be empty".to_string());
+        }
+        if name.chars().count() > Self::MAX_NAME_LEN {
+            return Err(format!(
+                "Name must not be longer than {} characters",
+                Self::MAX_NAME_LEN
+            ));
+        }
+        if name.chars().any(|c| c.is_control()) {
+            return Err("Name must not contain control characters".to_string());
+        }
+        Ok(())
+    }
+
+    /// Checks name format and that name is not used by other player
+    pub fn validate_name(&self, name: &str) -> Result<(), String> {
+        Self::validate_name_format(name)?;
+        if self
+            .players
+            .iter()
+            .any(|x| x.name.to_lowercase() == name.to_lowercase())
+        {
+            return Err(format!("Name `{}` is already taken", name));
+        }
+        Ok(())
+    }
+
+    pub fn add(&mut self, info: PlayerInfo) {
+        self.remove(info.player_id);
+        self.players.push(info);
+    }
+
+    pub fn remove(&mut self, player_id: NonZero<u64>) -> Option<PlayerInfo> {
+        let index = self.players.iter().position(|x| x.player_id == player_id)?;
+        Some(self.players.remove(index))
+    }
+
+    pub fn get(&self, player_id: NonZero<u64>) -> Option<&PlayerInfo> {
+        self.players.iter().find(|x| x.player_id == player_id)
+    }
+
+    pub fn team(&self, player_id: NonZero<u64>) -> Option<Team> {
+        self.get(player_id).and_then(|x| x.team)
+    }
+
+    /// Whether players are different members of the same team
+    pub fn teammates(&self, a: NonZero<u64>, b: NonZero<u64>) -> bool {
+        a != b && self.team(a).is_some() && self.team(a) == self.team(b)
+    }
+
+    /// Team with fewest players. Used to auto balance teams on join
+    pub fn smallest_team(&self) -> Team {
+        Team::ALL
+            .into_iter()
+            .min_by_key(|team| {
+                self.players
+                    .iter()
+                    .filter(|x| x.team == Some(*team))
+                    .count()
+            })
+            .unwrap()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{PlayerInfo, PlayerRegistry};
+    use crate::common::Color;
+    use std::num::NonZero;
+
+    #[test]
+    fn name_validation() {
+        let mut registry: PlayerRegistry = Default::default();
+        registry.add(PlayerInfo {
+            player_id: NonZero::new(1).unwrap(),
+            name: "Alice".to_string(),
+            color: Color {
+                a: 255,
+                r: 255,
+                g: 0,
+                b: 0,
+            },
+            team: None,
+        });
+
+        assert!(registry.validate_name("Bob").is_ok());
+        assert!(registry.validate_name("alice").is_err());
+        assert!(registry.validate_name("").is_err());
+        assert!(registry.validate_name(" Bob").is_err());
+        assert!(registry.validate_name("Bob\n").is_err());
+        assert!(registry
+            .validate_name(&"x".repeat(PlayerRegistry::MAX_NAME_LEN))
+            .is_ok());
+        assert!(registry
+            .validate_name(&"x".repeat(PlayerRegistry::MAX_NAME_LEN + 1))
+            .is_err());
+    }
+}
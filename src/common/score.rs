@@ -0,0 +1,294 @@
+use super::{GameMode, Team, ZoneHolder};
+use serde::{Deserialize, Serialize};
+use std::num::NonZero;
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct PlayerStats {
+    pub player_id: NonZero<u64>,
+    pub team: Option<Team>,
+    pub kills: u32,
+    pub deaths: u32,
+    /// Deaths caused by player's own projectiles
+    pub self_kills: u32,
+    /// Deliberate deaths, e.g. shield self destruct
+    pub suicides: u32,
+    /// Teammates killed by friendly fire
+    pub team_kills: u32,
+    /// Enemy flags brought to own base
+    pub captures: u32,
+    /// Points earned by holding king of the hill zone
+    pub zone_points: u32,
+    pub streak: u32,
+    pub best_streak: u32,
+}
+
+impl PlayerStats {
+    fn new(player_id: NonZero<u64>, team: Option<Team>) -> Self {
+        Self {
+            player_id,
+            team,
+            kills: 0,
+            deaths: 0,
+            self_kills: 0,
+            suicides: 0,
+            team_kills: 0,
+            captures: 0,
+            zone_points: 0,
+            streak: 0,
+            best_streak: 0,
+        }
+    }
+
+    pub fn score(&self) -> i64 {
+        self.kills as i64 + self.captures as i64 + self.zone_points as i64
+            - self.self_kills as i64
+            - self.suicides as i64
+            - self.team_kills as i64
+    }
+
+    fn die(&mut self) {
+        self.deaths += 1;
+        self.streak = 0;
+    }
+}
+
+/// Per player kill statistics. Part of game state so it is broadcasted to clients with it
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct Scoreboard {
+    players: Vec<PlayerStats>,
+    /// Kept separately from player stats so that team keeps its score when players leave
+    team_scores: Vec<(Team, i64)>,
+}
+
+impl Scoreboard {
+    pub fn add_player(&mut self, player_id: NonZero<u64>, team: Option<Team>) {
+        if self.stats(player_id).is_none() {
+            self.players.push(PlayerStats::new(player_id, team));
+        }
+    }
+
+    pub fn remove_player(&mut self, player_id: NonZero<u64>) {
+        self.players.retain(|x| x.player_id != player_id);
+    }
+
+    pub fn player_count(&self) -> usize {
+        self.players.len()
+    }
+
+    /// Zeroes stats of all players
+    pub fn reset(&mut self) {
+        for stats in &mut self.players {
+            *stats = PlayerStats::new(stats.player_id, stats.team);
+        }
+        self.team_scores.clear();
+    }
+
+    pub fn team_score(&self, team: Team) -> i64 {
+        self.team_scores
+            .iter()
+            .find(|(x, _)| *x == team)
+            .map_or(0, |(_, score)| *score)
+    }
+
+    fn add_team_score(&mut self, team: Option<Team>, delta: i64) {
+        if let Some(team) = team {
+            match self.team_scores.iter_mut().find(|(x, _)| *x == team) {
+                Some((_, score)) => *score += delta,
+                None => self.team_scores.push((team, delta)),
+            }
+        }
+    }
+
+    fn team_of(&self, player_id: NonZero<u64>) -> Option<Team> {
+        self.stats(player_id).and_then(|x| x.team)
+    }
+
+    /// Team with highest score. `None` if nobody plays in a team
+    pub fn leading_team(&self) -> Option<Team> {
+        Team::ALL
+            .into_iter()
+            .filter(|team| self.players.iter().any(|x| x.team == Some(*team)))
+            .max_by_key(|team| self.team_score(*team))
+    }
+
+    /// Score of leading team in team modes or of best player otherwise
+    pub fn top_score(&self) -> Option<i64> {
+        match self.leading_team() {
+            Some(team) => Some(self.team_score(team)),
+            None => self.players.iter().map(|x| x.score()).max(),
+        }
+    }
+
+    pub fn stats(&self, player_id: NonZero<u64>) -> Option<&PlayerStats> {
+        self.players.iter().find(|x| x.player_id == player_id)
+    }
+
+    fn stats_mut(&mut self, player_id: NonZero<u64>) -> Option<&mut PlayerStats> {
+        self.players.iter_mut().find(|x| x.player_id == player_id)
+    }
+
+    /// Credits `killer` for killing `victim`. Killing yourself is counted as a self kill,
+    /// killing a teammate as a team kill. Both take a point from the team.
+    /// Players which are not added (e.g. already left) are ignored
+    pub fn record_kill(&mut self, victim: NonZero<u64>, killer: NonZero<u64>, mode: GameMode) {
+        let killer_team = self.team_of(killer);
+        if victim == killer {
+            self.add_team_score(killer_team, -1);
+            if let Some(stats) = self.stats_mut(victim) {
+                stats.self_kills += 1;
+                stats.die();
+            }
+        } else if killer_team.is_some() && killer_team == self.team_of(victim) {
+            self.add_team_score(killer_team, -1);
+            if let Some(stats) = self.stats_mut(killer) {
+                stats.team_kills += 1;
+            }
+            if let Some(stats) = self.stats_mut(victim) {
+                stats.die();
+            }
+        } else {
+            self.add_team_score(killer_team, mode.kill_team_points());
+            if let Some(stats) = self.stats_mut(killer) {
+                stats.kills += 1;
+                stats.streak += 1;
+                stats.best_streak = stats.best_streak.max(stats.streak);
+            }
+            if let Some(stats) = self.stats_mut(victim) {
+                stats.die();
+            }
+        }
+    }
+
+    pub fn record_capture(&mut self, player_id: NonZero<u64>) {
+        self.add_team_score(self.team_of(player_id), 1);
+        if let Some(stats) = self.stats_mut(player_id) {
+            stats.captures += 1;
+        }
+    }
+
+    pub fn record_zone_points(&mut self, holder: ZoneHolder, points: u32) {
+        match holder {
+            ZoneHolder::Team(team) => self.add_team_score(Some(team), points as i64),
+            ZoneHolder::Player(player_id) => {
+                if let Some(stats) = self.stats_mut(player_id) {
+                    stats.zone_points += points;
+                }
+            }
+        }
+    }
+
+    pub fn record_suicide(&mut self, victim: NonZero<u64>) {
+        self.add_team_score(self.team_of(victim), -1);
+        if let Some(stats) = self.stats_mut(victim) {
+            stats.suicides += 1;
+            stats.die();
+        }
+    }
+
+    /// Stats sorted from best to worst
+    pub fn ranked(&self) -> Vec<&PlayerStats> {
+        let mut result: Vec<_> = self.players.iter().collect();
+        result.sort_by(|a, b| {
+            b.score()
+                .cmp(&a.score())
+                .then(a.deaths.cmp(&b.deaths))
+                .then(a.player_id.cmp(&b.player_id))
+        });
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Scoreboard;
+    use crate::common::{GameMode, Team};
+    use std::num::NonZero;
+
+    #[test]
+    fn kills_and_streaks() {
+        let a = NonZero::new(1).unwrap();
+        let b = NonZero::new(2).unwrap();
+        let mut scoreboard: Scoreboard = Default::default();
+        scoreboard.add_player(a, None);
+        scoreboard.add_player(b, None);
+
+        scoreboard.record_kill(b, a, GameMode::FreeForAll);
+        scoreboard.record_kill(b, a, GameMode::FreeForAll);
+        scoreboard.record_kill(a, b, GameMode::FreeForAll);
+        scoreboard.record_kill(b, a, GameMode::FreeForAll);
+
+        let stats = scoreboard.stats(a).unwrap();
+        assert_eq!((stats.kills, stats.deaths), (3, 1));
+        assert_eq!((stats.streak, stats.best_streak), (1, 2));
+
+        let stats = scoreboard.stats(b).unwrap();
+        assert_eq!((stats.kills, stats.deaths), (1, 3));
+        assert_eq!((stats.streak, stats.best_streak), (0, 1));
+
+        assert_eq!(scoreboard.ranked()[0].player_id, a);
+    }
+
+    #[test]
+    fn self_kills_and_suicides() {
+        let a = NonZero::new(1).unwrap();
+        let b = NonZero::new(2).unwrap();
+        let mut scoreboard: Scoreboard = Default::default();
+        scoreboard.add_player(a, None);
+        scoreboard.add_player(b, None);
+
+        scoreboard.record_kill(b, a, GameMode::FreeForAll);
+        scoreboard.record_kill(a, a, GameMode::FreeForAll);
+        scoreboard.record_suicide(a);
+
+        let stats = scoreboard.stats(a).unwrap();
+        assert_eq!(stats.kills, 1);
+        assert_eq!(stats.self_kills, 1);
+        assert_eq!(stats.suicides, 1);
+        assert_eq!(stats.deaths, 2);
+        assert_eq!(stats.streak, 0);
+        assert_eq!(stats.score(), -1);
+    }
+
+    #[test]
+    fn team_scores() {
+        let a = NonZero::new(1).unwrap();
+        let b = NonZero::new(2).unwrap();
+        let c = NonZero::new(3).unwrap();
+        let mut scoreboard: Scoreboard = Default::default();
+        scoreboard.add_player(a, Some(Team::Red));
+        scoreboard.add_player(b, Some(Team::Red));
+        scoreboard.add_player(c, Some(Team::Blue));
+
+        scoreboard.record_kill(c, a, GameMode::TeamDeathmatch);
+        scoreboard.record_kill(c, b, GameMode::TeamDeathmatch);
+        scoreboard.record_kill(b, a, GameMode::TeamDeathmatch);
+        scoreboard.record_kill(a, c, GameMode::TeamDeathmatch);
+
+        assert_eq!(scoreboard.team_score(Team::Red), 1);
+        assert_eq!(scoreboard.team_score(Team::Blue), 1);
+        assert_eq!(scoreboard.stats(a).unwrap().team_kills, 1);
+        assert_eq!(scoreboard.stats(a).unwrap().score(), 0);
+        assert_eq!(scoreboard.stats(b).unwrap().deaths, 1);
+
+        scoreboard.record_kill(c, b, GameMode::TeamDeathmatch);
+        scoreboard.remove_player(b);
+        assert_eq!(scoreboard.team_score(Team::Red), 2);
+        assert_eq!(scoreboard.leading_team(), Some(Team::Red));
+        assert_eq!(scoreboard.top_score(), Some(2));
+    }
+
+    #[test]
+    fn captures() {
+        let a = NonZero::new(1).unwrap();
+        let b = NonZero::new(2).unwrap();
+        let mut scoreboard: Scoreboard = Default::default();
+        scoreboard.add_player(a, Some(Team::Red));
+        scoreboard.add_player(b, Some(Team::Blue));
+
+        scoreboard.record_kill(b, a, GameMode::CaptureTheFlag);
+        scoreboard.record_capture(a);
+
+        assert_eq!(scoreboard.team_score(Team::Red), 1);
+        assert_eq!(scoreboard.stats(a).unwrap().score(), 2);
+    }
+}
@@ -0,0 +1,74 @@
+use super::Color;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+pub enum Team {
+    Red,
+    Blue,
+}
+
+impl Team {
+    pub const ALL: [Team; 2] = [Team::Red, Team::Blue];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Team::Red => "Red",
+            Team::Blue => "Blue",
+        }
+    }
+
+    /// Color of team characters. Overrides color chosen by player
+    pub fn color(self) -> Color {
+        match self {
+            Team::Red => Color {
+                a: 255,
+                r: 128,
+                g: 0,
+                b: 0,
+            },
+            Team::Blue => Color {
+                a: 255,
+                r: 0,
+                g: 64,
+                b: 255,
+            },
+        }
+    }
+}
+
+/// Defines how projectiles of teammates affect characters
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Default)]
+pub enum FriendlyFire {
+    /// Projectiles of teammates pass through and do not trigger mines
+    #[default]
+    Off,
+    /// Projectiles and explosions of teammates deal damage multiplied by `factor`
+    Reduced {
+        factor: f32,
+    },
+    /// Projectiles and explosions of teammates deal full damage but can not take last health
+    /// point
+    NonLethal,
+    Full,
+}
+
+impl FriendlyFire {
+    /// Part of `damage` from a teammate which character with `health` left takes
+    pub fn limit_damage(self, health: f32, damage: f32) -> f32 {
+        match self {
+            FriendlyFire::Off => 0.,
+            FriendlyFire::Reduced { factor } => damage * factor,
+            FriendlyFire::NonLethal => damage.min(health - 1.).max(0.),
+            FriendlyFire::Full => damage,
+        }
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        match self {
+            FriendlyFire::Reduced { factor } if !(*factor > 0. && *factor <= 1.) => {
+                Err(format!("`factor` must be in (0, 1], got {}", factor))
+            }
+            _ => Ok(()),
+        }
+    }
+}
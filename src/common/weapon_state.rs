@@ -0,0 +1,238 @@
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+/// Optional magazine of character weapon
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+pub struct Magazine {
+    pub size: u16,
+    pub reload_duration: Duration,
+    /// Rounds available for reloads after spawn. Unlimited if `None`
+    pub reserve: Option<u16>,
+}
+
+impl Magazine {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.size == 0 {
+            return Err("`size` must not be zero".to_string());
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+pub struct Ammo {
+    pub loaded: u16,
+    pub reserve: Option<u16>,
+    /// Time until reload is finished if weapon is being reloaded
+    pub reload_remaining: Option<Duration>,
+}
+
+/// Cooldown and ammo of character weapon. Synced only to the owning client
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Default)]
+pub struct WeaponState {
+    /// Time until weapon can fire again
+    pub cooldown: Duration,
+    /// Cooldown at the moment of the last shot. Used to show cooldown progress
+    pub cooldown_duration: Duration,
+    /// `None` if weapon has no magazine
+    pub ammo: Option<Ammo>,
+}
+
+impl WeaponState {
+    /// Ready to fire state with full magazine
+    pub fn new(magazine: Option<&Magazine>) -> Self {
+        Self {
+            cooldown: Duration::ZERO,
+            cooldown_duration: Duration::ZERO,
+            ammo: magazine.map(|x| Ammo {
+                loaded: x.size,
+                reserve: x.reserve,
+                reload_remaining: None,
+            }),
+        }
+    }
+
+    pub fn ready(&self) -> bool {
+        self.cooldown.is_zero()
+            && self
+                .ammo
+                .is_none_or(|x| x.loaded > 0 && x.reload_remaining.is_none())
+    }
+
+    pub fn start_cooldown(&mut self, cooldown: Duration) {
+        self.cooldown = cooldown;
+        self.cooldown_duration = cooldown;
+    }
+
+    /// Takes a round from magazine and starts reload when it gets empty
+    pub fn consume_round(&mut self, magazine: Option<&Magazine>) {
+        let Some(ammo) = &mut self.ammo else {
+            return;
+        };
+        ammo.loaded = ammo.loaded.saturating_sub(1);
+        if let (0, Some(magazine)) = (ammo.loaded, magazine) {
+            self.start_reload(magazine);
+        }
+    }
+
+    /// Starts reload unless magazine is full, reload is in progress or there is no reserve
+    pub fn start_reload(&mut self, magazine: &Magazine) -> bool {
+        match &mut self.ammo {
+            Some(ammo)
+                if ammo.loaded < magazine.size
+                    && ammo.reload_remaining.is_none()
+                    && ammo.reserve != Some(0) =>
+            {
+                ammo.reload_remaining = Some(magazine.reload_duration);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Adapts state to changed magazine of the same weapon. Cooldown and reload progress are
+    /// kept and ammo is clamped, so that config reload does not refill magazines
+    pub fn fit_magazine(&mut self, magazine: Option<&Magazine>) {
+        let Some(magazine) = magazine else {
+            self.ammo = None;
+            return;
+        };
+        let Some(ammo) = &mut self.ammo else {
+            self.ammo = Self::new(Some(magazine)).ammo;
+            return;
+        };
+        ammo.loaded = ammo.loaded.min(magazine.size);
+        ammo.reserve = match (ammo.reserve, magazine.reserve) {
+            (Some(reserve), Some(max)) => Some(reserve.min(max)),
+            (None, max) => max,
+            (Some(_), None) => None,
+        };
+        ammo.reload_remaining = ammo
+            .reload_remaining
+            .map(|x| x.min(magazine.reload_duration));
+    }
+
+    /// Counts down cooldown and reload. Magazine is refilled from reserve when reload ends
+    pub fn proceed(&mut self, dt: Duration, magazine: Option<&Magazine>) {
+        self.cooldown = self.cooldown.saturating_sub(dt);
+        let (Some(ammo), Some(magazine)) = (&mut self.ammo, magazine) else {
+            return;
+        };
+        let Some(remaining) = ammo.reload_remaining else {
+            return;
+        };
+        let remaining = remaining.saturating_sub(dt);
+        if !remaining.is_zero() {
+            ammo.reload_remaining = Some(remaining);
+            return;
+        }
+        ammo.reload_remaining = None;
+        let needed = magazine.size.saturating_sub(ammo.loaded);
+        let taken = ammo.reserve.map_or(needed, |x| x.min(needed));
+        ammo.loaded += taken;
+        if let Some(reserve) = &mut ammo.reserve {
+            *reserve -= taken;
+        }
+    }
+
+    /// Fraction of cooldown or reload which is done. 1 if weapon is ready
+    pub fn progress(&self, magazine: Option<&Magazine>) -> f32 {
+        let reload = self
+            .ammo
+            .and_then(|x| x.reload_remaining)
+            .zip(magazine.map(|x| x.reload_duration));
+        let (remaining, total) = reload.unwrap_or((self.cooldown, self.cooldown_duration));
+        if total.is_zero() {
+            1.
+        } else {
+            1. - remaining.div_duration_f32(total).min(1.)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Magazine, WeaponState};
+    use std::time::Duration;
+
+    #[test]
+    fn cooldown_and_reload() {
+        let magazine = Magazine {
+            size: 2,
+            reload_duration: Duration::from_secs(2),
+            reserve: Some(3),
+        };
+        let mut state = WeaponState::new(Some(&magazine));
+        assert!(state.ready());
+
+        state.start_cooldown(Duration::from_secs(1));
+        state.consume_round(Some(&magazine));
+        assert!(!state.ready());
+        state.proceed(Duration::from_millis(500), Some(&magazine));
+        assert_eq!(state.progress(Some(&magazine)), 0.5);
+        state.proceed(Duration::from_millis(500), Some(&magazine));
+        assert!(state.ready());
+        assert_eq!(state.ammo.unwrap().loaded, 1);
+
+        assert!(state.start_reload(&magazine));
+        assert!(!state.ready());
+        state.proceed(Duration::from_millis(500), Some(&magazine));
+        assert_eq!(state.progress(Some(&magazine)), 0.25);
+        state.proceed(Duration::from_secs(2), Some(&magazine));
+        assert!(state.ready());
+        assert_eq!(state.ammo.unwrap().loaded, 2);
+        assert_eq!(state.ammo.unwrap().reserve, Some(2));
+        assert!(!state.start_reload(&magazine));
+
+        state.consume_round(Some(&magazine));
+        state.consume_round(Some(&magazine));
+        assert!(!state.ready());
+        state.proceed(Duration::from_secs(2), Some(&magazine));
+        assert_eq!(state.ammo.unwrap().loaded, 2);
+        assert_eq!(state.ammo.unwrap().reserve, Some(0));
+
+        state.consume_round(Some(&magazine));
+        state.consume_round(Some(&magazine));
+        assert!(!state.start_reload(&magazine));
+        assert!(!state.ready());
+
+        let mut state = WeaponState::new(None);
+        state.consume_round(None);
+        assert!(state.ready());
+        assert_eq!(state.progress(None), 1.);
+    }
+
+    #[test]
+    fn fit_magazine() {
+        let magazine = Magazine {
+            size: 4,
+            reload_duration: Duration::from_secs(2),
+            reserve: Some(8),
+        };
+        let mut state = WeaponState::new(Some(&magazine));
+        state.consume_round(Some(&magazine));
+        state.start_cooldown(Duration::from_secs(1));
+        assert!(state.start_reload(&magazine));
+
+        // Same magazine changes nothing, ammo is not refilled
+        let before = state;
+        state.fit_magazine(Some(&magazine));
+        assert_eq!(state, before);
+
+        let smaller = Magazine {
+            size: 2,
+            reload_duration: Duration::from_secs(1),
+            reserve: Some(4),
+        };
+        state.fit_magazine(Some(&smaller));
+        assert_eq!(state.cooldown, Duration::from_secs(1));
+        let ammo = state.ammo.unwrap();
+        assert_eq!(ammo.loaded, 2);
+        assert_eq!(ammo.reserve, Some(4));
+        assert_eq!(ammo.reload_remaining, Some(Duration::from_secs(1)));
+
+        state.fit_magazine(None);
+        assert!(state.ammo.is_none());
+        assert_eq!(state.cooldown, Duration::from_secs(1));
+    }
+}
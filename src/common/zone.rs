@@ -0,0 +1,230 @@
+use super::{Point, Rect, Team};
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use std::{num::NonZero, time::Duration};
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+pub enum ZoneShape {
+    Circle { radius: f32 },
+    Rect { w: f32, h: f32 },
+}
+
+impl ZoneShape {
+    /// Half of zone size along x and y axes
+    fn half_extent(self) -> (f32, f32) {
+        match self {
+            ZoneShape::Circle { radius } => (radius, radius),
+            ZoneShape::Rect { w, h } => (w / 2., h / 2.),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct KingOfTheHillConfig {
+    pub shape: ZoneShape,
+    /// Zone moves to a random place after this interval
+    pub relocation_interval: Duration,
+    /// Time which zone has to be held uncontested before it awards points
+    pub capture_duration: Duration,
+    pub points_per_second: f32,
+}
+
+impl Default for KingOfTheHillConfig {
+    fn default() -> Self {
+        Self {
+            shape: ZoneShape::Circle { radius: 64. },
+            relocation_interval: Duration::from_secs(60),
+            capture_duration: Duration::from_secs(3),
+            points_per_second: 1.,
+        }
+    }
+}
+
+impl KingOfTheHillConfig {
+    pub fn validate(&self) -> Result<(), String> {
+        let (half_w, half_h) = self.shape.half_extent();
+        if !(half_w.is_finite() && half_w > 0. && half_h.is_finite() && half_h > 0.) {
+            return Err("`shape` must have positive size".to_string());
+        }
+        if self.relocation_interval.is_zero() {
+            return Err("`relocation_interval` must not be zero".to_string());
+        }
+        if !(self.points_per_second.is_finite() && self.points_per_second > 0.) {
+            return Err("`points_per_second` must be positive".to_string());
+        }
+        Ok(())
+    }
+}
+
+/// Team in team modes or player otherwise
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+pub enum ZoneHolder {
+    Team(Team),
+    Player(NonZero<u64>),
+}
+
+#[derive(Debug, Default, PartialEq)]
+pub struct ZoneUpdate {
+    /// Whole points earned by holder during this step
+    pub points: u32,
+    pub captured: bool,
+    pub relocate: bool,
+}
+
+/// King of the hill zone. Part of game state so it is broadcasted to clients with it
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Zone {
+    pub center: Point,
+    pub shape: ZoneShape,
+    pub relocation_remaining: Duration,
+    /// Last uncontested occupant
+    pub holder: Option<ZoneHolder>,
+    /// Zone awards points to holder when progress reaches 1
+    pub capture_progress: f32,
+    pub contested: bool,
+    /// Fraction of a point earned but not awarded yet
+    points_progress: f32,
+}
+
+impl Zone {
+    /// Creates zone at random place where it fits into `bounds`
+    pub fn new<R: Rng>(config: &KingOfTheHillConfig, bounds: Rect, rng: &mut R) -> Self {
+        let (half_w, half_h) = config.shape.half_extent();
+        let random = |rng: &mut R, from: f32, len: f32, half: f32| {
+            if len > half * 2. {
+                rng.random_range((from + half)..(from + len - half))
+            } else {
+                from + len / 2.
+            }
+        };
+        Self {
+            center: Point {
+                x: random(rng, bounds.x, bounds.w, half_w),
+                y: random(rng, bounds.y, bounds.h, half_h),
+            },
+            shape: config.shape,
+            relocation_remaining: config.relocation_interval,
+            holder: None,
+            capture_progress: 0.,
+            contested: false,
+            points_progress: 0.,
+        }
+    }
+
+    /// Whether circle with center `pos` overlaps zone
+    pub fn contains(&self, pos: Point, radius: f32) -> bool {
+        match self.shape {
+            ZoneShape::Circle {
+                radius: zone_radius,
+            } => (pos - self.center).len() < zone_radius + radius,
+            ZoneShape::Rect { w, h } => {
+                (pos.x - self.center.x).abs() < w / 2. + radius
+                    && (pos.y - self.center.y).abs() < h / 2. + radius
+            }
+        }
+    }
+
+    /// Advances capture by occupants of the zone which may repeat
+    pub fn proceed(
+        &mut self,
+        dt: Duration,
+        config: &KingOfTheHillConfig,
+        occupants: &[ZoneHolder],
+    ) -> ZoneUpdate {
+        let mut update: ZoneUpdate = Default::default();
+
+        self.relocation_remaining = self.relocation_remaining.saturating_sub(dt);
+        update.relocate = self.relocation_remaining.is_zero();
+
+        let occupant = occupants.first().copied();
+        self.contested = occupants.iter().any(|x| Some(*x) != occupant);
+        if self.contested {
+            return update;
+        }
+        let Some(occupant) = occupant else {
+            return update;
+        };
+
+        if self.holder != Some(occupant) {
+            self.holder = Some(occupant);
+            self.capture_progress = 0.;
+            self.points_progress = 0.;
+        }
+
+        if self.capture_progress < 1. {
+            self.capture_progress = if config.capture_duration.is_zero() {
+                1.
+            } else {
+                (self.capture_progress + dt.div_duration_f32(config.capture_duration)).min(1.)
+            };
+            update.captured = self.capture_progress == 1.;
+        } else {
+            self.points_progress += config.points_per_second * dt.as_secs_f32();
+            update.points = self.points_progress as u32;
+            self.points_progress -= update.points as f32;
+        }
+
+        update
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{KingOfTheHillConfig, Zone, ZoneHolder, ZoneShape, ZoneUpdate};
+    use crate::common::{Rect, Team, Vector};
+    use rand::rng;
+    use std::{num::NonZero, time::Duration};
+
+    #[test]
+    fn capture_and_points() {
+        let config = KingOfTheHillConfig {
+            shape: ZoneShape::Rect { w: 100., h: 50. },
+            relocation_interval: Duration::from_secs(10),
+            capture_duration: Duration::from_secs(2),
+            points_per_second: 2.,
+        };
+        let bounds = Rect {
+            x: 0.,
+            y: 0.,
+            w: 400.,
+            h: 400.,
+        };
+        let dt = Duration::from_secs(1);
+        let red = ZoneHolder::Team(Team::Red);
+        let blue = ZoneHolder::Team(Team::Blue);
+        let mut zone = Zone::new(&config, bounds, &mut rng());
+
+        assert!(zone.contains(zone.center + Vector { x: 55., y: 0. }, 8.));
+        assert!(!zone.contains(zone.center + Vector { x: 0., y: 40. }, 8.));
+
+        assert_eq!(zone.proceed(dt, &config, &[red, red]), Default::default());
+        assert_eq!(zone.capture_progress, 0.5);
+
+        let contested = zone.proceed(dt, &config, &[red, blue]);
+        assert_eq!(contested.points, 0);
+        assert!(zone.contested);
+        assert_eq!(zone.capture_progress, 0.5);
+
+        assert!(zone.proceed(dt, &config, &[red]).captured);
+        assert_eq!(zone.proceed(dt, &config, &[red]).points, 2);
+        assert_eq!(zone.proceed(dt, &config, &[]).points, 0);
+
+        let player = ZoneHolder::Player(NonZero::new(1).unwrap());
+        zone.proceed(dt, &config, &[player]);
+        assert_eq!(zone.holder, Some(player));
+        assert_eq!(zone.capture_progress, 0.5);
+
+        let update = (0..4)
+            .map(|_| zone.proceed(dt, &config, &[]))
+            .last()
+            .unwrap();
+        assert_eq!(
+            update,
+            ZoneUpdate {
+                points: 0,
+                captured: false,
+                relocate: true
+            }
+        );
+    }
+}
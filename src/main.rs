@@ -1,42 +1,82 @@
-#![feature(thread_id_value)]
-#![feature(duration_millis_float)]
-
-use std::net::SocketAddrV4;
+use std::{net::SocketAddrV4, path::PathBuf, time::Duration};
 
 use clap::Parser;
-use client::exec_client;
-use server::exec_server;
-
-mod client;
-mod common;
-mod server;
+#[cfg(feature = "sdl")]
+use fast_pased_mp_game::client::exec_client;
+use fast_pased_mp_game::{
+    client::{exec_bench_clients, BenchScript},
+    server::exec_server,
+};
 
 #[derive(Parser)]
 #[command(version, about, long_about = None)]
 enum Args {
     Server(ServerCommand),
+    #[cfg(feature = "sdl")]
     Client(ClientCommand),
+    /// Connects headless players which send scripted input and reports network measurements
+    BenchClients(BenchClientsCommand),
 }
 
 #[derive(Parser)]
 struct ServerCommand {
     #[arg(short, long)]
     port: u16,
+    /// Gameplay config file. It is watched and reloaded when changed
+    #[arg(short, long)]
+    config: Option<PathBuf>,
+    /// Map file with world bounds, walls and spawn points
+    #[arg(short, long)]
+    map: Option<PathBuf>,
+    /// Number of bot players started with the server
+    #[arg(short, long, default_value_t = 0)]
+    bots: usize,
 }
 
+#[cfg(feature = "sdl")]
 #[derive(Parser)]
 struct ClientCommand {
     #[arg(short, long)]
     address: SocketAddrV4,
+    /// Player name. Can be changed on join screen
+    #[arg(short, long)]
+    name: Option<String>,
+}
+
+#[derive(Parser)]
+struct BenchClientsCommand {
+    #[arg(short, long)]
+    address: SocketAddrV4,
+    /// Number of connections
+    #[arg(short = 'n', long, default_value_t = 10)]
+    clients: usize,
+    /// Benchmark duration in seconds
+    #[arg(short, long, default_value_t = 30)]
+    duration: u64,
+    #[arg(short, long, value_enum, default_value_t = BenchScript::Random)]
+    script: BenchScript,
+    /// Print summary as JSON instead of a table
+    #[arg(long)]
+    json: bool,
 }
 
 pub fn main() {
     match Args::parse() {
         Args::Server(command) => {
-            exec_server(command.port);
+            exec_server(command.port, command.config, command.map, command.bots);
         }
+        #[cfg(feature = "sdl")]
         Args::Client(command) => {
-            exec_client(command.address).unwrap();
+            exec_client(command.address, command.name).unwrap();
+        }
+        Args::BenchClients(command) => {
+            exec_bench_clients(
+                command.address,
+                command.clients,
+                Duration::from_secs(command.duration),
+                command.script,
+                command.json,
+            );
         }
     }
 }
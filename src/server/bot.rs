@@ -0,0 +1,116 @@
+use crate::common::{
+    Bot, ClientToServerPackage, Color, GameConfig, GameState, Map, PacketReader, PacketWriter,
+    PlayerConnectedPackage, PlayerInputPackage, RespawnRequestPackage, ServerToClientPackage,
+    MOVEMENT_STEP,
+};
+use rand::{rng, Rng};
+use std::{
+    net::{SocketAddrV4, TcpStream},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant},
+};
+
+/// Time bot waits after death before it asks to respawn
+const BOT_RESPAWN_DELAY: Duration = Duration::from_secs(2);
+
+/// Connects bot to the local server as a regular client and plays until connection is closed
+/// or `stop` is set. Bot receives the same packages as players do and sends input each movement
+/// step
+pub(crate) fn exec_bot(addr: SocketAddrV4, name: String, stop: &AtomicBool) -> std::io::Result<()> {
+    let mut stream = TcpStream::connect(addr)?;
+    stream.set_nonblocking(true)?;
+    let mut reader: PacketReader = Default::default();
+    let mut rng = rng();
+
+    let write_package = |stream: &mut TcpStream, p: ClientToServerPackage| -> std::io::Result<()> {
+        PacketWriter::write(stream, &serde_json::to_vec(&p).unwrap())
+    };
+
+    let mut bot: Bot = Default::default();
+    let mut player_id = None;
+    let mut map: Arc<Map> = Default::default();
+    let mut game_state: Option<GameState> = None;
+    let mut config: GameConfig = Default::default();
+    let mut killed_instant: Option<Instant> = None;
+    let mut sequence_number = 0;
+    let mut last_step_instant = Instant::now();
+
+    while !stop.load(Ordering::Relaxed) {
+        for data in reader.read(&mut stream)? {
+            let package: ServerToClientPackage = serde_json::from_slice(&data).unwrap();
+            match package {
+                ServerToClientPackage::Init(init_package) => {
+                    player_id = Some(init_package.player_id);
+                    write_package(
+                        &mut stream,
+                        ClientToServerPackage::PlayerConnected(PlayerConnectedPackage {
+                            name: name.clone(),
+                            color: Color {
+                                a: 255,
+                                r: rng.random(),
+                                g: rng.random(),
+                                b: rng.random(),
+                            },
+                            team: None,
+                        }),
+                    )?;
+                }
+                ServerToClientPackage::Broadcast(broadcast_package) => {
+                    let mut received = broadcast_package.game_state;
+                    received.set_map(map.clone());
+                    game_state = Some(received);
+                }
+                ServerToClientPackage::Kill(_) => {
+                    killed_instant = Some(Instant::now());
+                }
+                ServerToClientPackage::Config(config_package) => {
+                    config = config_package.config;
+                }
+                ServerToClientPackage::Event(_) => {}
+                ServerToClientPackage::JoinRejected(join_rejected_package) => {
+                    return Err(std::io::Error::other(join_rejected_package.reason));
+                }
+                ServerToClientPackage::Map(map_package) => {
+                    map = Arc::new(map_package.map);
+                }
+            }
+        }
+
+        let now = Instant::now();
+        if killed_instant.is_some_and(|x| now - x > BOT_RESPAWN_DELAY) {
+            killed_instant = None;
+            write_package(
+                &mut stream,
+                ClientToServerPackage::RespawnRequest(RespawnRequestPackage {
+                    loadout: Bot::choose_loadout(&mut rng),
+                }),
+            )?;
+        }
+
+        if now - last_step_instant >= MOVEMENT_STEP {
+            last_step_instant = now;
+            if let (Some(player_id), Some(game_state)) = (player_id, &game_state) {
+                if let Some(input) =
+                    bot.think(game_state, player_id, MOVEMENT_STEP, &config.bots, &mut rng)
+                {
+                    sequence_number += 1;
+                    write_package(
+                        &mut stream,
+                        ClientToServerPackage::PlayerInput(PlayerInputPackage {
+                            sequence_number,
+                            movement: input.movement,
+                            rotation: input.rotation,
+                            buttons: input.buttons,
+                        }),
+                    )?;
+                }
+            }
+        }
+
+        std::thread::sleep(Duration::from_millis(1));
+    }
+    Ok(())
+}
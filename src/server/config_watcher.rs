@@ -0,0 +1,61 @@
+use crate::common::{GameConfig, Map};
+use std::{
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
+
+pub fn load_game_config(path: &Path) -> Result<GameConfig, String> {
+    let data = std::fs::read(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+    let config: GameConfig =
+        serde_json::from_slice(&data).map_err(|e| format!("{}: {}", path.display(), e))?;
+    config
+        .validate()
+        .map_err(|e| format!("{}: {}", path.display(), e))?;
+    Ok(config)
+}
+
+pub fn load_map(path: &Path) -> Result<Map, String> {
+    let data = std::fs::read(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+    let map: Map =
+        serde_json::from_slice(&data).map_err(|e| format!("{}: {}", path.display(), e))?;
+    map.validate()
+        .map_err(|e| format!("{}: {}", path.display(), e))?;
+    Ok(map)
+}
+
+/// Polls config file modification time and reloads it when it is changed
+pub(crate) struct ConfigWatcher {
+    path: PathBuf,
+    last_modified: Option<SystemTime>,
+}
+
+impl ConfigWatcher {
+    pub(crate) fn new(path: PathBuf) -> Self {
+        let last_modified = Self::modified(&path);
+        Self {
+            path,
+            last_modified,
+        }
+    }
+
+    fn modified(path: &Path) -> Option<SystemTime> {
+        std::fs::metadata(path).and_then(|m| m.modified()).ok()
+    }
+
+    /// Returns new config if file is changed since previous call. Invalid configs are reported as errors
+    pub(crate) fn poll(&mut self) -> Option<Result<GameConfig, String>> {
+        let modified = Self::modified(&self.path);
+        if modified.is_none() || modified == self.last_modified {
+            return None;
+        }
+        self.last_modified = modified;
+        Some(load_game_config(&self.path))
+    }
+}
+
+/// Current config with a version incremented on each successful reload
+#[derive(Debug, Clone)]
+pub(crate) struct VersionedConfig {
+    pub(crate) version: u32,
+    pub(crate) config: GameConfig,
+}
@@ -1,2 +1,6 @@
 mod server;
-pub(crate) use server::*;
+pub use server::*;
+mod config_watcher;
+pub use config_watcher::*;
+mod bot;
+pub(crate) use bot::*;
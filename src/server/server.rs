@@ -1,400 +1,968 @@
+use super::{exec_bot, load_game_config, load_map, ConfigWatcher, VersionedConfig};
 use crate::common::{
-    BroadcastPackage, CharacterWeapon, ClientToServerPackage, Collide as _, Complex,
-    EntityCreateInfo, EntityRole, EntityTail, GameState, InitPackage, KillPackage, PacketReader,
-    PacketWriter, PlayerState, PlayerWeapon, Point, ProjectileKind, Segments as _,
-    ServerToClientPackage, Shield,
+    BroadcastPackage, Buff, CharacterWeapon, ClientToServerPackage, Complex, ConfigPackage,
+    EntityCreateInfo, EntityRole, EntityTail, EventPackage, GameState, InitPackage, InputButtons,
+    JoinRejectedPackage, KillPackage, Loadout, MapPackage, PacketReader, PacketWriter, PlayerInfo,
+    PlayerState, PlayerWeapon, ProjectileKind, SecondaryAction, ServerToClientPackage,
+    WeaponUpdatePolicy,
 };
-use rand::rng;
+use rand::{rngs::StdRng, Rng, SeedableRng};
 use std::{
     f32::consts::PI,
-    net::{Ipv4Addr, SocketAddrV4, TcpListener, TcpStream},
-    sync::{Arc, Mutex},
-    thread,
+    io::ErrorKind,
+    net::{Ipv4Addr, SocketAddr, SocketAddrV4, TcpListener, TcpStream},
+    num::NonZero,
+    path::PathBuf,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
+    thread::{self, JoinHandle},
     time::{Duration, Instant},
 };
 
-fn character_weapon_from_player_weapon(weapon: PlayerWeapon) -> CharacterWeapon {
-    match weapon {
-        PlayerWeapon::BallGun => CharacterWeapon::BallGun {
-            life_duration: Duration::from_secs(60),
-            owner_invincibility_duration: Duration::from_millis(200),
-            fire_interval: Duration::from_millis(1000 / 10),
-            velocity: 200.,
-            projectile_health: 1,
-            radius: 4.,
-        },
-        PlayerWeapon::PulseGun => CharacterWeapon::RayGun {
-            life_duration: Duration::from_millis(4000),
-            owner_invincibility_duration: Duration::from_millis(500),
-            tail_freeze_duration: Duration::from_millis(100),
-            fire_interval: Duration::from_millis(1000),
-            velocity: 1000.,
-            projectile_health: 1,
-        },
-        PlayerWeapon::RayGun => CharacterWeapon::RayGun {
-            life_duration: Duration::from_millis(1000),
-            owner_invincibility_duration: Duration::from_millis(500),
-            tail_freeze_duration: Duration::from_millis(1000),
-            fire_interval: Duration::from_millis(2000),
-            velocity: 2000.,
-            projectile_health: 16,
-        },
-        PlayerWeapon::Shield => CharacterWeapon::Shield {
-            shield: Shield {
-                width: 48.,
-                dst_from_character: 32.,
+/// Parameters of a server started by [`Server::start`]
+#[derive(Debug, Clone, Default)]
+pub struct ServerOptions {
+    /// Port to listen on. With 0 a free port is chosen, see [`Server::local_addr`]
+    pub port: u16,
+    /// Gameplay config file. It is watched and reloaded when changed
+    pub config_path: Option<PathBuf>,
+    /// Map file with world bounds, walls and spawn points
+    pub map_path: Option<PathBuf>,
+    /// Number of bot players started with the server
+    pub bots: usize,
+    pub clock: ServerClock,
+    /// Seed of game randomness such as spawn points and pickups. Random when not set
+    pub seed: Option<u64>,
+}
+
+/// Time source of game state simulation
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ServerClock {
+    /// Game state proceeds in a background thread each tick
+    #[default]
+    RealTime,
+    /// Game state proceeds only in [`Server::advance`]. Lets tests control time
+    Manual,
+}
+
+/// Interval between game state updates with real time clock
+const TICK_DURATION: Duration = Duration::from_millis(1000 / 30);
+
+/// Game server running in background threads. Stopped when dropped
+pub struct Server {
+    addr: SocketAddrV4,
+    clock: ServerClock,
+    game_state: Arc<Mutex<GameState>>,
+    config: Arc<Mutex<VersionedConfig>>,
+    /// Polled in [`Server::advance`]. Owned by tick thread with real time clock
+    config_watcher: Option<ConfigWatcher>,
+    stop: Arc<AtomicBool>,
+    threads: Vec<JoinHandle<()>>,
+    /// Used in [`Server::advance`]. Threads use generators seeded from it
+    rng: StdRng,
+}
+
+impl Server {
+    pub fn start(options: ServerOptions) -> Result<Server, String> {
+        let mut game_state = GameState::new();
+        if let Some(path) = &options.map_path {
+            game_state.set_map(Arc::new(load_map(path)?));
+        }
+        let game_state = Arc::new(Mutex::new(game_state));
+        let config = Arc::new(Mutex::new(VersionedConfig {
+            version: 0,
+            config: match &options.config_path {
+                Some(path) => load_game_config(path)?,
+                None => Default::default(),
             },
-            self_destruct_timeout: Duration::from_secs(2),
-        },
-        PlayerWeapon::MineGun => CharacterWeapon::MineGun {
-            fire_interval: Duration::from_secs(8),
-            life_duration: Duration::from_secs(32),
-            owner_invincibility_duration: Duration::from_secs(1000),
-            activation_duration: Duration::from_secs(2),
-            start_velocity: 500.,
-            acceleration: -100.,
-            radius: 6.,
-            detection_radius: 200.,
-            explosion_radius: 100.,
-            debris_kind: Box::new(ProjectileKind::Mine {
-                life_duration: Duration::from_secs(16),
-                owner_invincibility_duration: Duration::from_secs(2),
-                activation_duration: Duration::from_secs(1),
-                velocity: 500.,
-                acceleration: -100.,
-                radius: 4.,
-                detection_radius: 100.,
-                explosion_radius: 50.,
-                debris_kind: Box::new(ProjectileKind::Ray {
-                    life_duration: Duration::from_millis(4000),
-                    owner_invincibility_duration: Duration::from_millis(500),
-                    tail_freeze_duration: Duration::from_millis(100),
-                    velocity: 200.,
-                    health: 1,
-                }),
-                debris_count: 12,
-            }),
-            debris_count: 6,
-        },
+        }));
+        let mut config_watcher = options.config_path.map(ConfigWatcher::new);
+        let listener =
+            TcpListener::bind(SocketAddrV4::new(Ipv4Addr::new(127, 0, 0, 1), options.port))
+                .map_err(|e| format!("port {}: {}", options.port, e))?;
+        let addr = match listener.local_addr().map_err(|e| e.to_string())? {
+            SocketAddr::V4(addr) => addr,
+            SocketAddr::V6(_) => unreachable!(),
+        };
+        listener.set_nonblocking(true).map_err(|e| e.to_string())?;
+        println!("listening started, ready to accept");
+
+        let stop = Arc::new(AtomicBool::new(false));
+        let mut threads = vec![];
+        let mut rng = match options.seed {
+            Some(seed) => StdRng::seed_from_u64(seed),
+            None => StdRng::from_os_rng(),
+        };
+
+        if options.clock == ServerClock::RealTime {
+            let mut last_proceed_instant = Instant::now();
+            let mut config_watcher = config_watcher.take();
+            let game_state = game_state.clone();
+            let config = config.clone();
+            let stop = stop.clone();
+            let mut rng = StdRng::from_rng(&mut rng);
+            threads.push(thread::spawn(move || {
+                while !stop.load(Ordering::Relaxed) {
+                    let now = Instant::now();
+                    proceed_game(
+                        &game_state,
+                        &config,
+                        config_watcher.as_mut(),
+                        now - last_proceed_instant,
+                        &mut rng,
+                    );
+                    let proceed_duration = Instant::now() - now;
+
+                    last_proceed_instant = now;
+                    std::thread::sleep(TICK_DURATION.saturating_sub(proceed_duration));
+                }
+            }));
+        }
+
+        {
+            let game_state = game_state.clone();
+            let config = config.clone();
+            let stop = stop.clone();
+            let mut rng = StdRng::from_rng(&mut rng);
+            threads.push(thread::spawn(move || {
+                let mut players = vec![];
+                // Ids are not reused while server runs. `WORLD_PLAYER_ID` is never reached
+                let mut next_player_id = NonZero::<u64>::MIN;
+                while !stop.load(Ordering::Relaxed) {
+                    let stream = match listener.accept() {
+                        Ok((stream, _)) => stream,
+                        Err(err) if err.kind() == ErrorKind::WouldBlock => {
+                            std::thread::sleep(Duration::from_millis(1));
+                            continue;
+                        }
+                        Err(err) => {
+                            println!("Accept failed: {}", err);
+                            continue;
+                        }
+                    };
+                    let player_id = next_player_id;
+                    next_player_id = next_player_id.checked_add(1).unwrap();
+                    let game_state = game_state.clone();
+                    let config = config.clone();
+                    let stop = stop.clone();
+                    let mut rng = StdRng::from_rng(&mut rng);
+                    players.push(thread::spawn(move || {
+                        if let Err(err) = serve_player(
+                            stream,
+                            player_id,
+                            game_state.clone(),
+                            config,
+                            &stop,
+                            &mut rng,
+                        ) {
+                            println!("Player disconnected: {} ({})", player_id, err);
+                        }
+                        game_state.lock().unwrap().remove_player(player_id);
+                    }));
+                    players.retain(|x| !x.is_finished());
+                }
+                for x in players {
+                    if x.join().is_err() {
+                        println!("Player thread panicked");
+                    }
+                }
+            }));
+        }
+
+        for i in 0..options.bots {
+            let stop = stop.clone();
+            threads.push(thread::spawn(move || {
+                if let Err(err) = exec_bot(addr, format!("Bot {}", i + 1), &stop) {
+                    println!("Bot stopped: {} ({})", i + 1, err);
+                }
+            }));
+        }
+
+        Ok(Server {
+            addr,
+            clock: options.clock,
+            game_state,
+            config,
+            config_watcher,
+            stop,
+            threads,
+            rng,
+        })
+    }
+
+    /// Proceeds game state by `dt` split into ticks. Server must be started with manual clock
+    pub fn advance(&mut self, dt: Duration) {
+        assert_eq!(self.clock, ServerClock::Manual);
+        let mut remaining = dt;
+        while !remaining.is_zero() {
+            let step = remaining.min(TICK_DURATION);
+            proceed_game(
+                &self.game_state,
+                &self.config,
+                self.config_watcher.as_mut(),
+                step,
+                &mut self.rng,
+            );
+            remaining -= step;
+        }
+    }
+
+    /// Address clients connect to
+    pub fn local_addr(&self) -> SocketAddrV4 {
+        self.addr
+    }
+
+    /// Disconnects all players and waits until server threads are finished
+    pub fn stop(mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+        self.join_threads();
+    }
+
+    /// Blocks until server threads are finished. They are never stopped otherwise, so server
+    /// command runs until process is terminated
+    pub fn join(mut self) {
+        self.join_threads();
+    }
+
+    fn join_threads(&mut self) {
+        for x in std::mem::take(&mut self.threads) {
+            if x.join().is_err() {
+                println!("Server thread panicked");
+            }
+        }
     }
 }
 
-pub(crate) fn exec_server(port: u16) {
-    let game_state = Arc::new(Mutex::new(GameState::new()));
-    let listener = TcpListener::bind(SocketAddrV4::new(Ipv4Addr::new(127, 0, 0, 1), port)).unwrap();
-    println!("listening started, ready to accept");
+impl Drop for Server {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+        self.join_threads();
+    }
+}
 
-    {
-        let mut last_proceed_instant = Instant::now();
-        let game_state = game_state.clone();
-        thread::spawn(move || loop {
-            let now = Instant::now();
-            {
-                game_state
-                    .lock()
-                    .unwrap()
-                    .proceed(now - last_proceed_instant);
+fn proceed_game<R: Rng>(
+    game_state: &Mutex<GameState>,
+    config: &Mutex<VersionedConfig>,
+    config_watcher: Option<&mut ConfigWatcher>,
+    dt: Duration,
+    rng: &mut R,
+) {
+    if let Some(new_config) = config_watcher.and_then(|w| w.poll()) {
+        match new_config {
+            Ok(new_config) => {
+                let mut config = config.lock().unwrap();
+                if config.config != new_config {
+                    config.version += 1;
+                    config.config = new_config;
+                    println!("Config reloaded (version {})", config.version);
+                }
             }
-            let proceed_duration = Instant::now() - now;
+            Err(err) => println!("Config is not reloaded: {}", err),
+        }
+    }
+    let game_config = config.lock().unwrap().config.clone();
+    let mut game_state = game_state.lock().unwrap();
+    game_state.proceed(dt, &game_config, rng);
+    game_state.proceed_match(dt, &game_config, rng);
+}
+
+pub fn exec_server(
+    port: u16,
+    config_path: Option<PathBuf>,
+    map_path: Option<PathBuf>,
+    bots: usize,
+) {
+    Server::start(ServerOptions {
+        port,
+        config_path,
+        map_path,
+        bots,
+        clock: ServerClock::RealTime,
+        seed: None,
+    })
+    .unwrap()
+    .join();
+}
+
+fn serve_player<R: Rng>(
+    mut stream: TcpStream,
+    player_id: NonZero<u64>,
+    game_state: Arc<Mutex<GameState>>,
+    config: Arc<Mutex<VersionedConfig>>,
+    stop: &AtomicBool,
+    rng: &mut R,
+) -> std::io::Result<()> {
+    let mut player_state: PlayerState = Default::default();
+    let mut buttons: InputButtons = Default::default();
+    // Game state time, so holding is measured with server clock
+    let mut secondary_pressed_time = Duration::ZERO;
+
+    stream.set_nonblocking(true).unwrap();
+    let mut reader: PacketReader = Default::default();
+
+    let write_package = |stream: &mut TcpStream, p: ServerToClientPackage| -> std::io::Result<()> {
+        PacketWriter::write(stream, &serde_json::to_vec(&p).unwrap())
+    };
+
+    write_package(
+        &mut stream,
+        ServerToClientPackage::Init(InitPackage { player_id }),
+    )?;
+
+    let map = game_state.lock().unwrap().map().clone();
+    write_package(
+        &mut stream,
+        ServerToClientPackage::Map(MapPackage {
+            map: map.as_ref().clone(),
+        }),
+    )?;
 
-            last_proceed_instant = now;
-            std::thread::sleep(Duration::from_millis(1000 / 30) - proceed_duration);
-        });
+    let (mut config_version, game_config) = {
+        let config = config.lock().unwrap();
+        (config.version, config.config.clone())
+    };
+
+    write_package(
+        &mut stream,
+        ServerToClientPackage::Config(Box::new(ConfigPackage {
+            config: game_config.clone(),
+        })),
+    )?;
+
+    let mut loadout = Loadout::new(&[PlayerWeapon::BallGun], &game_config);
+    let mut weapon = game_config.character_weapon(loadout.active());
+    player_state.loadout = loadout.weapons();
+
+    loop {
+        if stop.load(Ordering::Relaxed) {
+            return Ok(());
+        }
+        if let Some(data) = reader.read(&mut stream)?.next() {
+            let package: ClientToServerPackage = serde_json::from_slice(&data)?;
+            match package {
+                ClientToServerPackage::PlayerConnected(player_connected_package) => {
+                    let mut game_state = game_state.lock().unwrap();
+                    if let Err(reason) = game_state
+                        .players()
+                        .validate_name(&player_connected_package.name)
+                    {
+                        drop(game_state);
+                        println!("Player rejected: {} ({})", player_id, reason);
+                        write_package(
+                            &mut stream,
+                            ServerToClientPackage::JoinRejected(JoinRejectedPackage { reason }),
+                        )?;
+                        return Ok(());
+                    }
+
+                    println!(
+                        "Player connected: {} ({})",
+                        player_id, player_connected_package.name
+                    );
+                    let team = if game_config.mode.has_teams() {
+                        Some(
+                            player_connected_package
+                                .team
+                                .unwrap_or_else(|| game_state.players().smallest_team()),
+                        )
+                    } else {
+                        None
+                    };
+                    player_state.color = match team {
+                        Some(team) => team.color(),
+                        None => player_connected_package.color,
+                    };
+
+                    game_state.add_player(PlayerInfo {
+                        player_id,
+                        name: player_connected_package.name,
+                        color: player_state.color.clone(),
+                        team,
+                    });
+                    let pos = game_state.spawn_point(player_id, rng);
+                    game_state.create(
+                        EntityCreateInfo {
+                            pos,
+                            rot: Complex { r: 1., i: 0. },
+                            color: player_state.color.clone(),
+                            role: EntityRole::Character { weapon },
+                            player_weapon: loadout.active(),
+                            tail: None,
+                        },
+                        player_id,
+                        &game_config.health,
+                    );
+                }
+                ClientToServerPackage::RespawnRequest(_)
+                | ClientToServerPackage::PlayerInput(_)
+                | ClientToServerPackage::SwitchWeapon(_) => {
+                    return Err(std::io::Error::other("first package must be init package"));
+                }
+            }
+            break;
+        }
+        std::thread::sleep(Duration::from_millis(1));
     }
 
-    for stream in listener.incoming() {
-        let game_state = game_state.clone();
-        thread::spawn(move || -> std::io::Result<()> {
-            let player_id = std::thread::current().id().as_u64();
-            let mut player_state: PlayerState = Default::default();
-            let mut left_mouse_pressed: bool = false;
-            let mut left_mouse_pressed_instant: Instant = Instant::now();
-
-            let mut stream = stream.unwrap();
-            stream.set_nonblocking(true).unwrap();
-            let mut reader: PacketReader = Default::default();
-            let mut rng = rng();
-
-            let write_package =
-                |stream: &mut TcpStream, p: ServerToClientPackage| -> std::io::Result<()> {
-                    PacketWriter::write(stream, &serde_json::to_vec(&p).unwrap())
-                };
+    let mut last_broadcust_instant = Instant::now();
+    let mut last_sequence_number = 0;
+    let mut next_event_id = game_state.lock().unwrap().events().next_id();
 
-            write_package(
-                &mut stream,
-                ServerToClientPackage::Init(InitPackage { player_id }),
-            )?;
+    while !stop.load(Ordering::Relaxed) {
+        let now = Instant::now();
+        let mut secondary_pressed = false;
+        // Time secondary button was held before it was released
+        let mut secondary_released: Option<Duration> = None;
+        for data in reader.read(&mut stream)? {
+            let package: ClientToServerPackage = serde_json::from_slice(&data)?;
+            match package {
+                ClientToServerPackage::PlayerConnected(_) => {
+                    return Err(std::io::Error::other("double init"));
+                }
+                ClientToServerPackage::PlayerInput(package) => {
+                    let game_state = game_state.lock().unwrap();
+                    if let Some(mut entity) = game_state.find_character_by_player_id_mut(player_id)
+                    {
+                        // Movement is stepped on server tick, so its speed does not depend on
+                        // how often input is sent
+                        entity.movement_input = package.movement;
+                        entity.dash_held = package.buttons.contains(InputButtons::ABILITY);
+                        entity.rot = package.rotation;
 
-            let mut weapon = character_weapon_from_player_weapon(PlayerWeapon::BallGun);
+                        last_sequence_number = package.sequence_number;
+                    };
 
-            loop {
-                if let Some(data) = reader.read(&mut stream)?.next() {
-                    let package: ClientToServerPackage = serde_json::from_slice(&data).unwrap();
-                    match package {
-                        ClientToServerPackage::PlayerConnected(player_connected_package) => {
-                            println!("Player connected: {}", player_id);
-                            player_state.color = player_connected_package.color;
+                    if package
+                        .buttons
+                        .pressed_since(buttons)
+                        .contains(InputButtons::SECONDARY)
+                    {
+                        secondary_pressed = true;
+                        secondary_pressed_time = game_state.time();
+                    }
+                    if buttons
+                        .pressed_since(package.buttons)
+                        .contains(InputButtons::SECONDARY)
+                    {
+                        secondary_released = Some(game_state.time() - secondary_pressed_time);
+                    }
 
-                            let mut game_state = game_state.lock().unwrap();
-                            let pos = game_state.random_point_inside_bounds(&mut rng);
+                    if package
+                        .buttons
+                        .pressed_since(buttons)
+                        .contains(InputButtons::RELOAD)
+                    {
+                        if let Some(mut entity) =
+                            game_state.find_character_by_player_id_mut(player_id)
+                        {
+                            if let EntityRole::Character { weapon } = &entity.role {
+                                if let Some(magazine) = weapon.magazine().copied() {
+                                    entity.weapon_state.start_reload(&magazine);
+                                }
+                            }
+                        }
+                    }
+
+                    buttons = package.buttons
+                }
+                ClientToServerPackage::RespawnRequest(package) => {
+                    if player_state.killed {
+                        player_state.killed = false;
+                        let mut game_state = game_state.lock().unwrap();
+
+                        let config = &config.lock().unwrap().config;
+                        loadout = Loadout::new(&package.loadout, config);
+                        weapon = config.character_weapon(loadout.active());
+                        player_state.loadout = loadout.weapons();
+
+                        let create_info = EntityCreateInfo {
+                            pos: game_state.spawn_point(player_id, rng),
+                            rot: Complex { r: 1., i: 0. },
+                            color: player_state.color.clone(),
+                            role: EntityRole::Character { weapon },
+                            player_weapon: loadout.active(),
+                            tail: None,
+                        };
+
+                        game_state.create(create_info, player_id, &config.health);
+                    }
+                }
+                ClientToServerPackage::SwitchWeapon(package) => {
+                    let game_state = game_state.lock().unwrap();
+                    if let Some(mut entity) = game_state.find_character_by_player_id_mut(player_id)
+                    {
+                        let config = &config.lock().unwrap().config;
+                        match loadout.switch(package.slot, entity.weapon_state, config) {
+                            Ok(weapon_state) => {
+                                weapon = config.character_weapon(loadout.active());
+                                entity.player_weapon = loadout.active();
+                                entity.role = EntityRole::Character {
+                                    weapon: weapon.clone(),
+                                };
+                                entity.weapon_state = weapon_state;
+                            }
+                            Err(reason) => {
+                                println!("Weapon switch rejected: {} ({})", player_id, reason)
+                            }
+                        }
+                    };
+                }
+            }
+        }
+
+        {
+            let config = config.lock().unwrap();
+            if config.version != config_version {
+                config_version = config.version;
+                let game_config = config.config.clone();
+                drop(config);
+
+                if game_config.weapon_update_policy == WeaponUpdatePolicy::Immediately {
+                    weapon = game_config.character_weapon(loadout.active());
+                    if let Some(mut character) = game_state
+                        .lock()
+                        .unwrap()
+                        .find_character_by_player_id_mut(player_id)
+                    {
+                        character.role = EntityRole::Character {
+                            weapon: weapon.clone(),
+                        };
+                        character.weapon_state.fit_magazine(weapon.magazine());
+                    }
+                }
+
+                write_package(
+                    &mut stream,
+                    ServerToClientPackage::Config(Box::new(ConfigPackage {
+                        config: game_config,
+                    })),
+                )?;
+            }
+        }
+
+        if buttons.contains(InputButtons::PRIMARY) {
+            let mut game_state = game_state.lock().unwrap();
+            if let Some(mut character) = game_state
+                .find_character_by_player_id_mut(player_id)
+                .map(|x| x.clone())
+                .filter(|x| {
+                    // Swapped weapon is ready for its only shot regardless of own weapon state
+                    (Buff::swapped_weapon(&x.buffs).is_some() || x.weapon_state.ready())
+                        && game_state.match_state().combat_allowed()
+                        && game_state.alive(x)
+                })
+            {
+                let magazine = match &character.role {
+                    EntityRole::Character { weapon } => weapon.magazine().copied(),
+                    EntityRole::Projectile { .. }
+                    | EntityRole::Flag { .. }
+                    | EntityRole::Pickup { .. } => None,
+                };
+                let swapped_weapon = Buff::swapped_weapon(&character.buffs);
+                if let Some(swapped_weapon) = swapped_weapon {
+                    character.player_weapon = swapped_weapon;
+                    character.role = EntityRole::Character {
+                        weapon: config
+                            .lock()
+                            .unwrap()
+                            .config
+                            .character_weapon(swapped_weapon),
+                    };
+                }
+                let health = config.lock().unwrap().config.health.clone();
+                let mut fired: Option<Duration> = None;
+
+                match character.role {
+                    EntityRole::Character { weapon } => match weapon {
+                        CharacterWeapon::BallGun {
+                            life_duration,
+                            owner_invincibility_duration,
+                            fire_interval,
+                            velocity,
+                            projectile_health,
+                            damage,
+                            radius,
+                            ..
+                        } => {
                             game_state.create(
                                 EntityCreateInfo {
-                                    pos,
-                                    rot: Complex { r: 1., i: 0. },
-                                    color: player_state.color.clone(),
-                                    role: EntityRole::Character { weapon },
+                                    pos: character.pos,
+                                    rot: character.rot,
+                                    color: character.color.clone(),
+                                    role: EntityRole::Projectile {
+                                        kind: ProjectileKind::Ball {
+                                            life_duration,
+                                            owner_invincibility_duration,
+                                            velocity,
+                                            health: projectile_health,
+                                            damage,
+                                            radius,
+                                        },
+                                    },
+                                    player_weapon: character.player_weapon,
                                     tail: None,
                                 },
                                 player_id,
+                                &health,
                             );
+                            fired = Some(fire_interval);
                         }
-                        ClientToServerPackage::RespawnRequest(_) => {
-                            panic!("First package must be init package")
-                        }
-                        ClientToServerPackage::PlayerInput(_) => {
-                            panic!("First package must be init package")
+                        CharacterWeapon::RayGun {
+                            life_duration,
+                            owner_invincibility_duration,
+                            tail_freeze_duration,
+                            fire_interval,
+                            velocity,
+                            projectile_health,
+                            damage,
+                            ..
+                        } => {
+                            game_state.create(
+                                EntityCreateInfo {
+                                    pos: character.pos,
+                                    rot: character.rot,
+                                    color: character.color.clone(),
+                                    role: EntityRole::Projectile {
+                                        kind: ProjectileKind::Ray {
+                                            life_duration,
+                                            owner_invincibility_duration,
+                                            tail_freeze_duration,
+                                            velocity,
+                                            health: projectile_health,
+                                            damage,
+                                        },
+                                    },
+                                    player_weapon: character.player_weapon,
+                                    tail: Some(EntityTail {
+                                        end: character.pos,
+                                        rotation: character.rot,
+                                        reflection_points: Default::default(),
+                                    }),
+                                },
+                                player_id,
+                                &health,
+                            );
+                            fired = Some(fire_interval);
                         }
-                    }
-                    break;
-                }
-                std::thread::sleep(Duration::from_millis(1));
-            }
-
-            let mut last_broadcust_instant = Instant::now();
-            let mut last_sequence_number = 0;
-            let mut last_projectile_instant = Instant::now();
-
-            loop {
-                let now = Instant::now();
-                for data in reader.read(&mut stream)? {
-                    let package: ClientToServerPackage = serde_json::from_slice(&data).unwrap();
-                    match package {
-                        ClientToServerPackage::PlayerConnected(_) => {
-                            panic!("Double init")
+                        CharacterWeapon::Shield { .. } => {}
+                        CharacterWeapon::MineGun {
+                            fire_interval,
+                            life_duration,
+                            owner_invincibility_duration,
+                            activation_duration,
+                            start_velocity,
+                            acceleration,
+                            radius,
+                            detection_radius,
+                            explosion_radius,
+                            explosion_damage,
+                            debris_kind,
+                            debris_count,
+                            ..
+                        } => {
+                            game_state.create(
+                                EntityCreateInfo {
+                                    pos: character.pos,
+                                    rot: character.rot,
+                                    color: character.color.clone(),
+                                    role: EntityRole::Projectile {
+                                        kind: ProjectileKind::Mine {
+                                            life_duration,
+                                            owner_invincibility_duration,
+                                            activation_duration,
+                                            velocity: start_velocity,
+                                            acceleration,
+                                            radius,
+                                            detection_radius,
+                                            explosion_radius,
+                                            explosion_damage,
+                                            debris_kind,
+                                            debris_count,
+                                        },
+                                    },
+                                    player_weapon: character.player_weapon,
+                                    tail: None,
+                                },
+                                player_id,
+                                &health,
+                            );
+                            fired = Some(fire_interval);
                         }
-                        ClientToServerPackage::PlayerInput(package) => {
-                            let game_state = game_state.lock().unwrap();
-                            if let Some(mut entity) =
-                                game_state.find_character_by_player_id_mut(player_id)
-                            {
-                                entity.pos.x = entity.pos.x + package.movement.x;
-                                entity.pos.y = entity.pos.y + package.movement.y;
-                                entity.rot = package.rotation;
-
-                                for bound in game_state.world_bounds().edges() {
-                                    if let Some(exit_vec) =
-                                        entity.vertices().segments_ringe().collide(&[bound])
-                                    {
-                                        entity.pos += exit_vec;
-                                    }
-                                }
-
-                                last_sequence_number = package.sequence_number;
-                            };
-
-                            if !left_mouse_pressed && package.left_mouse_pressed {
-                                left_mouse_pressed_instant = now;
-                            }
-
-                            left_mouse_pressed = package.left_mouse_pressed
+                        CharacterWeapon::MissileLauncher {
+                            fire_interval,
+                            life_duration,
+                            owner_invincibility_duration,
+                            start_velocity,
+                            acceleration,
+                            turn_rate,
+                            lock_on_angle,
+                            fuel_duration,
+                            projectile_health,
+                            damage,
+                            radius,
+                            ..
+                        } => {
+                            game_state.create(
+                                EntityCreateInfo {
+                                    pos: character.pos,
+                                    rot: character.rot,
+                                    color: character.color.clone(),
+                                    role: EntityRole::Projectile {
+                                        kind: ProjectileKind::Missile {
+                                            life_duration,
+                                            owner_invincibility_duration,
+                                            velocity: start_velocity,
+                                            acceleration,
+                                            turn_rate,
+                                            lock_on_angle,
+                                            fuel_duration,
+                                            health: projectile_health,
+                                            damage,
+                                            radius,
+                                        },
+                                    },
+                                    player_weapon: character.player_weapon,
+                                    tail: None,
+                                },
+                                player_id,
+                                &health,
+                            );
+                            fired = Some(fire_interval);
                         }
-                        ClientToServerPackage::RespawnRequest(package) => {
-                            if player_state.killed {
-                                player_state.killed = false;
-                                let mut game_state = game_state.lock().unwrap();
-
-                                weapon = character_weapon_from_player_weapon(package.weapon);
-
-                                let create_info = EntityCreateInfo {
-                                    pos: game_state.random_point_inside_bounds(&mut rng),
-                                    rot: Complex { r: 1., i: 0. },
-                                    color: player_state.color.clone(),
-                                    role: EntityRole::Character { weapon },
+                        CharacterWeapon::GrenadeLauncher {
+                            fire_interval,
+                            fuse_duration,
+                            start_velocity,
+                            deceleration,
+                            radius,
+                            explosion_radius,
+                            explosion_damage,
+                            ..
+                        } => {
+                            game_state.create(
+                                EntityCreateInfo {
+                                    pos: character.pos,
+                                    rot: character.rot,
+                                    color: character.color.clone(),
+                                    role: EntityRole::Projectile {
+                                        kind: ProjectileKind::Grenade {
+                                            fuse_duration,
+                                            velocity: start_velocity,
+                                            deceleration,
+                                            radius,
+                                            explosion_radius,
+                                            explosion_damage,
+                                        },
+                                    },
+                                    player_weapon: character.player_weapon,
                                     tail: None,
-                                };
+                                },
+                                player_id,
+                                &health,
+                            );
+                            fired = Some(fire_interval);
+                        }
+                    },
+                    EntityRole::Projectile { .. }
+                    | EntityRole::Flag { .. }
+                    | EntityRole::Pickup { .. } => {}
+                }
 
-                                game_state.create(create_info, player_id);
-                            }
+                if let Some(fire_interval) = fired {
+                    if let Some(mut character) =
+                        game_state.find_character_by_player_id_mut(player_id)
+                    {
+                        if swapped_weapon.is_some() {
+                            character
+                                .buffs
+                                .retain(|x| !matches!(x, Buff::WeaponSwap { .. }));
+                        } else {
+                            let fire_interval_scale = Buff::fire_interval_scale(&character.buffs);
+                            character
+                                .weapon_state
+                                .start_cooldown(fire_interval.mul_f32(fire_interval_scale));
+                            character.weapon_state.consume_round(magazine.as_ref());
                         }
                     }
                 }
+            }
+        }
 
-                if left_mouse_pressed
-                    && now - last_projectile_instant > Duration::from_millis(1000 / 10)
-                {
-                    let mut game_state = game_state.lock().unwrap();
-                    if let Some(character) = game_state
-                        .find_character_by_player_id_mut(player_id)
-                        .map(|x| x.clone())
+        if secondary_pressed
+            || secondary_released.is_some()
+            || buttons.contains(InputButtons::SECONDARY)
+        {
+            let mut game_state = game_state.lock().unwrap();
+            let game_config = config.lock().unwrap().config.clone();
+            if let Some(character) = game_state
+                .find_character_by_player_id_mut(player_id)
+                .map(|x| x.clone())
+                .filter(|x| game_state.match_state().combat_allowed() && game_state.alive(x))
+            {
+                let weapon = match &character.role {
+                    EntityRole::Character { weapon } => Some(weapon),
+                    EntityRole::Projectile { .. }
+                    | EntityRole::Flag { .. }
+                    | EntityRole::Pickup { .. } => None,
+                };
+                match (weapon.and_then(|x| x.secondary()), weapon) {
+                    (Some(SecondaryAction::SelfDestruct { hold_duration }), _)
+                        if buttons.contains(InputButtons::SECONDARY)
+                            && game_state.time() - secondary_pressed_time > *hold_duration =>
                     {
-                        match character.role {
-                            EntityRole::Character { weapon } => match weapon {
-                                CharacterWeapon::BallGun {
-                                    life_duration,
-                                    owner_invincibility_duration,
-                                    fire_interval,
-                                    velocity,
-                                    projectile_health,
-                                    radius,
-                                } => {
-                                    if now - last_projectile_instant > fire_interval {
-                                        game_state.create(
-                                            EntityCreateInfo {
-                                                pos: character.pos,
-                                                rot: character.rot,
-                                                color: character.color.clone(),
-                                                role: EntityRole::Projectile {
-                                                    kind: ProjectileKind::Ball {
-                                                        life_duration,
-                                                        owner_invincibility_duration,
-                                                        velocity,
-                                                        health: projectile_health,
-                                                        radius,
-                                                    },
-                                                },
-                                                tail: None,
-                                            },
-                                            player_id,
-                                        );
-                                        last_projectile_instant = now;
-                                    }
-                                }
-                                CharacterWeapon::RayGun {
-                                    life_duration,
-                                    owner_invincibility_duration,
-                                    tail_freeze_duration,
-                                    fire_interval,
-                                    velocity,
-                                    projectile_health,
-                                } => {
-                                    if now - last_projectile_instant > fire_interval {
-                                        game_state.create(
-                                            EntityCreateInfo {
-                                                pos: character.pos,
-                                                rot: character.rot,
-                                                color: character.color.clone(),
-                                                role: EntityRole::Projectile {
-                                                    kind: ProjectileKind::Ray {
-                                                        life_duration,
-                                                        owner_invincibility_duration,
-                                                        tail_freeze_duration,
-                                                        velocity,
-                                                        health: projectile_health,
-                                                    },
-                                                },
-                                                tail: Some(EntityTail {
-                                                    end: character.pos,
-                                                    rotation: character.rot,
-                                                    reflection_points: Default::default(),
-                                                }),
-                                            },
-                                            player_id,
-                                        );
-                                        last_projectile_instant = now;
-                                    }
-                                }
-                                CharacterWeapon::Shield {
-                                    self_destruct_timeout,
-                                    ..
-                                } => {
-                                    if now - left_mouse_pressed_instant > self_destruct_timeout {
-                                        game_state.register_kill(character.id);
-                                        let count = 32;
-                                        for i in 0..count {
-                                            game_state.create(
-                                                EntityCreateInfo {
-                                                    pos: character.pos,
-                                                    rot: Complex::from_rad(
-                                                        (i as f32 / count as f32) * 2. * PI,
-                                                    ),
-                                                    color: character.color.clone(),
-                                                    role: EntityRole::Projectile {
-                                                        kind: ProjectileKind::Ball {
-                                                            life_duration: Duration::from_secs(i),
-                                                            owner_invincibility_duration:
-                                                                Duration::from_secs(1000),
-                                                            velocity: 500.,
-                                                            health: 1,
-                                                            radius: 4.,
-                                                        },
-                                                    },
-                                                    tail: None,
-                                                },
-                                                player_id,
-                                            );
-                                        }
-                                    }
-                                }
-                                CharacterWeapon::MineGun {
-                                    fire_interval,
-                                    life_duration,
-                                    owner_invincibility_duration,
-                                    activation_duration,
-                                    start_velocity,
-                                    acceleration,
-                                    radius,
-                                    detection_radius,
-                                    explosion_radius,
-                                    debris_kind,
-                                    debris_count,
-                                } => {
-                                    if now - last_projectile_instant > fire_interval {
-                                        game_state.create(
-                                            EntityCreateInfo {
-                                                pos: character.pos,
-                                                rot: character.rot,
-                                                color: character.color.clone(),
-                                                role: EntityRole::Projectile {
-                                                    kind: ProjectileKind::Mine {
-                                                        life_duration,
-                                                        owner_invincibility_duration,
-                                                        activation_duration,
-                                                        velocity: start_velocity,
-                                                        acceleration,
-                                                        radius,
-                                                        detection_radius,
-                                                        explosion_radius,
-                                                        debris_kind,
-                                                        debris_count,
-                                                    },
-                                                },
-                                                tail: None,
-                                            },
-                                            player_id,
-                                        );
-                                        last_projectile_instant = now;
-                                    }
-                                }
+                        game_state.register_suicide(
+                            character.id,
+                            player_id,
+                            character.player_weapon,
+                        );
+                        let count = 32;
+                        for i in 0..count {
+                            game_state.create(
+                                EntityCreateInfo {
+                                    pos: character.pos,
+                                    rot: Complex::from_rad((i as f32 / count as f32) * 2. * PI),
+                                    color: character.color.clone(),
+                                    role: EntityRole::Projectile {
+                                        kind: ProjectileKind::Ball {
+                                            life_duration: Duration::from_secs(i),
+                                            owner_invincibility_duration: Duration::from_secs(1000),
+                                            velocity: 500.,
+                                            health: 1,
+                                            damage: 1.,
+                                            radius: 4.,
+                                        },
+                                    },
+                                    player_weapon: character.player_weapon,
+                                    tail: None,
+                                },
+                                player_id,
+                                &game_config.health,
+                            );
+                        }
+                    }
+                    (Some(SecondaryAction::DetonateMines), _) if secondary_pressed => {
+                        game_state.detonate_mines(player_id, &game_config);
+                    }
+                    (
+                        Some(SecondaryAction::ChargedShot {
+                            charge_duration,
+                            max_multiplier,
+                        }),
+                        Some(
+                            ray_gun @ CharacterWeapon::RayGun {
+                                life_duration,
+                                owner_invincibility_duration,
+                                tail_freeze_duration,
+                                fire_interval,
+                                velocity,
+                                projectile_health,
+                                damage,
+                                ..
                             },
-                            EntityRole::Projectile { .. } => panic!("Woops!"),
+                        ),
+                    ) => {
+                        if let Some(held) =
+                            secondary_released.filter(|_| character.weapon_state.ready())
+                        {
+                            let multiplier = SecondaryAction::charge_multiplier(
+                                *charge_duration,
+                                *max_multiplier,
+                                held,
+                            );
+                            game_state.create(
+                                EntityCreateInfo {
+                                    pos: character.pos,
+                                    rot: character.rot,
+                                    color: character.color.clone(),
+                                    role: EntityRole::Projectile {
+                                        kind: ProjectileKind::Ray {
+                                            life_duration: *life_duration,
+                                            owner_invincibility_duration:
+                                                *owner_invincibility_duration,
+                                            tail_freeze_duration: *tail_freeze_duration,
+                                            velocity: velocity * multiplier,
+                                            health: (*projectile_health as f32 * multiplier).round()
+                                                as u8,
+                                            damage: *damage,
+                                        },
+                                    },
+                                    player_weapon: character.player_weapon,
+                                    tail: Some(EntityTail {
+                                        end: character.pos,
+                                        rotation: character.rot,
+                                        reflection_points: Default::default(),
+                                    }),
+                                },
+                                player_id,
+                                &game_config.health,
+                            );
+                            if let Some(mut character) =
+                                game_state.find_character_by_player_id_mut(player_id)
+                            {
+                                let fire_interval_scale =
+                                    Buff::fire_interval_scale(&character.buffs);
+                                character
+                                    .weapon_state
+                                    .start_cooldown(fire_interval.mul_f32(fire_interval_scale));
+                                character.weapon_state.consume_round(ray_gun.magazine());
+                            }
                         }
                     }
+                    _ => {}
                 }
+            }
+        }
 
-                {
-                    let mut game_state = game_state.lock().unwrap();
-                    if game_state.account_kill(player_id) {
-                        drop(game_state);
-                        player_state.killed = true;
-                        left_mouse_pressed = false;
-                        write_package(&mut stream, ServerToClientPackage::Kill(KillPackage {}))?;
-                    }
-                }
+        {
+            let mut game_state = game_state.lock().unwrap();
+            if let Some(cause) = game_state.account_kill(player_id) {
+                drop(game_state);
+                player_state.killed = true;
+                buttons = Default::default();
+                write_package(
+                    &mut stream,
+                    ServerToClientPackage::Kill(KillPackage {
+                        killer: cause.killer,
+                        weapon: cause.weapon,
+                    }),
+                )?;
+            }
+        }
 
-                if now - last_broadcust_instant > Duration::from_millis(1000 / 30) {
-                    write_package(
-                        &mut stream,
-                        ServerToClientPackage::Broadcast(BroadcastPackage {
-                            game_state: game_state.lock().unwrap().clone(),
-                            sequence_number: last_sequence_number,
-                            player_state: player_state.clone(),
-                        }),
-                    )?;
-                    last_broadcust_instant = now;
-                }
+        {
+            let events: Vec<_> = {
+                let game_state = game_state.lock().unwrap();
+                let events = game_state.events().since(next_event_id).cloned().collect();
+                next_event_id = game_state.events().next_id();
+                events
+            };
+            for event in events {
+                write_package(
+                    &mut stream,
+                    ServerToClientPackage::Event(EventPackage { event }),
+                )?;
+            }
+        }
 
-                std::thread::sleep(Duration::from_millis(1));
+        if now - last_broadcust_instant > Duration::from_millis(1000 / 30) {
+            let game_state = game_state.lock().unwrap().clone();
+            if let Some(character) = game_state.find_character_by_player_id_mut(player_id) {
+                player_state.weapon_state = character.weapon_state;
             }
-        });
+            write_package(
+                &mut stream,
+                ServerToClientPackage::Broadcast(Box::new(BroadcastPackage {
+                    game_state,
+                    sequence_number: last_sequence_number,
+                    player_state: player_state.clone(),
+                })),
+            )?;
+            last_broadcust_instant = now;
+        }
+
+        std::thread::sleep(Duration::from_millis(1));
     }
+    Ok(())
 }
@@ -0,0 +1,251 @@
+mod harness;
+
+use fast_pased_mp_game::common::{
+    CharacterWeapon, Entity, EntityRole, GameConfig, HealthConfig, InputButtons, MatchPhase,
+    MovementInput, PacketWriter, PlayerWeapon, Point, ProjectileKind, SecondaryAction,
+};
+use harness::{TestClient, TestServer};
+use std::{io::Read, net::TcpStream, time::Duration};
+
+fn ball(client: &TestClient) -> Option<Entity> {
+    client
+        .game_state()
+        .entities()
+        .find(|e| {
+            matches!(
+                e.role,
+                EntityRole::Projectile {
+                    kind: ProjectileKind::Ball { .. }
+                }
+            )
+        })
+        .map(|e| e.clone())
+}
+
+/// Config in which single ball hit kills
+fn one_hit_config() -> GameConfig {
+    GameConfig {
+        health: HealthConfig {
+            max: 1.,
+            ..Default::default()
+        },
+        ..Default::default()
+    }
+}
+
+/// Starts round with two connected players and waits until their spawn protection expires
+fn start_round(
+    server: &mut TestServer,
+    config: &GameConfig,
+    a: &mut TestClient,
+    b: &mut TestClient,
+) {
+    // Second player starts countdown. Characters are respawned with protection when round starts
+    server.advance(config.match_rules.countdown_duration + Duration::from_secs(1));
+    server.advance(config.spawn_protection);
+    for client in [a, b] {
+        client.wait_until("round is started", |c| {
+            matches!(
+                c.game_state().match_state().phase(),
+                MatchPhase::Round { .. }
+            ) && c.character().is_some_and(|x| !x.protected)
+        });
+    }
+}
+
+/// Shoots `victim` with ball from `killer` and waits until kill is received
+fn ball_kill(server: &mut TestServer, killer: &mut TestClient, victim: &mut TestClient) {
+    let target = victim.character().unwrap().pos;
+    killer.fire_at(target);
+    assert!(ball(killer).is_some());
+    // Longest distance between characters is covered by ball in less than five seconds
+    server.advance(Duration::from_secs(5));
+
+    victim.wait_until("kill is received", |c| c.killed());
+}
+
+#[test]
+fn ball_kill_and_respawn() {
+    let config = one_hit_config();
+    let mut server = TestServer::start(&config);
+    let mut a = TestClient::connect(&server, "A");
+    let mut b = TestClient::connect(&server, "B");
+    let (a_id, b_id) = (a.player_id(), b.player_id());
+
+    start_round(&mut server, &config, &mut a, &mut b);
+    ball_kill(&mut server, &mut a, &mut b);
+    let kill = b.last_kill().unwrap();
+    assert_eq!(kill.killer, a_id);
+    assert_eq!(kill.weapon, PlayerWeapon::BallGun);
+    a.wait_until("kill is credited", |c| {
+        c.game_state()
+            .scoreboard()
+            .stats(a_id)
+            .is_some_and(|x| x.kills == 1)
+    });
+    assert_eq!(a.game_state().scoreboard().stats(b_id).unwrap().deaths, 1);
+    assert!(a.character().is_some());
+
+    b.respawn(vec![PlayerWeapon::MineGun]);
+    b.wait_until("character is respawned", |c| {
+        c.character()
+            .is_some_and(|x| x.player_weapon == PlayerWeapon::MineGun)
+    });
+    assert!(!b.killed());
+}
+
+#[test]
+fn respawn_loadout_and_weapon_switch() {
+    let config = one_hit_config();
+    let mut server = TestServer::start(&config);
+    let mut a = TestClient::connect(&server, "A");
+    let mut b = TestClient::connect(&server, "B");
+    b.wait_until("initial loadout is received", |c| {
+        c.loadout() == [PlayerWeapon::BallGun]
+    });
+
+    start_round(&mut server, &config, &mut a, &mut b);
+    ball_kill(&mut server, &mut a, &mut b);
+
+    let loadout = vec![PlayerWeapon::BallGun, PlayerWeapon::MineGun];
+    b.respawn(loadout.clone());
+    b.wait_until("loadout is received", |c| c.loadout() == loadout);
+    b.wait_until("character is respawned", |c| {
+        c.character()
+            .is_some_and(|x| x.player_weapon == PlayerWeapon::BallGun)
+    });
+
+    b.switch_weapon(1);
+    b.wait_until("weapon is switched", |c| {
+        c.character()
+            .is_some_and(|x| x.player_weapon == PlayerWeapon::MineGun)
+    });
+}
+
+#[test]
+fn projectiles_age_with_server_clock() {
+    let mut config: GameConfig = Default::default();
+    let CharacterWeapon::BallGun { life_duration, .. } = &mut config.weapons.ball_gun else {
+        panic!("Ball gun expected")
+    };
+    *life_duration = Duration::from_secs(1);
+    let mut server = TestServer::start(&config);
+    let mut a = TestClient::connect(&server, "A");
+
+    // Ball flying through the middle of default map does not reach its bounds in a second
+    a.fire_at(Point { x: 400., y: 316. });
+    let fired = ball(&a).unwrap();
+
+    server.advance(Duration::from_millis(900));
+    a.wait_until("ball moves", |c| ball(c).is_none_or(|x| x.pos != fired.pos));
+    assert_eq!(ball(&a).unwrap().id, fired.id);
+
+    server.advance(Duration::from_millis(200));
+    a.wait_until("ball expires", |c| ball(c).is_none());
+}
+
+#[test]
+fn movement_does_not_depend_on_input_rate() {
+    let config: GameConfig = Default::default();
+    let mut server = TestServer::start(&config);
+    let mut a = TestClient::connect(&server, "A");
+    let start = a.character().unwrap();
+    // Towards the middle of default map, so that bounds are not reached
+    let movement = MovementInput {
+        left: start.pos.x > 400.,
+        right: start.pos.x <= 400.,
+        ..Default::default()
+    };
+
+    a.send_input(movement, start.rot, Default::default());
+    server.advance(Duration::from_millis(500));
+    a.wait_until("character accelerates", |c| {
+        c.character().is_some_and(|x| x.pos != start.pos)
+    });
+    let accelerated = a.character().unwrap().pos;
+
+    // Repeated packets only replace held input
+    for _ in 0..10 {
+        a.send_input(movement, start.rot, Default::default());
+    }
+    server.advance(Duration::from_millis(500));
+    a.wait_until("character moves", |c| {
+        c.character().is_some_and(|x| x.pos != accelerated)
+    });
+    let distance = (a.character().unwrap().pos.x - accelerated.x).abs();
+    assert!(
+        (distance - config.movement.max_speed * 0.5).abs() < 1.,
+        "{}",
+        distance
+    );
+}
+
+#[test]
+fn weapon_state_is_sent_to_owner_only() {
+    let config: GameConfig = Default::default();
+    let magazine = *config.weapons.ball_gun.magazine().unwrap();
+    let mut server = TestServer::start(&config);
+    let mut a = TestClient::connect(&server, "A");
+    a.wait_until("weapon state is received", |c| {
+        c.weapon_state()
+            .ammo
+            .is_some_and(|x| x.loaded == magazine.size)
+    });
+
+    a.fire_at(Point { x: 400., y: 316. });
+    server.advance(Duration::from_millis(10));
+    a.wait_until("round is consumed", |c| {
+        c.weapon_state()
+            .ammo
+            .is_some_and(|x| x.loaded == magazine.size - 1)
+    });
+    assert!(!a.weapon_state().ready());
+    assert_eq!(a.character().unwrap().weapon_state, Default::default());
+}
+
+#[test]
+fn self_destruct_hold_uses_server_clock() {
+    let mut config: GameConfig = Default::default();
+    let CharacterWeapon::BallGun { secondary, .. } = &mut config.weapons.ball_gun else {
+        panic!("Ball gun expected")
+    };
+    // Longer than test timeout, so real time hold would never trigger
+    let hold_duration = Duration::from_secs(60);
+    *secondary = Some(SecondaryAction::SelfDestruct { hold_duration });
+    let mut server = TestServer::start(&config);
+    let mut a = TestClient::connect(&server, "A");
+
+    let rotation = a.character().unwrap().rot;
+    a.send_input(Default::default(), rotation, InputButtons::SECONDARY);
+    server.advance(hold_duration + Duration::from_secs(1));
+    a.wait_until("character self destructs", |c| c.killed());
+}
+
+#[test]
+fn stopped_server_refuses_connections() {
+    let server = TestServer::start(&Default::default());
+    let addr = server.addr();
+    let _client = TestClient::connect(&server, "A");
+    server.stop();
+    assert!(TcpStream::connect(addr).is_err());
+}
+
+#[test]
+fn malformed_package_disconnects_only_sender() {
+    let server = TestServer::start(&Default::default());
+    let mut stream = TcpStream::connect(server.addr()).unwrap();
+    PacketWriter::write(&mut stream, b"not a package").unwrap();
+    stream
+        .set_read_timeout(Some(Duration::from_secs(10)))
+        .unwrap();
+    // Init and config packages are sent before the malformed one is read
+    let mut received = vec![];
+    stream.read_to_end(&mut received).unwrap();
+    assert!(!received.is_empty());
+
+    let mut a = TestClient::connect(&server, "A");
+    a.wait_until("only own character remains", |c| {
+        c.game_state().entities().count() == 1
+    });
+    server.stop();
+}
@@ -0,0 +1,237 @@
+//! Server on an ephemeral port with manually advanced clock and headless clients connected to
+//! it. Game state changes only when test advances server time, network exchange happens in real
+//! time and is waited for with a timeout
+
+use fast_pased_mp_game::{
+    client::{GameStateQueue, Hud, Networker},
+    common::{
+        ClientToServerPackage, Color, Complex, Entity, EntityRole, GameConfig, GameState,
+        InputButtons, KillPackage, MovementInput, PlayerConnectedPackage, PlayerInputPackage,
+        PlayerState, PlayerWeapon, Point, RespawnRequestPackage, SwitchWeaponPackage, WeaponState,
+    },
+    server::{Server, ServerClock, ServerOptions},
+};
+use std::{
+    net::SocketAddrV4,
+    num::NonZero,
+    path::PathBuf,
+    sync::atomic::{AtomicUsize, Ordering},
+    thread,
+    time::{Duration, Instant},
+};
+
+/// Max real time waited for packages. Only reached when test fails
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+pub struct TestServer {
+    /// Taken when stopped explicitly
+    server: Option<Server>,
+    /// Written on start and deleted on drop
+    config_path: PathBuf,
+}
+
+impl TestServer {
+    pub fn start(config: &GameConfig) -> Self {
+        static NEXT_CONFIG_ID: AtomicUsize = AtomicUsize::new(0);
+        let config_path = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join(format!(
+            "config-{}-{}.json",
+            std::process::id(),
+            NEXT_CONFIG_ID.fetch_add(1, Ordering::Relaxed)
+        ));
+        std::fs::write(&config_path, serde_json::to_vec(config).unwrap()).unwrap();
+        let server = Server::start(ServerOptions {
+            port: 0,
+            config_path: Some(config_path.clone()),
+            map_path: None,
+            bots: 0,
+            clock: ServerClock::Manual,
+            seed: Some(0),
+        })
+        .unwrap();
+        Self {
+            server: Some(server),
+            config_path,
+        }
+    }
+
+    fn server(&mut self) -> &mut Server {
+        self.server.as_mut().unwrap()
+    }
+
+    pub fn addr(&self) -> SocketAddrV4 {
+        self.server.as_ref().unwrap().local_addr()
+    }
+
+    /// Proceeds game state by `dt` in server ticks
+    pub fn advance(&mut self, dt: Duration) {
+        self.server().advance(dt);
+    }
+
+    pub fn stop(mut self) {
+        self.server.take().unwrap().stop();
+    }
+}
+
+impl Drop for TestServer {
+    fn drop(&mut self) {
+        // Server watches config file, so it is stopped first
+        drop(self.server.take());
+        std::fs::remove_file(&self.config_path).unwrap();
+    }
+}
+
+pub struct TestClient {
+    networker: Networker,
+    game_state_queue: GameStateQueue,
+    player_state: PlayerState,
+    hud: Hud,
+    sequence_number: u32,
+}
+
+impl TestClient {
+    /// Joins server and waits until own character is received
+    pub fn connect(server: &TestServer, name: &str) -> Self {
+        let networker = Networker::connect(
+            server.addr(),
+            PlayerConnectedPackage {
+                name: name.to_string(),
+                color: Color {
+                    a: 255,
+                    r: 255,
+                    g: 255,
+                    b: 255,
+                },
+                team: None,
+            },
+        )
+        .unwrap();
+        let mut client = Self {
+            networker,
+            game_state_queue: GameStateQueue::new(),
+            player_state: Default::default(),
+            hud: Hud::new(),
+            sequence_number: 0,
+        };
+        client.wait_until("character is spawned", |c| c.character().is_some());
+        client
+    }
+
+    fn poll(&mut self) {
+        self.networker
+            .proceed(
+                &mut self.game_state_queue,
+                &mut self.player_state,
+                &mut self.hud,
+                self.sequence_number,
+            )
+            .unwrap();
+        if let Some(reason) = &self.networker.join_rejection {
+            panic!("Join rejected: {}", reason);
+        }
+    }
+
+    /// Receives packages until `condition` holds. Panics after timeout
+    pub fn wait_until(&mut self, what: &str, condition: impl Fn(&Self) -> bool) {
+        let start = Instant::now();
+        loop {
+            self.poll();
+            if condition(self) {
+                return;
+            }
+            assert!(
+                start.elapsed() < TIMEOUT,
+                "Timed out waiting until {}",
+                what
+            );
+            thread::sleep(Duration::from_millis(1));
+        }
+    }
+
+    pub fn player_id(&self) -> NonZero<u64> {
+        self.networker.player_id.unwrap()
+    }
+
+    /// Last game state broadcasted by server
+    pub fn game_state(&self) -> &GameState {
+        &self.game_state_queue.last_received
+    }
+
+    /// Own alive character
+    pub fn character(&self) -> Option<Entity> {
+        self.networker.player_id.and_then(|player_id| {
+            self.game_state()
+                .entities()
+                .find(|e| {
+                    e.player_id == player_id
+                        && matches!(e.role, EntityRole::Character { .. })
+                        && e.health > 0.
+                })
+                .map(|e| e.clone())
+        })
+    }
+
+    pub fn last_kill(&self) -> Option<&KillPackage> {
+        self.hud.event_feed.last_kill.as_ref()
+    }
+
+    pub fn killed(&self) -> bool {
+        self.player_state.killed
+    }
+
+    /// Own weapon state which server sends only to this client
+    pub fn weapon_state(&self) -> WeaponState {
+        self.player_state.weapon_state
+    }
+
+    /// Weapons chosen at last respawn as confirmed by server
+    pub fn loadout(&self) -> &[PlayerWeapon] {
+        &self.player_state.loadout
+    }
+
+    /// Sends input and waits until server applies it
+    pub fn send_input(
+        &mut self,
+        movement: MovementInput,
+        rotation: Complex,
+        buttons: InputButtons,
+    ) {
+        self.sequence_number += 1;
+        self.networker
+            .write_package(ClientToServerPackage::PlayerInput(PlayerInputPackage {
+                sequence_number: self.sequence_number,
+                movement,
+                rotation,
+                buttons,
+            }))
+            .unwrap();
+        let sequence_number = self.sequence_number;
+        self.wait_until("input is applied", |c| {
+            c.networker.stats.acknowledged_sequence_number >= sequence_number
+        });
+    }
+
+    /// Turns towards `target`, presses and releases primary button. One projectile is fired if
+    /// weapon is ready
+    pub fn fire_at(&mut self, target: Point) {
+        let rotation = (target - self.character().unwrap().pos).normalize_into_complex();
+        self.send_input(Default::default(), rotation, InputButtons::PRIMARY);
+        self.send_input(Default::default(), rotation, Default::default());
+    }
+
+    pub fn switch_weapon(&mut self, slot: usize) {
+        self.networker
+            .write_package(ClientToServerPackage::SwitchWeapon(SwitchWeaponPackage {
+                slot,
+            }))
+            .unwrap();
+    }
+
+    pub fn respawn(&mut self, loadout: Vec<PlayerWeapon>) {
+        self.networker
+            .write_package(ClientToServerPackage::RespawnRequest(
+                RespawnRequestPackage { loadout },
+            ))
+            .unwrap();
+        self.player_state.killed = false;
+    }
+}